@@ -0,0 +1,24 @@
+use pacsam_optimization::Config;
+use std::time::Instant;
+
+// times the whole pipeline, from reading the file to the finished route, on neighborhoods of a few different
+// sizes. run with `cargo bench`
+fn main() {
+    let fixtures = [
+        "neighborhood.csv",
+        "benches/fixtures/grid_10x10.csv",
+        "benches/fixtures/grid_25x25.csv",
+    ];
+    let iterations = 10;
+    for fixture in fixtures {
+        let file_path = format!("{}/{fixture}", env!("CARGO_MANIFEST_DIR"));
+        let start = Instant::now();
+        for _ in 0..iterations {
+            let mut config = Config::build(["pacsam_optimization".to_string()].into_iter())
+                .expect("there are no flags to parse");
+            config.file_path = file_path.clone();
+            pacsam_optimization::run(config).expect("fixture should route");
+        }
+        println!("{fixture}: {:?} per run", start.elapsed() / iterations);
+    }
+}
@@ -1,260 +1,12086 @@
-use graph_builder::prelude::*;
-use std::{error::Error, fs};
+use graph_builder::{input::EdgeList, prelude::*};
+use log::{debug, info, trace, LevelFilter};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::{
+    cell::OnceCell,
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque},
+    error::Error,
+    fmt, fs,
+    io::{self, BufRead, BufReader, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
-struct Edge {
-    vertices: (usize, usize),
-    length: usize,
+// random maps for tests/properties.rs, which can only reach the crate's public items. hidden from the docs because
+// it's test support rather than part of the library, and can change without notice
+#[doc(hidden)]
+pub mod testgen;
+
+// intersections are nodes and streets are undirected edges weighted by length. naming it here means code using
+// the library doesn't have to spell out graph_builder's type
+pub type RoadGraph = UndirectedALGraph<usize, (), usize>;
+// the same network with one-way streets kept one way. a two-way street is an arc in each direction
+pub type DirectedRoadGraph = DirectedALGraph<usize, (), usize>;
+
+pub enum Command {
+    // eulerize the graph and report the route
+    Route,
+    // report statistics about the network without routing it
+    Analyze,
+    // check the input without routing it
+    Validate,
+    // point out what in the input file looks like a mistake, line by line
+    Doctor,
+    // check a walk recorded in the field, in the file at the given path, against the map
+    Audit(String),
+    // compare two routes over the map, in the files at the given paths, like before and after closing a street
+    Diff(String, String),
+    // rework the route in the file at the given path by the pins and fixes read from stdin
+    Review(String),
+    // read the input and write it back out to the given path
+    Convert(String),
+    // route every map file in a directory, writing each route to the given directory or next to its map
+    SolveAll(Option<String>),
 }
 
-impl Clone for Edge {
-    fn clone(&self) -> Self {
-        Edge {
-            vertices: (self.vertices.0, self.vertices.1),
-            length: self.length,
-        }
-    }
+pub enum WeightFormat {
+    // whole feet
+    Feet,
+    // travel time written as H:MM:SS, stored as seconds
+    Duration,
 }
 
-// expecting most of the options in these functions because we know from the data input that they will
-// always return Some(_), so more error handling is unnecessary.
-pub fn run(file_path: String) -> Result<(), Box<dyn Error>> {
-    let contents = fs::read_to_string(file_path)?;
-    let graph = build_graph(contents);
-    fix_culdesacs(&graph);
-    eulerize(&graph);
-    // let path = find_cycle(&graph);
-    // println!("{}", alphabetize(&path));
-    // println!("{} miles", length_miles(&path, &graph));
-    Ok(())
+pub enum BoundaryPolicy {
+    // a node with one street is a real dead end. the walker goes in and comes back out the same street, so that
+    // street is doubled up front
+    TreatAsCuldesac,
+    // a node with one street is where the map was cut off, not a dead end. it's left with odd degree, so eulerize
+    // decides how to reach it instead of forcing a return trip down its own street
+    TreatAsEndpoint,
 }
 
-fn alphabetize(path: &Vec<usize>) -> String {
-    // nodes are numeric but the graph I create in Google earth uses letters for the nodes. this converts back
-    // for easier readability
-    let alphabet = [
-        "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R",
-        "S", "T", "U", "V", "W", "X", "Y", "Z",
-    ];
-    let mut alpha_path: String = "".to_string();
-    for (i, node) in path.iter().enumerate() {
-        alpha_path.push_str(alphabet[*node]);
-        if i < path.len() - 1 {
-            alpha_path.push_str(" -- ");
-        }
-    }
-    alpha_path
+#[derive(Clone, Copy)]
+pub enum MapService {
+    // directions links that open in the Google Maps app or website, several stops to a link
+    Google,
+    // links for Apple Maps, which only takes a start and a destination
+    Apple,
 }
 
-fn length_miles(path: &Vec<usize>, graph: &UndirectedALGraph<usize, (), usize>) -> f64 {
-    // the weights of each edge are expressed as feet. this finds each edge along the final path and sums them,
-    // then returns the value expressed in miles (truncated to two decimal places)
-    let mut ft = 0.0;
-    let mut count = 0;
-    while count < path.len() - 1 {
-        let v1 = path[count];
-        let v2 = path[count + 1];
-        let edge = graph
-            .neighbors_with_values(v1)
-            .find(|edge| edge.target == v2)
-            .expect("this exists");
-        ft += edge.value as f64;
-        count += 1;
-    }
-    let miles = ft / 5280.0;
-    let miles_two_decimals = f64::trunc(miles * 100.0) / 100.0;
-    miles_two_decimals
+#[derive(Clone, Copy, Default)]
+pub enum ObjectiveKind {
+    // the odd intersections are paired up for the least distance walked twice
+    #[default]
+    MinDistance,
+    // they're paired up for the fewest streets walked twice, with distance only breaking ties, for turfs where
+    // going back down a street bothers people more than the extra steps
+    MinRepeatedEdges,
 }
 
-fn fix_culdesacs(graph: &UndirectedALGraph<usize, (), usize>) {
-    // each node with degree 1 is a cul de sac / dead end, and the only way to include a cul de sac on an euler cycle is to
-    // go into it, then come back out. this function adds those returning edges to each cul de sac before running the rest
-    // of the algorithm.
-    let mut nodes_with_degree_one: Vec<usize> = vec![];
-    for i in 0..graph.node_count() {
-        if graph.degree(i) == 1 {
-            nodes_with_degree_one.push(i);
+#[derive(Clone, Copy)]
+pub enum RestartCriterion {
+    // the least turning, and above all the fewest times doubling straight back
+    Turns,
+    // every early street reached soonest
+    EarlyStreets,
+    // the fewest shifts, and the least difference between the longest and shortest of them
+    ShiftBalance,
+}
+
+pub enum RouteKind {
+    // a closed loop that ends where it starts
+    Circuit,
+    // a walk from start to end, for when the walker gets dropped off at one place and picked up at another
+    OpenPath { start: usize, end: usize },
+}
+
+pub enum OutputFormat {
+    // the summary line and the path as letters, for reading
+    Text,
+    // one json object with the summary and the path, for scripts
+    Json,
+}
+
+pub const USAGE: &str = "\
+usage: pacsam_optimization [COMMAND] [FILE] [OUTPUT] [OPTIONS]
+
+commands:
+  route, solve      find the route (the default)
+  analyze, stats    report statistics about the map and the deadheading a route adds
+  validate          check the map can be routed
+  doctor, lint      point out likely mistakes in the map file, like a street listed twice or a length with a digit
+                    too many, by line and column
+  audit FILE WALK   check a walk recorded in the field against the map: the streets it covered and missed, and
+                    how much further it went than the best route. WALK lists the intersections it went through in
+                    order, by name, index or letters, split by lines, commas or --
+  diff FILE BEFORE AFTER
+                    compare two routes over the map, like before and after closing a street: how much longer
+                    the second is, the streets only one of them walks, and the stretches where the order
+                    changed. each route is read like a WALK, and a route OUTPUT file reads as it is
+  review FILE ROUTE rework a route a line at a time from stdin, typed or from a script, keeping it a walk of the
+                    same streets: `pin A B C` walks A -- B -- C in one go, `fix I J` keeps stops I to J together
+                    as they are, `unpin N` drops the Nth pin or fix, `show` prints the route, `write OUT` saves
+                    it like a route OUTPUT, and `done` ends. the rest of the route is put back around the pins
+                    as close to its old order as it fits
+  convert           write the map back out to OUTPUT, as a Graphviz graph if it ends in .dot
+  solve-all DIR [OUT]
+                    route every map in DIR, writing NAME.route.txt for each to OUT (or DIR) and a table of their
+                    mileage to stdout
+
+options:
+  --start N            start the route at node N
+  --best-start N       start the circuit where it passes closest to node N
+  --priority-street A B
+                       start the circuit by walking the street from A to B
+  --units mi|km        units for reported lengths
+  --input-units ft|m   what the weights in the file measure, instead of the file's #units line or feet
+  --format text|json   how route prints its result
+  --durations          weights are travel times as H:MM:SS
+  --no-culdesac        treat dead ends as the edge of the map
+  --seed N             break ties between equally good routes at random, the same way every time for the same N,
+                       to try other routes of the same length. without it ties always go the same way
+  --restarts N         try N circuits over the same streets and keep the one that does best by --restart-by
+  --restart-by turns|early|shifts
+                       what --restarts keeps the best circuit by: the least turning, the early streets soonest, or
+                       the most even shifts. by default early with --early-street, shifts with --shift-length or
+                       --shift-minutes, and turns otherwise
+  --objective distance|repeats
+                       pair up dead ends and odd corners for the least distance walked twice (the default) or the
+                       fewest streets walked twice
+  --open START END     end the route somewhere other than where it starts
+  --per-component      route each piece of a disconnected map separately
+  --walkers K          share the route among K walkers
+  --both-sides         walk every street twice, once down each side, for literature drops
+  --depot N            where the walkers start and finish. off the turf, the route starts there too and walks out
+                       to the turf and back, along the connectors
+  --connector A B LENGTH
+                       a street from A to B that can be walked but doesn't need covering, like a
+                       #connector A B LENGTH line, for reaching a depot off the turf. an end the map doesn't have
+                       becomes a new intersection by that name
+  --avoid-node N       leave node N and its streets out of the map, like a #avoid-node N line
+  --avoid-street A B   leave the street from A to B out of the map, like a #avoid-street A B line
+  --early-street A B   get the street from A to B walked early in the route, like a ! on the end of it
+  --early-fraction F   how far into the route the early streets should all be reached, 0.5 (halfway) by default
+  --shift-length D     cut the route into consecutive shifts of at most D (in --units)
+  --shift-minutes M    cut the route into consecutive shifts of at most M minutes (see --speed)
+  --turn-by-turn       print directions after the route
+  --mark-repeats       print the route a street at a time, with the streets it walks again marked (repeat)
+  --heatmap            print how many times the route passes each intersection and walks each street, flagging
+                       the corners it keeps coming back to, and add the counts to the --geojson and --kml
+  --speed S            estimate the route's time at S, like 3mph or 5kph (3mph if only --door-minutes is given)
+  --door-minutes M     minutes spent at each door, counted from #N door counts on the streets
+  --effort tobler|grade:P
+                       weigh streets by the effort of walking them, from node elevations (see --coords). tobler
+                       uses Tobler's hiking function, grade:P adds P% to a street's length for every 1% it climbs
+  --coords FILE        node coordinates for --gpx, --kml and the turns in --turn-by-turn, with an optional
+                       elevation for --effort
+  --gpx OUT, --kml OUT write the route for a gps or Google Earth
+  --kml-stops          add a numbered pin for every visit to the kml
+  --map-links google|apple
+                       print walking directions links to follow the route on a phone, a few stops each, located
+                       like --geojson
+  --geojson OUT        write the route as GeoJSON, one numbered street per feature, located by --coords or
+                       by the map itself when it's GeoJSON
+  --svg OUT            draw the map and the route over it as a printable svg
+  --dot OUT            write the route as a Graphviz graph, numbered in walking order
+  --dump-eulerized P   write the eulerized map to P
+  --max-traversal N    fail if a street would be walked more than N times
+  --warnings-json      print input warnings as json
+  -v, -vv              log what the solve is doing to stderr: sizes, matching costs and timings, more with -vv
+  --allow-sparse-indices
+                       don't reject node indices far past the end of the file
+  --config FILE        read default options from FILE instead of pacsam.toml in the current directory
+  --no-config          ignore pacsam.toml
+  -h, --help           show this message
+
+settings file:
+  pacsam.toml sets options for every run, each one written like its option without the dashes. options given on
+  the command line win over it, and lists like --avoid-node get both. a switch it turns on is turned off again
+  with its --no- form, like --no-both-sides, and --culdesac undoes no_culdesac. for example
+    units = \"km\"
+    speed = \"3mph\"
+    start = \"A\"
+    walkers = 2
+    both_sides = true
+    avoid_node = [\"Q\", 17]
+    avoid_street = [[\"A\", \"B\"], [\"C\", \"D\"]]
+";
+
+pub struct Config {
+    pub command: Command,
+    pub file_path: String,
+    pub weight_format: WeightFormat,
+    pub boundary_policy: BoundaryPolicy,
+    // what the streets added to make the route a circuit are chosen to keep down
+    pub objective: ObjectiveKind,
+    // shuffles which of several equally good pairings and streets get picked, reproducibly
+    pub seed: Option<u64>,
+    // how many circuits to try, and what to keep the best by when it's more than one
+    pub restarts: usize,
+    pub restart_by: Option<RestartCriterion>,
+    pub dump_eulerized: Option<String>,
+    pub max_traversal: Option<usize>,
+    pub warnings_json: bool,
+    // how much the solve logs to stderr: 0 nothing, 1 each step's sizes and timings, 2 more detail, 3 everything
+    pub verbosity: usize,
+    pub allow_sparse_indices: bool,
+    pub turn_by_turn: bool,
+    // where route writes the path and mileage instead of stdout
+    pub output_path: Option<String>,
+    // the two ends of an open route, by anything resolve_node takes, like start. none for a circuit
+    pub open: Option<(String, String)>,
+    // how many canvassers share the route, each leaving from and returning to depot
+    pub walkers: Option<usize>,
+    pub depot: Option<String>,
+    // streets out to a depot off the turf, on top of the file's own #connector lines
+    pub connectors: Vec<Connector>,
+    // cut the one route into consecutive shifts no longer than this, each walker picking up where the last stopped
+    pub shift_limit: Option<ShiftLimit>,
+    // a file of node coordinates (see parse_coordinates), and where route writes the route as gpx using them
+    pub coords_path: Option<String>,
+    pub gpx_path: Option<String>,
+    // where route writes the route as kml for Google Earth, and whether each visit gets its own numbered pin
+    pub kml_path: Option<String>,
+    pub kml_stops: bool,
+    // where route writes the route as a GeoJSON FeatureCollection
+    pub geojson_path: Option<String>,
+    // print the route as directions links for this map app
+    pub map_links: Option<MapService>,
+    // the route as a graphviz graph, for rendering with dot or neato
+    pub dot_path: Option<String>,
+    // the map and route drawn as an svg, needs coords_path
+    pub svg_path: Option<String>,
+    // the node the route starts from, instead of the first one with a street. nodes given on the command line can
+    // be an index, letters like AB, or a name from the input (see resolve_node)
+    pub start: Option<String>,
+    // turn the circuit so it starts at the point nearest this node, e.g. where the car is parked
+    pub best_start: Option<String>,
+    // turn the circuit so it starts by walking this street
+    pub priority_street: Option<(String, String)>,
+    pub units: Distance,
+    // what the weights in the file measure, when --input-units says
+    pub input_unit: Option<Unit>,
+    // nodes and streets to take out of the map before routing, on top of the file's own #avoid lines
+    pub avoid: Avoid,
+    // streets to walk as early in the route as possible, on top of the ones marked with a ! in the file, and the
+    // fraction of the route they should all be reached within
+    pub early_streets: Vec<(String, String)>,
+    pub early_fraction: f64,
+    // walking speed and time per door, for estimating how long the route takes. None leaves the estimate out
+    pub pace: Option<Pace>,
+    // how a street's grade makes it harder to walk. None leaves elevations out of it
+    pub effort: Option<Effort>,
+    pub output_format: OutputFormat,
+    // route a disconnected map one piece at a time instead of refusing it
+    pub per_component: bool,
+    // walk every street once down each side, the way literature drops are done
+    pub both_sides: bool,
+    // print the route one street per line, saying which ones are walked again
+    pub mark_repeats: bool,
+    // count the route's passes through each intersection and along each street (see route_visits)
+    pub heatmap: bool,
+}
+
+impl Config {
+    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
+        // see USAGE. a bare file path means route, and if no file path is given at all it's left empty for main to
+        // ask for interactively
+        args.next();
+        let mut command_name: Option<String> = None;
+        let mut positional: Vec<String> = vec![];
+        let mut weight_format = WeightFormat::Feet;
+        let mut boundary_policy = BoundaryPolicy::TreatAsCuldesac;
+        let mut objective = ObjectiveKind::MinDistance;
+        let mut seed = None;
+        let mut restarts = 1;
+        let mut restart_by = None;
+        let mut dump_eulerized = None;
+        let mut max_traversal = None;
+        let mut warnings_json = false;
+        let mut verbosity = 0;
+        let mut allow_sparse_indices = false;
+        let mut turn_by_turn = false;
+        let mut open = None;
+        let mut walkers = None;
+        let mut shift_limit = None;
+        let mut avoid = Avoid::default();
+        let mut early_streets = vec![];
+        let mut early_fraction = 0.5;
+        let mut depot = None;
+        let mut connectors = vec![];
+        let mut coords_path = None;
+        let mut gpx_path = None;
+        let mut kml_path = None;
+        let mut kml_stops = false;
+        let mut geojson_path = None;
+        let mut map_links = None;
+        let mut dot_path = None;
+        let mut svg_path = None;
+        let mut start = None;
+        let mut best_start = None;
+        let mut priority_street = None;
+        let mut units = Distance::Miles;
+        let mut input_unit = None;
+        let mut speed = None;
+        let mut door_minutes = None;
+        let mut effort = None;
+        let mut output_format = OutputFormat::Text;
+        let mut per_component = false;
+        let mut both_sides = false;
+        let mut mark_repeats = false;
+        let mut heatmap = false;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                // every switch has an opposite, so one a settings file turns on can be turned off for a run
+                "--durations" => weight_format = WeightFormat::Duration,
+                "--no-durations" => weight_format = WeightFormat::Feet,
+                "--no-culdesac" => boundary_policy = BoundaryPolicy::TreatAsEndpoint,
+                "--culdesac" => boundary_policy = BoundaryPolicy::TreatAsCuldesac,
+                "--seed" => {
+                    let n = args.next().ok_or("--seed needs a number")?;
+                    seed = Some(
+                        n.parse::<u64>()
+                            .map_err(|_| format!("--seed: '{n}' is not a whole number"))?,
+                    );
+                }
+                "--restarts" => {
+                    let n = args.next().ok_or("--restarts needs a number")?;
+                    restarts = n
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or(format!("--restarts: '{n}' is not a number of circuits"))?;
+                }
+                "--restart-by" => {
+                    restart_by = Some(match args.next().as_deref() {
+                        Some("turns") => RestartCriterion::Turns,
+                        Some("early") => RestartCriterion::EarlyStreets,
+                        Some("shifts") => RestartCriterion::ShiftBalance,
+                        _ => return Err("--restart-by needs turns, early or shifts".to_string()),
+                    });
+                }
+                "--objective" => {
+                    objective = match args.next().as_deref() {
+                        Some("distance") => ObjectiveKind::MinDistance,
+                        Some("repeats") => ObjectiveKind::MinRepeatedEdges,
+                        _ => return Err("--objective needs distance or repeats".to_string()),
+                    };
+                }
+                "--warnings-json" => warnings_json = true,
+                "--no-warnings-json" => warnings_json = false,
+                "-v" | "--verbose" => verbosity += 1,
+                "-vv" => verbosity += 2,
+                "-vvv" => verbosity += 3,
+                "--no-verbose" => verbosity = 0,
+                "--allow-sparse-indices" => allow_sparse_indices = true,
+                "--no-allow-sparse-indices" => allow_sparse_indices = false,
+                "--turn-by-turn" => turn_by_turn = true,
+                "--no-turn-by-turn" => turn_by_turn = false,
+                "--both-sides" => both_sides = true,
+                "--no-both-sides" => both_sides = false,
+                "--mark-repeats" => mark_repeats = true,
+                "--no-mark-repeats" => mark_repeats = false,
+                "--heatmap" => heatmap = true,
+                "--no-heatmap" => heatmap = false,
+                "--dump-eulerized" => {
+                    dump_eulerized = Some(args.next().ok_or("--dump-eulerized needs a path")?);
+                }
+                "--max-traversal" => {
+                    let limit = args.next().ok_or("--max-traversal needs a number")?;
+                    max_traversal = Some(
+                        limit
+                            .parse::<usize>()
+                            .map_err(|_| format!("--max-traversal: '{limit}' is not a number"))?,
+                    );
+                }
+                "--open" => {
+                    let mut node = || args.next().ok_or("--open needs a start and an end node");
+                    open = Some((node()?, node()?));
+                }
+                "--coords" => coords_path = Some(args.next().ok_or("--coords needs a path")?),
+                "--gpx" => gpx_path = Some(args.next().ok_or("--gpx needs a path")?),
+                "--kml" => kml_path = Some(args.next().ok_or("--kml needs a path")?),
+                "--kml-stops" => kml_stops = true,
+                "--no-kml-stops" => kml_stops = false,
+                "--geojson" => geojson_path = Some(args.next().ok_or("--geojson needs a path")?),
+                "--dot" => dot_path = Some(args.next().ok_or("--dot needs a path")?),
+                "--svg" => svg_path = Some(args.next().ok_or("--svg needs a path")?),
+                "--per-component" => per_component = true,
+                "--no-per-component" => per_component = false,
+                "--start" => start = Some(args.next().ok_or("--start needs a node")?),
+                "--best-start" => {
+                    best_start = Some(args.next().ok_or("--best-start needs a node")?);
+                }
+                "--priority-street" => {
+                    let mut node = || args.next().ok_or("--priority-street needs two nodes");
+                    priority_street = Some((node()?, node()?));
+                }
+                "--units" => {
+                    units = match args.next().as_deref() {
+                        Some("mi") => Distance::Miles,
+                        Some("km") => Distance::Kilometers,
+                        _ => return Err("--units needs mi or km".to_string()),
+                    };
+                }
+                "--speed" => {
+                    let value = args
+                        .next()
+                        .ok_or("--speed needs a walking speed like 3mph or 5kph")?;
+                    let (number, factor) = match value.strip_suffix("kph") {
+                        Some(number) => (number, 0.621371),
+                        None => (value.strip_suffix("mph").unwrap_or(&value), 1.0),
+                    };
+                    match number.trim().parse::<f64>() {
+                        Ok(number) if number > 0.0 => speed = Some(number * factor),
+                        _ => {
+                            return Err(format!(
+                                "--speed: '{value}' is not a speed like 3mph or 5kph"
+                            ))
+                        }
+                    }
+                }
+                "--effort" => {
+                    let value = args.next().ok_or("--effort needs tobler or grade:P")?;
+                    effort = match value.split_once(':') {
+                        None if value == "tobler" => Some(Effort::Tobler),
+                        Some(("grade", percent)) => match percent.trim().parse::<f64>() {
+                            Ok(percent) if percent >= 0.0 => Some(Effort::Grade(percent)),
+                            _ => return Err(format!("--effort: '{percent}' is not a percentage")),
+                        },
+                        _ => return Err(format!("--effort: '{value}' isn't tobler or grade:P")),
+                    };
+                }
+                "--door-minutes" => {
+                    let value = args
+                        .next()
+                        .ok_or("--door-minutes needs a number of minutes")?;
+                    match value.parse::<f64>() {
+                        Ok(minutes) if minutes >= 0.0 => door_minutes = Some(minutes),
+                        _ => {
+                            return Err(format!(
+                                "--door-minutes: '{value}' is not a number of minutes"
+                            ))
+                        }
+                    }
+                }
+                "--input-units" => {
+                    input_unit = match args.next().as_deref() {
+                        Some("ft") => Some(Unit::Feet),
+                        Some("m") => Some(Unit::Meters),
+                        _ => return Err("--input-units needs ft or m".to_string()),
+                    };
+                }
+                "--map-links" => {
+                    map_links = match args.next().as_deref() {
+                        Some("google") => Some(MapService::Google),
+                        Some("apple") => Some(MapService::Apple),
+                        _ => return Err("--map-links needs google or apple".to_string()),
+                    };
+                }
+                "--format" => {
+                    output_format = match args.next().as_deref() {
+                        Some("text") => OutputFormat::Text,
+                        Some("json") => OutputFormat::Json,
+                        _ => return Err("--format needs text or json".to_string()),
+                    };
+                }
+                "--avoid-node" => avoid
+                    .nodes
+                    .push(args.next().ok_or("--avoid-node needs a node")?),
+                "--avoid-street" => {
+                    let mut node = || args.next().ok_or("--avoid-street needs two nodes");
+                    avoid.streets.push((node()?, node()?));
+                }
+                "--early-street" => {
+                    let mut node = || args.next().ok_or("--early-street needs two nodes");
+                    early_streets.push((node()?, node()?));
+                }
+                "--early-fraction" => {
+                    let value = args.next().ok_or("--early-fraction needs a number")?;
+                    early_fraction = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|fraction| *fraction > 0.0 && *fraction <= 1.0)
+                        .ok_or(format!(
+                            "--early-fraction: '{value}' is not between 0 and 1"
+                        ))?;
+                }
+                "--shift-length" | "--shift-minutes" => {
+                    let value = args.next().ok_or(format!("{arg} needs a number"))?;
+                    let limit = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|limit| *limit > 0.0)
+                        .ok_or(format!("{arg}: '{value}' is not a positive number"))?;
+                    shift_limit = Some(match arg.as_str() {
+                        "--shift-length" => ShiftLimit::Distance(limit),
+                        _ => ShiftLimit::Minutes(limit),
+                    });
+                }
+                "--walkers" => {
+                    let count = args.next().ok_or("--walkers needs a number")?;
+                    walkers = Some(
+                        count
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|count| *count > 0)
+                            .ok_or(format!("--walkers: '{count}' is not a number of walkers"))?,
+                    );
+                }
+                "--depot" => depot = Some(args.next().ok_or("--depot needs a node")?),
+                "--connector" => {
+                    let mut value = || {
+                        args.next()
+                            .ok_or("--connector needs two nodes and a length")
+                    };
+                    let (from, to, length) = (value()?, value()?, value()?);
+                    connectors.push(Connector {
+                        from,
+                        to,
+                        length: parse_weight(&length)
+                            .ok_or(format!("--connector: '{length}' is not a length"))?,
+                    });
+                }
+                flag if flag.starts_with("--") => {
+                    return Err(format!("unrecognized argument '{arg}'"))
+                }
+                "route" | "solve" | "analyze" | "stats" | "validate" | "doctor" | "lint"
+                | "audit" | "diff" | "review" | "convert" | "solve-all"
+                    if command_name.is_none() && positional.is_empty() =>
+                {
+                    command_name = Some(arg);
+                }
+                _ => positional.push(arg),
+            }
+        }
+        let mut positional = positional.into_iter();
+        // trimmed the same way as a path typed at the prompt, so a stray space or CR from a script doesn't matter
+        let file_path = positional
+            .next()
+            .map(|path| path.trim().to_string())
+            .unwrap_or_default();
+        let mut output_path = None;
+        let command = match command_name.as_deref() {
+            None | Some("route" | "solve") => {
+                output_path = positional.next();
+                Command::Route
+            }
+            Some("analyze" | "stats") => Command::Analyze,
+            Some("validate") => Command::Validate,
+            Some("doctor" | "lint") => Command::Doctor,
+            Some("audit") => {
+                Command::Audit(positional.next().ok_or("audit needs a map and a walk")?)
+            }
+            Some("diff") => {
+                let mut route = || positional.next().ok_or("diff needs a map and two routes");
+                Command::Diff(route()?, route()?)
+            }
+            Some("review") => {
+                Command::Review(positional.next().ok_or("review needs a map and a route")?)
+            }
+            Some("solve-all") => Command::SolveAll(positional.next()),
+            _ => Command::Convert(
+                positional
+                    .next()
+                    .ok_or("convert needs an input and an output path")?,
+            ),
+        };
+        if let Some(extra) = positional.next() {
+            return Err(format!("unexpected argument '{extra}'"));
+        }
+        if gpx_path.is_some() && coords_path.is_none() {
+            return Err("--gpx needs node coordinates from --coords".to_string());
+        }
+        if svg_path.is_some() && coords_path.is_none() {
+            return Err("--svg needs node coordinates from --coords".to_string());
         }
+        if kml_path.is_some() && coords_path.is_none() {
+            return Err("--kml needs node coordinates from --coords".to_string());
+        }
+        if (best_start.is_some() || priority_street.is_some()) && open.is_some() {
+            return Err(
+                "an open route already has its start, so --best-start and --priority-street \
+                        can't be combined with --open"
+                    .to_string(),
+            );
+        }
+        // the walkers' routes are cut from a circuit, which an open route doesn't have
+        if walkers.is_some() && open.is_some() {
+            return Err("--walkers can't be combined with --open".to_string());
+        }
+        // an open route's walk is settled by its two ends, so there's nothing for the restarts to try
+        if restarts > 1 && open.is_some() {
+            return Err("--restarts can't be combined with --open".to_string());
+        }
+        // each piece gets a circuit of its own, so there's no one place for the route to start or end and no single
+        // route to write out
+        if per_component {
+            let single = [
+                ("--start", start.is_some()),
+                ("--best-start", best_start.is_some()),
+                ("--priority-street", priority_street.is_some()),
+                ("--open", open.is_some()),
+                ("an OUTPUT path", output_path.is_some()),
+            ];
+            if let Some((option, _)) = single.iter().find(|(_, used)| *used) {
+                return Err(format!("{option} can't be combined with --per-component"));
+            }
+        }
+        if matches!(restart_by, Some(RestartCriterion::ShiftBalance)) && shift_limit.is_none() {
+            return Err("--restart-by shifts needs --shift-length or --shift-minutes".to_string());
+        }
+        Ok(Config {
+            command,
+            file_path,
+            weight_format,
+            boundary_policy,
+            objective,
+            seed,
+            restarts,
+            restart_by,
+            dump_eulerized,
+            max_traversal,
+            warnings_json,
+            verbosity,
+            allow_sparse_indices,
+            turn_by_turn,
+            output_path,
+            open,
+            walkers,
+            shift_limit,
+            avoid,
+            early_streets,
+            early_fraction,
+            depot,
+            connectors,
+            coords_path,
+            gpx_path,
+            kml_path,
+            kml_stops,
+            geojson_path,
+            map_links,
+            dot_path,
+            svg_path,
+            start,
+            best_start,
+            priority_street,
+            units,
+            input_unit,
+            // an average walker does about 3 mph
+            pace: (speed.is_some() || door_minutes.is_some()).then(|| Pace {
+                mph: speed.unwrap_or(3.0),
+                minutes_per_door: door_minutes.unwrap_or(0.0),
+            }),
+            effort,
+            output_format,
+            per_component,
+            both_sides,
+            mark_repeats,
+            heatmap,
+        })
     }
-    if nodes_with_degree_one.len() > 0 {
-        for node in nodes_with_degree_one {
-            let neighbor = graph
-                .neighbors_with_values(node)
-                .next()
-                .expect("there will always be a neighbor");
-            let _ = graph.add_edge_with_value(node, neighbor.target, neighbor.value);
+}
+
+pub const SETTINGS_FILE: &str = "pacsam.toml";
+
+pub fn with_settings(args: Vec<String>) -> Result<Vec<String>, String> {
+    // args with the options from a settings file put in right after the program name, ready for Config::build.
+    // anything on the command line comes after them, so it wins where an option only takes one value and adds to
+    // them where it builds a list. --config names the file and has to be readable, --no-config leaves it out, and
+    // otherwise it's SETTINGS_FILE in the current directory if there is one
+    let mut args = args.into_iter();
+    let program = args.next();
+    let mut path = None;
+    let mut skip = false;
+    let mut rest = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => path = Some(args.next().ok_or("--config needs a path")?),
+            "--no-config" => skip = true,
+            _ => rest.push(arg),
         }
     }
+    let settings = match (path, skip) {
+        (_, true) => vec![],
+        (Some(path), false) => {
+            let contents = fs::read_to_string(&path)
+                .map_err(|err| format!("--config: couldn't read {path}: {err}"))?;
+            settings_args(&contents).map_err(|err| format!("{path}: {err}"))?
+        }
+        (None, false) => match fs::read_to_string(SETTINGS_FILE) {
+            Ok(contents) => {
+                settings_args(&contents).map_err(|err| format!("{SETTINGS_FILE}: {err}"))?
+            }
+            Err(_) => vec![],
+        },
+    };
+    Ok(program.into_iter().chain(settings).chain(rest).collect())
 }
 
-fn eulerize(graph: &UndirectedALGraph<usize, (), usize>) {
-    // the neighborhoods will not usually have an euler cycle immediately.
-    // we use the following method to create one by duplicating edges until there are no odd-degree nodes
-    let mut nodes_with_odd_degree: Vec<usize> = vec![];
-    for i in 0..graph.node_count() {
-        if graph.degree(i) % 2 != 0 {
-            nodes_with_odd_degree.push(i);
+enum Setting {
+    Text(String),
+    Switch(bool),
+    List(Vec<Setting>),
+}
+
+pub fn settings_args(contents: &str) -> Result<Vec<String>, String> {
+    // a settings file as the options it stands for. it's the part of toml a settings file needs: `key = value`
+    // lines at the top level with # comments, the value a string, number, true or false, or a list of them, which
+    // can run over several lines. each key is an option without its dashes, in snake_case or kebab-case, so
+    // `avoid_node = "Q"` is --avoid-node Q. true is a bare switch like --both-sides and false leaves it off. a
+    // list repeats the option once for each item, and a list inside it gives one of those all its values, like
+    // `avoid_street = [["A", "B"]]`
+    let mut reader = SettingsReader {
+        chars: contents.chars().collect(),
+        at: 0,
+    };
+    let mut args = vec![];
+    loop {
+        reader.skip_blank(true);
+        let Some(first) = reader.peek() else {
+            break;
+        };
+        if first == '[' {
+            return Err(
+                reader.error("sections aren't supported, every setting goes at the top level")
+            );
+        }
+        let key = reader.key()?;
+        if matches!(key.as_str(), "config" | "no_config" | "no-config" | "help") {
+            return Err(reader.error(&format!("{key} can't be set from a settings file")));
+        }
+        let option = format!("--{}", key.replace('_', "-"));
+        reader.skip_blank(false);
+        if reader.next() != Some('=') {
+            return Err(reader.error(&format!("expected = after {key}")));
+        }
+        match reader.value()? {
+            Setting::Switch(true) => args.push(option),
+            Setting::Switch(false) => {}
+            Setting::Text(value) => args.extend([option, value]),
+            Setting::List(items) => {
+                for item in items {
+                    args.push(option.clone());
+                    match item {
+                        Setting::Text(value) => args.push(value),
+                        Setting::List(values) => {
+                            for value in values {
+                                match value {
+                                    Setting::Text(value) => args.push(value),
+                                    _ => {
+                                        return Err(reader.error(&format!(
+                                            "{key} can only list strings and numbers"
+                                        )))
+                                    }
+                                }
+                            }
+                        }
+                        Setting::Switch(_) => {
+                            return Err(reader.error(&format!("{key} can't list true or false")))
+                        }
+                    }
+                }
+            }
+        }
+        reader.skip_blank(false);
+        if !matches!(reader.peek(), None | Some('\n' | '\r')) {
+            return Err(reader.error(&format!("expected the end of the line after {key}")));
         }
     }
-    if nodes_with_odd_degree.len() == 0 {
-        return;
+    Ok(args)
+}
+
+struct SettingsReader {
+    chars: Vec<char>,
+    at: usize,
+}
+
+impl SettingsReader {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
     }
-    // construct a complete graph where the nodes are the set of odd degree nodes from the original, and their
-    // connected edges are the shortest path between them
-    let mut shortest_path_trees: Vec<Vec<Vertex>> = vec![];
-    for vertex in &nodes_with_odd_degree {
-        shortest_path_trees.push(dijkstra(graph, *vertex));
+    fn next(&mut self) -> Option<char> {
+        let next = self.peek();
+        self.at += 1;
+        next
     }
-    let mut new_edges: Vec<(usize, usize, usize)> = vec![];
-    let mut trimmed_sp_trees: Vec<Vec<Vertex>> = vec![];
-    for tree in shortest_path_trees {
-        let mut tree_filter = tree
+    fn error(&self, problem: &str) -> String {
+        // problems are reported by line, counted up to where reading stopped
+        let line = 1 + self.chars[..self.at.min(self.chars.len())]
             .iter()
-            .filter(|&vertex| nodes_with_odd_degree.contains(&vertex.idx));
-        let mut trimmed_tree = vec![];
-        while let Some(vertex) = tree_filter.next() {
-            trimmed_tree.push(Vertex::new(vertex.idx, vertex.distance_from_u));
+            .filter(|c| **c == '\n')
+            .count();
+        format!("line {line}: {problem}")
+    }
+    fn skip_blank(&mut self, newlines: bool) {
+        // spaces and comments, and line breaks too where a value can't end, between settings and inside lists
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' => self.at += 1,
+                '\n' | '\r' if newlines => self.at += 1,
+                '#' => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.at += 1;
+                    }
+                }
+                _ => break,
+            }
         }
-        trimmed_sp_trees.push(trimmed_tree);
     }
-    for (new_i, tree) in trimmed_sp_trees.iter().enumerate() {
-        for vertex in tree {
-            if nodes_with_odd_degree[new_i] == vertex.idx {
-                continue;
+    fn key(&mut self) -> Result<String, String> {
+        let start = self.at;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            self.at += 1;
+        }
+        match self.at > start {
+            true => Ok(self.chars[start..self.at].iter().collect()),
+            false => Err(self.error("expected a setting name")),
+        }
+    }
+    fn value(&mut self) -> Result<Setting, String> {
+        self.skip_blank(false);
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.at += 1;
+                let mut text = String::new();
+                loop {
+                    match self.next() {
+                        Some(c) if c == quote => return Ok(Setting::Text(text)),
+                        // only double quoted strings have escapes, like toml
+                        Some('\\') if quote == '"' => match self.next() {
+                            Some('n') => text.push('\n'),
+                            Some('t') => text.push('\t'),
+                            Some(c @ ('"' | '\\')) => text.push(c),
+                            _ => return Err(self.error("unknown escape in a string")),
+                        },
+                        Some('\n') | None => {
+                            // back off the line break, so the string is reported on the line it started
+                            self.at -= 1;
+                            return Err(self.error("a string isn't closed"));
+                        }
+                        Some(c) => text.push(c),
+                    }
+                }
             }
-            let new_j = nodes_with_odd_degree
-                .iter()
-                .position(|node| *node == vertex.idx)
-                .expect("this should exist");
-            if !new_edges.contains(&(new_j, new_i, vertex.distance_from_u)) {
-                new_edges.push((new_i, new_j, vertex.distance_from_u));
+            Some('[') => {
+                self.at += 1;
+                let mut items = vec![];
+                loop {
+                    self.skip_blank(true);
+                    if self.peek() == Some(']') {
+                        self.at += 1;
+                        return Ok(Setting::List(items));
+                    }
+                    items.push(self.value()?);
+                    self.skip_blank(true);
+                    match self.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Setting::List(items)),
+                        _ => return Err(self.error("expected , or ] in a list")),
+                    }
+                }
+            }
+            _ => {
+                // a bare word is a number or true or false. toml's 1_000 is 1000
+                let start = self.at;
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || "+-._".contains(c))
+                {
+                    self.at += 1;
+                }
+                let word: String = self.chars[start..self.at]
+                    .iter()
+                    .filter(|c| **c != '_')
+                    .collect();
+                match word.as_str() {
+                    "true" => Ok(Setting::Switch(true)),
+                    "false" => Ok(Setting::Switch(false)),
+                    number if number.parse::<f64>().is_ok() => {
+                        Ok(Setting::Text(number.to_string()))
+                    }
+                    _ => Err(self.error("expected a string, a number, true, false or a list")),
+                }
             }
         }
     }
-    let graph2: UndirectedALGraph<usize, (), usize> =
-        GraphBuilder::new().edges_with_values(new_edges).build();
 }
 
-struct Vertex {
-    idx: usize,
-    distance_from_u: usize,
-}
-impl Vertex {
-    fn new(idx: usize, distance_from_u: usize) -> Self {
-        Vertex {
-            idx,
-            distance_from_u,
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        // graph_builder logs every graph it builds, matching's little ones included, so its lines only show at -vvv
+        let ours = metadata.target().starts_with(module_path!());
+        metadata.level() <= log::max_level() && (ours || log::max_level() == LevelFilter::Trace)
+    }
+    fn log(&self, record: &log::Record) {
+        // the route goes to stdout, so the log goes to stderr with the warnings, prefixed the same way
+        if self.enabled(record.metadata()) {
+            eprintln!(
+                "{}: {}",
+                record.level().as_str().to_lowercase(),
+                record.args()
+            );
         }
     }
-    fn set_distance(&mut self, distance: usize) {
-        self.distance_from_u = distance;
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        // splitmix64, which is plenty random for making up maps and breaking ties and needs nothing outside std
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    pub fn below(&mut self, n: usize) -> usize {
+        // a whole number in 0..n, n has to be at least 1
+        (self.next_u64() % n as u64) as usize
+    }
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        // fisher-yates
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
     }
 }
-impl Ord for Vertex {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.distance_from_u.cmp(&other.distance_from_u)
+
+fn shuffled<T>(mut items: Vec<T>, seed: Option<u64>) -> Vec<T> {
+    // items in a random order for seed, or as they were without one
+    if let Some(seed) = seed {
+        Rng::new(seed).shuffle(&mut items);
     }
+    items
 }
-impl PartialOrd for Vertex {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.distance_from_u.cmp(&other.distance_from_u))
+
+struct Stopwatch(Option<Instant>);
+
+impl Stopwatch {
+    fn start() -> Self {
+        // wasm32-unknown-unknown has no clock and Instant::now panics there, so in the browser nothing is timed
+        match cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+            true => Stopwatch(None),
+            false => Stopwatch(Some(Instant::now())),
+        }
     }
 }
-impl PartialEq for Vertex {
-    fn eq(&self, other: &Self) -> bool {
-        (self.idx, self.distance_from_u) == (other.idx, other.distance_from_u)
+
+impl fmt::Display for Stopwatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // the time since start, for the log
+        match self.0 {
+            Some(started) => write!(f, "{:.2?}", started.elapsed()),
+            None => write!(f, "an untimed while"),
+        }
     }
 }
-impl Eq for Vertex {}
 
-fn dijkstra(graph: &UndirectedALGraph<usize, (), usize>, initial: usize) -> Vec<Vertex> {
-    let mut unvisited: Vec<Vertex> = vec![];
-    let mut sp_tree: Vec<Vertex> = vec![];
-    for i in 0..graph.node_count() {
-        unvisited.push(Vertex::new(i, usize::MAX));
+pub fn init_logging(verbosity: usize) {
+    // -v shows each step of the solve, -vv the detail inside them and -vvv every matched pair. a program using the
+    // library that's already set up its own logger keeps it
+    let level = match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
     }
-    let u: &mut Vertex = unvisited.iter_mut().find(|v| v.idx == initial).expect("ok");
-    u.set_distance(0);
-    let mut current = initial;
-    while !unvisited.is_empty() {
-        let cumulative_dist = unvisited
-            .iter()
-            .find(|u| u.idx == current)
-            .expect("ok")
-            .distance_from_u;
-        for neighbor in graph.neighbors_with_values(current) {
-            if let Some(v) = unvisited.iter_mut().find(|u| u.idx == neighbor.target) {
-                if neighbor.value + cumulative_dist < v.distance_from_u {
-                    v.set_distance(neighbor.value + cumulative_dist);
-                }
-            }
-        }
-        let rm_idx = unvisited.iter().position(|u| u.idx == current).expect("ok");
-        sp_tree.push(unvisited.swap_remove(rm_idx));
-        if let Some(new_vertex) = unvisited.iter().min() {
-            current = new_vertex.idx;
-        }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    // the route command's route so callers can use it, empty for the other commands
+    pub route: Route,
+    // what the command has to show, for main to put on stdout. an entry can run over several lines
+    pub lines: Vec<String>,
+    // warnings and notes about the input and the options, kept apart so they can go to stderr
+    pub warnings: Vec<String>,
+}
+
+pub fn run(config: Config) -> Result<Report, PacsamError> {
+    // the command config asks for, with everything it has to say handed back instead of printed
+    let mut report = Report::default();
+    run_into(config, &mut report)?;
+    Ok(report)
+}
+
+// expecting most of the options in these functions because we know from the data input that they will
+// always return Some(_), so more error handling is unnecessary.
+pub fn run_into(config: Config, report: &mut Report) -> Result<(), PacsamError> {
+    // like run, filling in report as it goes, so what was gathered before a failure (the warnings about the input,
+    // say) is still there to show alongside the error
+    if let Command::SolveAll(output_dir) = &config.command {
+        return solve_dir(
+            &config.file_path,
+            output_dir.as_deref(),
+            &config.units,
+            report,
+        );
     }
-    sp_tree
+    let parse_started = Stopwatch::start();
+    let map = read_map_file(&config.file_path, config.input_unit, &config.weight_format)?;
+    run_map(config, map, parse_started, report)
 }
 
-fn find_cycle(graph: &UndirectedALGraph<usize, (), usize>) -> Vec<usize> {
-    // get a vec of all edges, represented once each
-    let mut edges: Vec<(usize, usize)> = vec![];
-    for i in 0..graph.node_count() {
-        for neighbor in graph.neighbors_with_values(i as usize) {
-            if !edges.contains(&(neighbor.target as usize, i as usize)) {
-                edges.push((i as usize, neighbor.target as usize));
+fn run_map(
+    config: Config,
+    (contents, streamed): (String, Option<EdgeListMap>),
+    parse_started: Stopwatch,
+    report: &mut Report,
+) -> Result<(), PacsamError> {
+    // run_into for the map file's contents once they're read. a big enough edge list has already been read, and
+    // contents is just its header
+    // --avoid-node and --avoid-street add to whatever the file's header avoids
+    let mut avoid = avoid_directives(&contents)?;
+    avoid.nodes.extend(config.avoid.nodes.iter().cloned());
+    avoid.streets.extend(config.avoid.streets.iter().cloned());
+    let mut connectors = connector_directives(&contents)?;
+    connectors.extend(config.connectors.iter().map(|connector| Connector {
+        from: connector.from.clone(),
+        to: connector.to.clone(),
+        length: connector.length,
+    }));
+    // the header lines come off before anything reads the map, so doctor adds them back to its line numbers
+    let header_lines = split_header(&contents).0.len();
+    let (declared_unit, contents) = units_directive(&contents)?;
+    let contents = contents.to_string();
+    // --input-units wins over the file's own #units line
+    let unit = config.input_unit.or(declared_unit).unwrap_or(Unit::Feet);
+    // an OpenStreetMap extract is xml and GeoJSON is a json object, which none of the hand-written formats can
+    // contain. both are maps of located points with nothing else in them, and get read the same way
+    let is_geojson = contents.trim_start().starts_with('{');
+    let is_geo = contents.contains("<osm") || is_geojson;
+    let is_dot = is_dot(&contents);
+    // the edge list format has no colons at all, which is how it's told apart from the adjacency format. it's read
+    // in one go for its streets, labels, names and doors
+    let mut listed = match (&config.weight_format, streamed) {
+        (_, Some(map)) => Some(map),
+        (WeightFormat::Feet, None) if !is_geo && !is_dot && !contents.contains(':') => {
+            let mut map = read_edge_list(contents.as_bytes())?;
+            map.graph = scaled_to_feet(map.graph, &unit);
+            Some(map)
+        }
+        _ => None,
+    };
+    // door counts, by street keyed like street_names, for the time estimates
+    let doors = match config.weight_format {
+        WeightFormat::Feet if is_geo => BTreeMap::new(),
+        WeightFormat::Feet if is_dot => build_graph_dot(&contents)
+            .map(|map| map.doors)
+            .unwrap_or_default(),
+        WeightFormat::Feet if listed.is_some() => listed
+            .as_ref()
+            .map(|map| map.doors.clone())
+            .unwrap_or_default(),
+        WeightFormat::Feet => street_doors(&contents),
+        WeightFormat::Duration => BTreeMap::new(),
+    };
+    // lengths only make a grade in the feet format. an elevation in the --coords file wins over the map file's
+    let mut elevations = vec![];
+    if let (Some(_), WeightFormat::Feet) = (&config.effort, &config.weight_format) {
+        if !is_geo && !is_dot {
+            elevations = node_elevations(&contents, &unit)?;
+        }
+        if let Some(coords_path) = &config.coords_path {
+            let from_coords = coordinate_elevations(&fs::read_to_string(coords_path)?, &unit);
+            elevations.resize(elevations.len().max(from_coords.len()), None);
+            for (node, elevation) in from_coords.into_iter().enumerate() {
+                elevations[node] = elevation.or(elevations[node]);
             }
         }
     }
-    // hierholzer's algorithm finds the euler circuit
-    let mut path: Vec<usize> = vec![];
-    let mut vertices_with_unused_edges: Vec<usize> = vec![0];
-    while !vertices_with_unused_edges.is_empty() {
-        let v1 = vertices_with_unused_edges[0];
-        let neighbors: Vec<&(usize, usize)> = edges
-            .iter()
-            .filter(|edge| edge.0 == v1 || edge.1 == v1)
+    // in the adjacency format they ride on the end of the tokens, and nothing past here expects them. the ! on an
+    // early street comes off the same way, once the door count behind it is gone
+    let adjacency = matches!(config.weight_format, WeightFormat::Feet)
+        && !is_geo
+        && !is_dot
+        && contents.contains(':');
+    let contents = match adjacency {
+        true => without_doors(&contents),
+        false => contents,
+    };
+    let marked_early = match adjacency {
+        true => early_streets(&contents),
+        false => BTreeSet::new(),
+    };
+    let contents = match adjacency {
+        true => without_early(&contents),
+        false => contents,
+    };
+    // only the adjacency format has room for a weight each way
+    let mut costs = match adjacency {
+        true => direction_costs(&contents, &unit),
+        false => BTreeMap::new(),
+    };
+    if !config.allow_sparse_indices && !is_geo && !is_dot {
+        check_indices(&contents)?;
+    }
+    // one-way markers are only read in the feet format, same as street names
+    let directed = match config.weight_format {
+        WeightFormat::Feet
+            if !is_geo
+                && !is_dot
+                && contents
+                    .lines()
+                    .flat_map(|line| line.split(','))
+                    .any(is_one_way) =>
+        {
+            Some(build_directed_graph_in(&contents, &unit)?)
+        }
+        _ => None,
+    };
+    let two_way = match &directed {
+        Some(_) => two_way_streets(&contents),
+        None => BTreeMap::new(),
+    };
+    let diagnoses = match (&config.command, adjacency) {
+        (Command::Doctor, true) => diagnose(&contents, &unit),
+        _ => vec![],
+    };
+    // durations already use colons inside the weight, so only the feet format has room for street names
+    let mut labels = match config.weight_format {
+        WeightFormat::Feet if is_geo => BTreeMap::new(),
+        WeightFormat::Feet if is_dot => build_graph_dot(&contents)
+            .map(|map| map.labels)
+            .unwrap_or_default(),
+        WeightFormat::Feet if listed.is_some() => listed
+            .as_ref()
+            .map(|map| map.labels.clone())
+            .unwrap_or_default(),
+        _ => node_labels(&contents),
+    };
+    // where the map itself says its intersections are, for the outputs that draw the route
+    let mut located = vec![];
+    let (graph, names, mut optional) = match config.weight_format {
+        WeightFormat::Feet if is_geo => {
+            let map = match is_geojson {
+                true => build_graph_geojson(&contents, &unit)?,
+                false => build_graph_osm(&contents)?,
+            };
+            located = map.coords.into_iter().map(Some).collect();
+            (map.graph, map.names, BTreeSet::new())
+        }
+        WeightFormat::Feet if is_dot => {
+            let map = build_graph_dot(&contents)?;
+            (scaled_to_feet(map.graph, &unit), map.names, BTreeSet::new())
+        }
+        WeightFormat::Feet if listed.is_some() => {
+            let map = listed.take().expect("just checked");
+            (map.graph, map.names, BTreeSet::new())
+        }
+        WeightFormat::Feet => {
+            let names = street_names(&contents);
+            let optional = optional_streets(&contents);
+            (build_graph_in(contents, &unit)?, names, optional)
+        }
+        WeightFormat::Duration => (
+            build_graph_durations(&contents)?,
+            BTreeMap::new(),
+            BTreeSet::new(),
+        ),
+    };
+    info!(
+        "read {} intersections and {} streets ({} ft) from {} in {}",
+        graph.node_count(),
+        graph.edge_count(),
+        format_weight(edge_list(&graph).iter().map(|(_, _, weight)| weight).sum()),
+        config.file_path,
+        parse_started
+    );
+    if let Some(directed) = &directed {
+        debug!("one-way streets make it {} arcs", directed.edge_count());
+    }
+    // warnings about the input are for the map as it was written, before anything is taken out of it
+    let mut warnings = input_warnings(&graph);
+    let (graph, directed) = match &config.effort {
+        Some(effort) if !elevations.is_empty() => {
+            let graph = with_effort(&graph, &mut costs, &elevations, effort);
+            // one-way streets have an arc per way they can be walked, and each takes its own way's cost
+            let directed = directed.map(|directed| {
+                let arcs = arc_list(&directed)
+                    .into_iter()
+                    .map(|(u, v, weight)| (u, v, costs.get(&(u, v)).copied().unwrap_or(weight)))
+                    .collect();
+                directed_with_nodes(arcs, directed.node_count())
+            });
+            (graph, directed)
+        }
+        _ => (graph, directed),
+    };
+    let avoided_nodes: BTreeSet<usize> = avoid
+        .nodes
+        .iter()
+        .map(|name| resolve_node(name, &labels))
+        .collect::<Result<_, _>>()?;
+    let avoided_streets: BTreeSet<(usize, usize)> = avoid
+        .streets
+        .iter()
+        .map(|(u, v)| {
+            let (u, v) = (resolve_node(u, &labels)?, resolve_node(v, &labels)?);
+            Ok((u.min(v), u.max(v)))
+        })
+        .collect::<Result<_, PacsamError>>()?;
+    let avoids = |u: usize, v: usize| {
+        avoided_nodes.contains(&u)
+            || avoided_nodes.contains(&v)
+            || avoided_streets.contains(&(u.min(v), u.max(v)))
+    };
+    let (graph, directed) = if avoided_nodes.is_empty() && avoided_streets.is_empty() {
+        (graph, directed)
+    } else {
+        let kept: Vec<(usize, usize, usize)> = edge_list(&graph)
+            .into_iter()
+            .filter(|(u, v, _)| !avoids(*u, *v))
             .collect();
-        if neighbors.len() == 0 {
-            path.push(vertices_with_unused_edges.remove(0));
+        let kept = graph_with_nodes(kept, graph.node_count());
+        // an avoided node is left with no streets, which connected_components doesn't count as a piece
+        if is_connected(&graph) && !is_connected(&kept) {
+            warnings.push(Warning::AvoidDisconnects(connected_components(&kept).len()));
+        }
+        let directed = directed.map(|directed| {
+            let arcs: Vec<(usize, usize, usize)> = arc_list(&directed)
+                .into_iter()
+                .filter(|(u, v, _)| !avoids(*u, *v))
+                .collect();
+            directed_with_nodes(arcs, directed.node_count())
+        });
+        (kept, directed)
+    };
+    // connectors go in after the avoiding, so they're there even when an avoided node is one of their ends. nobody
+    // has to walk them, so they're optional like a * street. one-way maps don't route around optional streets and
+    // route_directed says so
+    let graph = match connectors.is_empty() {
+        true => graph,
+        false => {
+            let added = connector_edges(graph.node_count(), &connectors, &unit, &mut labels);
+            let nodes = added
+                .iter()
+                .map(|(_, v, _)| v + 1)
+                .max()
+                .unwrap_or(0)
+                .max(graph.node_count());
+            optional.extend(added.iter().map(|(u, v, _)| (*u, *v)));
+            let mut edges = edge_list(&graph);
+            edges.extend(added);
+            graph_with_nodes(edges, nodes)
+        }
+    };
+    // --early-street has to name a street that's still there to walk
+    let mut early = marked_early;
+    for (u, v) in &config.early_streets {
+        let (u, v) = (resolve_node(u, &labels)?, resolve_node(v, &labels)?);
+        if u >= graph.node_count() || !neighbors_vec(&graph, u).iter().any(|(node, _)| *node == v) {
+            return Err(PacsamError::NoSuchStreet(u, v));
+        }
+        early.insert((u.min(v), u.max(v)));
+    }
+    // the route goes to stdout, so warnings about the input go to stderr where they can be told apart
+    for warning in warnings {
+        if config.warnings_json {
+            report.warnings.push(warning.to_json());
         } else {
-            let chosen_edge = *neighbors[0];
-            let rm_idx = edges
+            report.warnings.push(format!("warning: {warning}"));
+        }
+    }
+    match &config.command {
+        Command::Route => {
+            // read up front so a bad coordinate file doesn't waste a solve
+            let coords = match &config.coords_path {
+                Some(coords_path) => parse_coordinates(&fs::read_to_string(coords_path)?)?,
+                None => located,
+            };
+            // routing adds the re-walked streets to graph, so the dot and svg output need the map as it was read
+            let map = (config.dot_path.is_some() || config.svg_path.is_some() || config.heatmap)
+                .then(|| copy_graph(&graph));
+            // walking a street once each way covers both its sides, so every street becomes an arc each way. a
+            // walker can go up either side of a one-way street, so the file's one-way markers don't matter here
+            let sides = config.both_sides.then(|| both_ways(&graph, &costs));
+            // taken before routing adds its repeats to the map, to tell them apart afterwards. a map with one-way
+            // streets only ever walks them the way they go, so its streets can be counted without their direction
+            let copies = match &sides {
+                Some(sides) => arc_copies(sides),
+                None => street_copies(&graph),
+            };
+            let streets = Streets {
+                names,
+                optional,
+                doors,
+                costs,
+                early,
+                copies,
+                two_way: match &sides {
+                    Some(_) => BTreeMap::new(),
+                    None => two_way,
+                },
+            };
+            let solved = match sides.as_ref().or(directed.as_ref()) {
+                Some(directed) => route_directed(directed, &config, &streets, &labels, report)?,
+                None => route(&graph, &config, &streets, &labels, &coords, report)?,
+            };
+            let (path, repeats) = (&solved.path, &solved.repeats);
+            // walking both sides goes down every street twice, so each intersection needs twice the passes
+            let visits = map
+                .as_ref()
+                .filter(|_| config.heatmap)
+                .map(|map| match &sides {
+                    Some(_) => {
+                        let mut twice = edge_list(map);
+                        twice.extend(edge_list(map));
+                        route_visits(path, &graph_with_nodes(twice, map.node_count()))
+                    }
+                    None => route_visits(path, map),
+                });
+            if let Some(gpx_path) = &config.gpx_path {
+                fs::write(gpx_path, to_gpx(path, &coords, &labels))?;
+            }
+            if let Some(kml_path) = &config.kml_path {
+                fs::write(
+                    kml_path,
+                    route_kml(path, &coords, &labels, config.kml_stops, visits.as_ref()),
+                )?;
+            }
+            if let Some(geojson_path) = &config.geojson_path {
+                if coords.iter().all(Option::is_none) {
+                    report.warnings.push(
+                        "warning: nothing in the route is located, by --coords or a GeoJSON or OpenStreetMap \
+                         map, so the GeoJSON is empty"
+                            .to_string(),
+                    );
+                }
+                fs::write(
+                    geojson_path,
+                    route_geojson(
+                        path,
+                        &coords,
+                        &streets.names,
+                        repeats,
+                        &labels,
+                        visits.as_ref(),
+                    ),
+                )?;
+            }
+            if let (Some(dot_path), Some(map)) = (&config.dot_path, &map) {
+                fs::write(dot_path, route_to_dot(path, map, &labels))?;
+            }
+            if let (Some(svg_path), Some(map)) = (&config.svg_path, &map) {
+                fs::write(svg_path, route_svg(path, map, &coords, &labels))?;
+            }
+            // with an output file the path went there instead, and json output already has it
+            let to_stdout =
+                config.output_path.is_none() && matches!(config.output_format, OutputFormat::Text);
+            if to_stdout && !path.is_empty() && config.mark_repeats {
+                for (hop, repeat) in path.windows(2).zip(repeats) {
+                    let street = label_nodes(hop, &labels);
+                    match *repeat {
+                        true => report.lines.push(format!("{street} (repeat)")),
+                        false => report.lines.push(street),
+                    }
+                }
+            } else if to_stdout && !path.is_empty() {
+                report.lines.push(label_nodes(path, &labels));
+            }
+            if let (Some(service), OutputFormat::Text) = (config.map_links, &config.output_format) {
+                if coords.iter().all(Option::is_none) {
+                    report.warnings.push(
+                        "warning: nothing in the route is located, by --coords or a GeoJSON or OpenStreetMap \
+                         map, so there are no links to give"
+                            .to_string(),
+                    );
+                }
+                for (leg, link) in map_links(path, &coords, service).iter().enumerate() {
+                    report.lines.push(format!("leg {}: {link}", leg + 1));
+                }
+            }
+            if let (Some(visits), OutputFormat::Text) = (&visits, &config.output_format) {
+                report.lines.push(visits.describe(&streets.names, &labels));
+            }
+            report.route = solved;
+            return Ok(());
+        }
+        Command::Analyze => {
+            let dead_ends = (0..graph.node_count())
+                .filter(|node| graph.degree(*node) == 1)
+                .count();
+            let stats = route_stats(&graph, &config.boundary_policy);
+            // every street's doors once, over the length of the streets
+            let door_count: usize = doors.values().sum();
+            let density = doors_per(door_count, stats.street_feet, &config.units);
+            let suffix = distance_suffix(&config.units);
+            if let OutputFormat::Json = config.output_format {
+                report.lines.push(format!(
+                    "{{\"intersections\":{},\"streets\":{},\"dead_ends\":{dead_ends},\"doors\":{door_count},\
+                     \"doors_per_{suffix}\":{density:.1},\"route\":{}}}",
+                    graph.node_count(),
+                    graph.edge_count(),
+                    stats.to_json(&config.units)
+                ));
+                return Ok(());
+            }
+            report
+                .lines
+                .push(format!("intersections: {}", graph.node_count()));
+            report
+                .lines
+                .push(format!("streets: {}", graph.edge_count()));
+            report.lines.push(format!(
+                "length: {} {suffix}",
+                convert_length(total_feet(&graph), &Unit::Feet, &config.units)
+            ));
+            report.lines.push(format!("dead ends: {dead_ends}"));
+            if door_count > 0 {
+                report
+                    .lines
+                    .push(format!("doors: {door_count} ({density:.1} per {suffix})"));
+            }
+            // what routing would have to add, worked out on a copy so the input is reported as it was read
+            let eulerized = copy_graph(&graph);
+            fix_culdesacs(&eulerized, &config.boundary_policy);
+            let culdesac_returns = eulerized.edge_count() - graph.edge_count();
+            let eulerization = eulerize(&eulerized);
+            report
+                .lines
+                .push(format!("cul de sac returns: {culdesac_returns}"));
+            report.lines.push(format!(
+                "streets duplicated to pair odd intersections: {} ({} {suffix})",
+                eulerization.duplicated_edge_count,
+                convert_length(eulerization.duplicated_feet, &Unit::Feet, &config.units)
+            ));
+            report.lines.push(stats.describe(&labels, &config.units));
+            if let Some(pace) = &config.pace {
+                // every street's doors once, and the walking the whole route takes
+                let at_doors = door_count as f64 * pace.minutes_per_door;
+                let walking = walking_minutes(stats.route_feet, pace);
+                report.lines.push(format!(
+                    "estimated time: {} ({} walking, {} at doors)",
+                    format_minutes(walking + at_doors),
+                    format_minutes(walking),
+                    format_minutes(at_doors)
+                ));
+            }
+            if let Some(directed) = &directed {
+                let unbalanced = unbalanced_nodes(directed);
+                report.lines.push(format!(
+                    "one-way intersections out of balance: {}",
+                    unbalanced.len()
+                ));
+                for (node, surplus) in unbalanced {
+                    report
+                        .lines
+                        .push(format!("  {node}: {surplus:+} (out - in)"));
+                }
+            }
+        }
+        Command::Audit(walk_path) => {
+            let walk = parse_walk(&fs::read_to_string(walk_path)?, &labels)?;
+            if let Some(node) = walk.iter().find(|node| **node >= graph.node_count()) {
+                return Err(PacsamError::NoSuchNode(*node));
+            }
+            if directed.is_some() {
+                report.warnings.push(
+                    "note: audit doesn't check which way the one-way streets were walked"
+                        .to_string(),
+                );
+            }
+            let audit = audit_walk(&graph, &optional, &walk);
+            match config.output_format {
+                OutputFormat::Json => report.lines.push(audit.to_json(&config.units)),
+                OutputFormat::Text => {
+                    report
+                        .lines
+                        .push(audit.describe(&names, &labels, &config.units))
+                }
+            }
+        }
+        Command::Diff(before_path, after_path) => {
+            let before = parse_walk(&fs::read_to_string(before_path)?, &labels)?;
+            let after = parse_walk(&fs::read_to_string(after_path)?, &labels)?;
+            if let Some(node) = before
                 .iter()
-                .position(|edge| *edge == chosen_edge)
-                .expect("exists");
-            // edges is unordered, so swap_remove is faster with no downside
-            edges.swap_remove(rm_idx);
-            if chosen_edge.0 == v1 {
-                vertices_with_unused_edges.insert(0, chosen_edge.1);
-            } else {
-                vertices_with_unused_edges.insert(0, chosen_edge.0);
+                .chain(&after)
+                .find(|node| **node >= graph.node_count())
+            {
+                return Err(PacsamError::NoSuchNode(*node));
+            }
+            let diff = diff_routes(&graph, &before, &after);
+            match config.output_format {
+                OutputFormat::Json => report.lines.push(diff.to_json(&config.units)),
+                OutputFormat::Text => {
+                    report
+                        .lines
+                        .push(diff.describe(&names, &labels, &config.units))
+                }
+            }
+        }
+        Command::Review(route_path) => {
+            let route = parse_walk(&fs::read_to_string(route_path)?, &labels)?;
+            if let Some(node) = route.iter().find(|node| **node >= graph.node_count()) {
+                return Err(PacsamError::NoSuchNode(*node));
             }
+            // a prompt only makes sense to someone typing, not in the middle of a script's output. someone typing
+            // needs each answer as they go too, so only then does the session talk to the terminal directly
+            let interactive = io::stdin().is_terminal();
+            let mut answers = vec![];
+            match interactive {
+                true => review(
+                    &route,
+                    &graph,
+                    &optional,
+                    &labels,
+                    &config.units,
+                    (io::stdin().lock(), &mut io::stdout()),
+                    true,
+                )?,
+                false => review(
+                    &route,
+                    &graph,
+                    &optional,
+                    &labels,
+                    &config.units,
+                    (io::stdin().lock(), &mut answers),
+                    false,
+                )?,
+            }
+            report
+                .lines
+                .extend(String::from_utf8_lossy(&answers).lines().map(String::from));
+        }
+        Command::Validate => {
+            let components = connected_components(&graph);
+            if components.len() > 1 {
+                return Err(PacsamError::Disconnected(components));
+            }
+            report.lines.push("ok".to_string());
+        }
+        Command::Doctor => {
+            // the other formats don't list a street per token, so the warnings already printed are all there is
+            if !adjacency {
+                report.warnings.push(
+                    "note: doctor only goes line by line through the adjacency format".to_string(),
+                );
+            }
+            let diagnoses: Vec<Diagnosis> = diagnoses
+                .into_iter()
+                .map(|diagnosis| diagnosis.shifted(header_lines))
+                .collect();
+            match config.output_format {
+                OutputFormat::Json => {
+                    let found: Vec<String> = diagnoses.iter().map(Diagnosis::to_json).collect();
+                    report.lines.push(format!("[{}]", found.join(",")));
+                }
+                OutputFormat::Text if diagnoses.is_empty() => {
+                    report.lines.push("no problems found".to_string())
+                }
+                OutputFormat::Text => {
+                    for diagnosis in &diagnoses {
+                        report.lines.push(diagnosis.to_string());
+                    }
+                }
+            }
+        }
+        Command::Convert(output_path) if output_path.ends_with(".dot") => {
+            fs::write(output_path, to_dot(&graph, &labels))?
         }
+        Command::Convert(output_path) => fs::write(output_path, to_colon_format(&graph))?,
+        // a directory rather than a map, so it was dealt with before anything was read
+        Command::SolveAll(_) => {}
     }
-    path
+    Ok(())
 }
 
-fn build_graph(input: String) -> UndirectedALGraph<usize, (), usize> {
-    // parse the input file
-    let mut edges: Vec<(usize, usize, usize)> = vec![];
-    let mut line_counter: usize = 0;
-    for line in input.lines() {
-        let edges_from_input: Vec<&str> = line.split(",").collect();
-        for edge in edges_from_input {
-            let vertex_and_weight: Vec<&str> = edge.split(":").collect();
-            if vertex_and_weight.len() == 1 {
-                continue;
+fn report_estimate(
+    path: &[usize],
+    graph: &RoadGraph,
+    doors: &BTreeMap<(usize, usize), usize>,
+    config: &Config,
+    report: &mut Report,
+) -> Vec<f64> {
+    // with --speed or --door-minutes, reports how long the route should take and hands back each hop's minutes
+    // for the turn-by-turn steps. without them there's no estimate and the minutes are empty
+    let Some(pace) = &config.pace else {
+        return vec![];
+    };
+    let minutes = hop_minutes(path, graph, doors, pace);
+    if let OutputFormat::Text = config.output_format {
+        let feet = total_feet_u128(path, graph) as f64 / WEIGHT_SCALE as f64;
+        let walking = walking_minutes(feet, pace);
+        report.lines.push(format!(
+            "estimated time: {} ({} walking, {} at doors)",
+            format_minutes(minutes.iter().sum()),
+            format_minutes(walking),
+            format_minutes(minutes.iter().sum::<f64>() - walking)
+        ));
+    }
+    minutes
+}
+
+fn turn_coords(config: &Config) -> Result<Vec<Option<(f64, f64)>>, PacsamError> {
+    // turn-by-turn directions only say which way to turn when --coords locates the intersections
+    match &config.coords_path {
+        Some(coords_path) => parse_coordinates(&fs::read_to_string(coords_path)?),
+        None => Ok(vec![]),
+    }
+}
+
+struct Streets {
+    // what the input says about its streets besides their lengths, keyed like street_names except costs, which
+    // are by the way the street is walked (see direction_costs)
+    names: BTreeMap<(usize, usize), String>,
+    optional: BTreeSet<(usize, usize)>,
+    doors: BTreeMap<(usize, usize), usize>,
+    costs: BTreeMap<(usize, usize), usize>,
+    // the streets to walk as early in the route as possible
+    early: BTreeSet<(usize, usize)>,
+    // how many of each street the map has before routing adds any, from street_copies or arc_copies
+    copies: BTreeMap<(usize, usize), usize>,
+    // on a map with one-way streets, the ones that can be walked either way (see two_way_streets)
+    two_way: BTreeMap<(usize, usize), usize>,
+}
+
+fn route(
+    graph: &RoadGraph,
+    config: &Config,
+    streets: &Streets,
+    labels: &BTreeMap<usize, String>,
+    located: &[Option<(f64, f64)>],
+    report: &mut Report,
+) -> Result<Route, PacsamError> {
+    // located is where --coords or the map itself puts the intersections. the shortest paths use it when every one
+    // is located, and turns only go by --coords
+    let Streets {
+        names,
+        optional,
+        doors,
+        costs,
+        early,
+        copies,
+        ..
+    } = streets;
+    // optional streets are shortcuts, so the base network and its street count are only the required ones. costs
+    // are direction_costs, and the map itself holds the average of a street's two so matching treats it fairly.
+    // the matching can't charge a repeat for the way it's walked, since that's only settled once the circuit is
+    // traced. maps with one-way streets go through route_directed, where the repeats follow each arc's own cost
+    let required: RoadGraph = GraphBuilder::new()
+        .edges_with_values(required_edges(graph, optional))
+        .build();
+    // a street that costs more one way than the other only has to be walked the cheaper way
+    let cheaper =
+        |(u, v, weight): (usize, usize, usize)| match (costs.get(&(u, v)), costs.get(&(v, u))) {
+            (Some(there), Some(back)) => *there.min(back),
+            _ => weight,
+        };
+    let base_feet = edge_list(&required)
+        .into_iter()
+        .map(|edge| cheaper(edge) as u128)
+        .sum::<u128>() as f64
+        / WEIGHT_SCALE as f64;
+    let distinct_edges = required.edge_count();
+    // a blank file still builds a graph with a node 0 but there's nothing to route, so the path is empty rather
+    // than that one phantom node
+    if distinct_edges == 0 {
+        let summary = RouteSummary {
+            base_feet: 0.0,
+            total_feet: 0.0,
+            distinct_edges: 0,
+            total_traversals: 0,
+            repeats: 0,
+            doors: 0,
+            approach_feet: None,
+        };
+        report
+            .lines
+            .extend(summary_lines(&summary, &[], &[], &[], config, labels, ""));
+        return Ok(Route::default());
+    }
+    // a circuit can't cross between pieces, and eulerize would happily pair odd nodes that have no path between them
+    if !is_connected(graph) {
+        if !config.per_component {
+            return Err(PacsamError::Disconnected(connected_components(graph)));
+        }
+        // each piece gets its own circuit, and there's no single path to hand back
+        let routes = routes_per_component(graph)?;
+        if let OutputFormat::Json = config.output_format {
+            let routes: Vec<String> = routes.iter().map(Route::to_json).collect();
+            report
+                .lines
+                .push(format!("{{\"components\":[{}]}}", routes.join(",")));
+            return Ok(Route::default());
+        }
+        for (piece, path) in routes.iter().map(|route| &route.path).enumerate() {
+            let at_doors = match doors.is_empty() {
+                true => String::new(),
+                false => format!(", {} doors", hop_doors(path, doors).iter().sum::<usize>()),
+            };
+            report.lines.push(format!(
+                "component {} ({} {}{at_doors}): {}",
+                piece + 1,
+                length(path, graph, &Unit::Feet, &config.units),
+                distance_suffix(&config.units),
+                label_nodes(path, labels)
+            ));
+        }
+        return Ok(Route::default());
+    }
+    let route_kind = match &config.open {
+        Some((start, end)) => RouteKind::OpenPath {
+            start: resolve_node(start, labels)?,
+            end: resolve_node(end, labels)?,
+        },
+        None => RouteKind::Circuit,
+    };
+    let endpoints = match route_kind {
+        RouteKind::OpenPath { start, end } => vec![start, end],
+        RouteKind::Circuit => vec![],
+    };
+    let chosen_start = config
+        .start
+        .as_deref()
+        .map(|name| resolve_node(name, labels))
+        .transpose()?;
+    let depot = config
+        .depot
+        .as_deref()
+        .map(|name| resolve_node(name, labels))
+        .transpose()?;
+    let criterion = start_criterion(config, labels)?;
+    if let Some(node) = endpoints
+        .into_iter()
+        .chain(depot)
+        .chain(chosen_start)
+        .chain(criterion.iter().flat_map(StartCriterion::nodes))
+        .find(|node| *node >= graph.node_count() || graph.degree(*node) == 0)
+    {
+        return Err(PacsamError::NoSuchNode(node));
+    }
+    // a depot with no street to cover is off the turf, and the route walks out to the turf from it and back
+    let off_turf =
+        depot.filter(|node| *node >= required.node_count() || required.degree(*node) == 0);
+    let chosen_start = chosen_start.or(off_turf);
+    let rural_route;
+    let graph = if !optional.is_empty() {
+        rural_route = join_required(graph, optional, off_turf)?;
+        duplicate_paths_for(
+            &rural_route,
+            graph,
+            &shuffled(route_odd_nodes(&rural_route, &route_kind), config.seed),
+            config.objective,
+            located,
+        );
+        &rural_route
+    } else if is_tree(graph)
+        && matches!(config.boundary_policy, BoundaryPolicy::TreatAsCuldesac)
+        && matches!(route_kind, RouteKind::Circuit)
+    {
+        // with no loops every street is a dead end in the end, so every one gets walked out and back and there's
+        // nothing for the shortest paths and matching to decide
+        for (u, v, weight) in edge_list(graph) {
+            let _ = graph.add_edge_with_value(u, v, weight);
+        }
+        graph
+    } else {
+        fix_culdesacs(graph, &config.boundary_policy);
+        duplicate_paths_for(
+            graph,
+            graph,
+            &shuffled(route_odd_nodes(graph, &route_kind), config.seed),
+            config.objective,
+            located,
+        );
+        graph
+    };
+    if let Some(limit) = config.max_traversal {
+        // some one-lane streets can't physically be driven more than a couple of times
+        if let Some(((u, v), count)) = traversal_counts(graph)
+            .into_iter()
+            .find(|(_, count)| *count > limit)
+        {
+            return Err(PacsamError::TraversalLimitExceeded {
+                edge: (u, v),
+                count,
+                limit,
+            });
+        }
+    }
+    if let Some(dump_path) = &config.dump_eulerized {
+        fs::write(dump_path, to_colon_format(graph))?;
+    }
+    // node 0 can be an intersection with nothing to cover, so the circuit starts at the first one that has some
+    let start = chosen_start.unwrap_or_else(|| {
+        (0..graph.node_count())
+            .find(|node| graph.degree(*node) > 0)
+            .unwrap_or(0)
+    });
+    let coords = turn_coords(config)?;
+    let circuit = |seed: Option<u64>| -> Result<Vec<usize>, PacsamError> {
+        if coords.is_empty() && costs.is_empty() && early.is_empty() {
+            let path = find_cycle_seeded(graph, start, seed, |_| 0)?;
+            debug_assert!(is_euler_circuit(&path, graph));
+            return Ok(start_where(path, graph, criterion.as_ref()));
+        }
+        // where the intersections are located the walk keeps as straight as it can, and where a street costs more
+        // one way it goes the cheaper way when it can, instead of taking whichever street comes first. early streets
+        // are taken ahead of anything else, then the circuit is turned to reach them all soonest, unless
+        // --best-start or --priority-street already says where it starts
+        let turns = turn_score(&coords);
+        let downhill = downhill_score(costs);
+        let score = |hop: &HopContext| {
+            let bias = match early.contains(&(hop.from.min(hop.to), hop.from.max(hop.to))) {
+                true => 1_000_000,
+                false => 0,
+            };
+            bias + downhill(hop) + if coords.is_empty() { 0 } else { turns(hop) }
+        };
+        let path = find_cycle_seeded(graph, start, seed, score)?;
+        debug_assert!(is_euler_circuit(&path, graph));
+        let path = cheaper_way_round(path, graph, costs);
+        Ok(match &criterion {
+            None if !early.is_empty() => early_first(path, graph, costs, early, chosen_start),
+            _ => start_where(path, graph, criterion.as_ref()),
+        })
+    };
+    let path = match route_kind {
+        RouteKind::Circuit if config.restarts > 1 => {
+            // every circuit walks the same streets, so they're all the same length and only differ in the order.
+            // the first is the one a single try gives, the rest break their ties at random, and a tie keeps the
+            // earlier circuit
+            let cost = |path: &[usize]| restart_cost(path, graph, streets, &coords, config);
+            let mut best = circuit(config.seed)?;
+            let mut best_cost = cost(&best);
+            let mut kept = 0;
+            for restart in 1..config.restarts {
+                let seed = config.seed.unwrap_or(0).wrapping_add(restart as u64);
+                let path = circuit(Some(seed))?;
+                let path_cost = cost(&path);
+                trace!("restart {restart} scores {path_cost:?}");
+                if path_cost < best_cost {
+                    (best, best_cost, kept) = (path, path_cost, restart);
+                }
+            }
+            debug!(
+                "kept circuit {kept} of {} restarts, scoring {best_cost:?}",
+                config.restarts
+            );
+            best
+        }
+        RouteKind::Circuit => circuit(config.seed)?,
+        RouteKind::OpenPath { start, end } => find_trail(graph, start, end)?,
+    };
+    let weights = walked_weights(&path, graph, costs);
+    let repeats = repeat_hops(&path, copies, false);
+    let summary = RouteSummary {
+        base_feet,
+        total_feet: weights.iter().map(|weight| *weight as u128).sum::<u128>() as f64
+            / WEIGHT_SCALE as f64,
+        distinct_edges,
+        total_traversals: graph.edge_count(),
+        repeats: repeats.iter().filter(|repeat| **repeat).count(),
+        doors: hop_doors(&path, doors).iter().sum(),
+        approach_feet: off_turf.map(|_| approach_legs(&path, &weights, optional)),
+    };
+    // a street two walkers both go down is only knocked by the first
+    let shares = match config.walkers {
+        Some(walkers) => knocked_shares(
+            split_route(graph, &path, walkers, depot.unwrap_or(start)),
+            doors,
+        ),
+        None => vec![],
+    };
+    let splits = format!(
+        "{}{}",
+        walkers_json(&shares, doors, config, labels),
+        shifts_json(&path, graph, doors, config, labels)
+    );
+    report.lines.extend(summary_lines(
+        &summary, &path, &weights, &repeats, config, labels, &splits,
+    ));
+    if let OutputFormat::Text = config.output_format {
+        report_early(&path, &weights, early, names, labels, config, report);
+    }
+    let minutes = report_estimate(&path, graph, doors, config, report);
+    if let Some(out_path) = &config.output_path {
+        write_result(&path, graph, labels, out_path, &config.units)?;
+    }
+    if config.turn_by_turn {
+        for step in turn_by_turn(&path, graph, names, labels, &coords, &minutes, false) {
+            report.lines.push(step);
+        }
+    }
+    // in json the walkers and shifts are part of the summary object instead
+    if let OutputFormat::Text = config.output_format {
+        for (walker, (share, reached)) in shares.iter().enumerate() {
+            match doors.is_empty() {
+                true => report.lines.push(format!(
+                    "walker {}: {}",
+                    walker + 1,
+                    share.describe(labels, &config.units)
+                )),
+                false => report.lines.push(format!(
+                    "walker {} ({reached} doors): {}",
+                    walker + 1,
+                    share.describe(labels, &config.units)
+                )),
             }
-            let vertex = vertex_and_weight[0].parse::<usize>().unwrap();
-            let weight = vertex_and_weight[1].parse::<usize>().unwrap();
-            edges.push((line_counter, vertex, weight));
         }
-        line_counter += 1;
+        report_shifts(&path, graph, doors, config, labels, report);
     }
-    let graph: UndirectedALGraph<usize, (), usize> =
-        GraphBuilder::new().edges_with_values(edges).build();
+    Ok(Route::walked(path, &weights, repeats))
+}
+
+fn knocked_shares(
+    shares: Vec<WalkerRoute>,
+    doors: &BTreeMap<(usize, usize), usize>,
+) -> Vec<(WalkerRoute, usize)> {
+    // each walker's share with the doors it reaches that no walker before it already knocked
+    let mut knocked = BTreeSet::new();
+    shares
+        .into_iter()
+        .map(|share| {
+            let reached = knock(&share.path, doors, &mut knocked).iter().sum();
+            (share, reached)
+        })
+        .collect()
+}
+
+fn walkers_json(
+    shares: &[(WalkerRoute, usize)],
+    doors: &BTreeMap<(usize, usize), usize>,
+    config: &Config,
+    labels: &BTreeMap<usize, String>,
+) -> String {
+    // the walkers member of the route's json, empty without --walkers
+    if shares.is_empty() {
+        return String::new();
+    }
+    let walkers: Vec<String> = shares
+        .iter()
+        .map(|(share, reached)| {
+            let at_doors = match doors.is_empty() {
+                true => String::new(),
+                false => format!(",\"doors\":{reached}"),
+            };
+            format!(
+                "{{\"length\":{},\"depot\":{}{at_doors},\"path\":[{}]}}",
+                convert_length(share.feet, &Unit::Feet, &config.units),
+                convert_length(share.deadhead_feet, &Unit::Feet, &config.units),
+                labels_json(&share.path, labels)
+            )
+        })
+        .collect();
+    format!(",\"walkers\":[{}]", walkers.join(","))
+}
 
-    graph
+fn shifts_json(
+    path: &[usize],
+    graph: &RoadGraph,
+    doors: &BTreeMap<(usize, usize), usize>,
+    config: &Config,
+    labels: &BTreeMap<usize, String>,
+) -> String {
+    // the shifts member of the route's json, the same shifts report_shifts lists. empty without a shift limit
+    if let OutputFormat::Text = config.output_format {
+        return String::new();
+    }
+    let Some(ShiftCuts {
+        feet,
+        minutes,
+        shifts,
+    }) = shift_cuts(path, graph, doors, config)
+    else {
+        return String::new();
+    };
+    let reached = hop_doors(path, doors);
+    let shifts: Vec<String> = shifts
+        .iter()
+        .map(|(first, last)| {
+            let at_doors = match doors.is_empty() {
+                true => String::new(),
+                false => format!(
+                    ",\"doors\":{}",
+                    reached[*first..*last].iter().sum::<usize>()
+                ),
+            };
+            format!(
+                "{{\"length\":{},\"minutes\":{:.1}{at_doors},\"path\":[{}]}}",
+                convert_length(feet[*first..*last].iter().sum(), &Unit::Feet, &config.units),
+                minutes[*first..*last].iter().sum::<f64>(),
+                labels_json(&path[*first..=*last], labels)
+            )
+        })
+        .collect();
+    format!(",\"shifts\":[{}]", shifts.join(","))
+}
+
+fn labels_json(path: &[usize], labels: &BTreeMap<usize, String>) -> String {
+    path.iter()
+        .map(|node| format!("\"{}\"", escape_json(&node_label(*node, labels))))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn report_shifts(
+    path: &[usize],
+    graph: &RoadGraph,
+    doors: &BTreeMap<(usize, usize), usize>,
+    config: &Config,
+    labels: &BTreeMap<usize, String>,
+    report: &mut Report,
+) {
+    // with --shift-length or --shift-minutes, one line per shift and the intersections where they hand over
+    let Some(ShiftCuts {
+        feet,
+        minutes,
+        shifts,
+    }) = shift_cuts(path, graph, doors, config)
+    else {
+        return;
+    };
+    let reached = hop_doors(path, doors);
+    for (shift, (first, last)) in shifts.iter().enumerate() {
+        let at_doors = match doors.is_empty() {
+            true => String::new(),
+            false => format!(", {} doors", reached[*first..*last].iter().sum::<usize>()),
+        };
+        report.lines.push(format!(
+            "shift {} ({} {}, {}{at_doors}): {}",
+            shift + 1,
+            convert_length(feet[*first..*last].iter().sum(), &Unit::Feet, &config.units),
+            distance_suffix(&config.units),
+            format_minutes(minutes[*first..*last].iter().sum()),
+            label_nodes(&path[*first..=*last], labels)
+        ));
+    }
+    let handovers: Vec<String> = shifts
+        .iter()
+        .skip(1)
+        .map(|(first, _)| node_label(path[*first], labels))
+        .collect();
+    if !handovers.is_empty() {
+        report
+            .lines
+            .push(format!("shifts hand over at {}", handovers.join(", ")));
+    }
+}
+
+struct ShiftCuts {
+    // each hop of the route in feet and in minutes, and the shifts as cut_route gives them
+    feet: Vec<f64>,
+    minutes: Vec<f64>,
+    shifts: Vec<(usize, usize)>,
+}
+
+fn shift_cuts(
+    path: &[usize],
+    graph: &RoadGraph,
+    doors: &BTreeMap<(usize, usize), usize>,
+    config: &Config,
+) -> Option<ShiftCuts> {
+    // where --shift-length or --shift-minutes cuts the route, or None without either
+    let limit = config.shift_limit.as_ref()?;
+    let feet: Vec<f64> = hop_weights(path, graph)
+        .into_iter()
+        .map(|weight| weight as f64 / WEIGHT_SCALE as f64)
+        .collect();
+    // an average walker does about 3 mph when --speed doesn't say
+    let pace = config.pace.unwrap_or(Pace {
+        mph: 3.0,
+        minutes_per_door: 0.0,
+    });
+    let minutes = hop_minutes(path, graph, doors, &pace);
+    let shifts = match limit {
+        ShiftLimit::Distance(length) => {
+            let cap = match config.units {
+                Distance::Miles => length * 5280.0,
+                Distance::Kilometers => length * 1000.0 / 0.3048,
+            };
+            cut_route(&feet, cap)
+        }
+        ShiftLimit::Minutes(cap) => cut_route(&minutes, *cap),
+    };
+    Some(ShiftCuts {
+        feet,
+        minutes,
+        shifts,
+    })
+}
+
+fn restart_cost(
+    path: &[usize],
+    graph: &RoadGraph,
+    streets: &Streets,
+    coords: &[Option<(f64, f64)>],
+    config: &Config,
+) -> (usize, f64) {
+    // how well a circuit does by --restart-by, lower being better. shift balance is (shifts, spread), so a circuit
+    // needing fewer shifts wins outright, and the others are (0, cost)
+    let by = config
+        .restart_by
+        .unwrap_or(match (streets.early.is_empty(), &config.shift_limit) {
+            (false, _) => RestartCriterion::EarlyStreets,
+            (true, Some(_)) => RestartCriterion::ShiftBalance,
+            (true, None) => RestartCriterion::Turns,
+        });
+    match by {
+        RestartCriterion::Turns => {
+            // turn_score is higher the straighter, so its negative adds up the turning
+            let turns = turn_score(coords);
+            let turning: i64 = path
+                .windows(3)
+                .map(|hops| {
+                    -turns(&HopContext {
+                        from: hops[1],
+                        to: hops[2],
+                        previous: Some(hops[0]),
+                        candidate: 0,
+                    })
+                })
+                .sum();
+            (0, turning as f64)
+        }
+        RestartCriterion::EarlyStreets => {
+            let weights = walked_weights(path, graph, &streets.costs);
+            let reached = early_reach(path, &weights, &streets.early);
+            (0, reached.last().map_or(0, |(_, walked)| *walked) as f64)
+        }
+        RestartCriterion::ShiftBalance => {
+            let Some(cuts) = shift_cuts(path, graph, &streets.doors, config) else {
+                return (0, 0.0);
+            };
+            let cut = match config.shift_limit {
+                Some(ShiftLimit::Minutes(_)) => &cuts.minutes,
+                _ => &cuts.feet,
+            };
+            let totals: Vec<f64> = cuts
+                .shifts
+                .iter()
+                .map(|(first, last)| cut[*first..*last].iter().sum())
+                .collect();
+            let longest = totals.iter().copied().fold(0.0, f64::max);
+            let shortest = totals.iter().copied().fold(f64::INFINITY, f64::min);
+            (cuts.shifts.len(), longest - shortest)
+        }
+    }
+}
+
+fn route_directed(
+    graph: &DirectedRoadGraph,
+    config: &Config,
+    streets: &Streets,
+    labels: &BTreeMap<usize, String>,
+    report: &mut Report,
+) -> Result<Route, PacsamError> {
+    // route for a map with one-way streets, or for --both-sides. graph is where the walk can go, with a two-way
+    // street as an arc each way. a one-way street only ever goes the way it does, and each two-way street is
+    // walked once whichever way suits (see orient_mixed), except with --both-sides where it's walked once each way
+    let Streets {
+        names,
+        optional,
+        doors,
+        early,
+        copies,
+        two_way,
+        ..
+    } = streets;
+    let ignored = [
+        ("optional streets", !optional.is_empty()),
+        ("early streets", !early.is_empty()),
+        ("--open", config.open.is_some()),
+        ("--walkers", config.walkers.is_some()),
+        ("--restarts", config.restarts > 1),
+        ("--max-traversal", config.max_traversal.is_some()),
+        ("--dump-eulerized", config.dump_eulerized.is_some()),
+    ];
+    for (option, _) in ignored.iter().filter(|(_, used)| *used) {
+        report.warnings.push(format!(
+            "warning: {option} isn't supported with one-way streets or --both-sides and is ignored"
+        ));
+    }
+    let travel = graph;
+    let oriented = (!two_way.is_empty()).then(|| orient_mixed(travel, two_way));
+    // the arcs that have to be walked, which the repeats get added to
+    let graph = oriented.as_ref().unwrap_or(travel);
+    let base: usize = arc_list(graph).iter().map(|(_, _, weight)| weight).sum();
+    let distinct_edges = graph.edge_count();
+    let Some(first) = (0..graph.node_count()).find(|node| graph.out_degree(*node) > 0) else {
+        let summary = RouteSummary {
+            base_feet: 0.0,
+            total_feet: 0.0,
+            distinct_edges: 0,
+            total_traversals: 0,
+            repeats: 0,
+            doors: 0,
+            approach_feet: None,
+        };
+        report
+            .lines
+            .extend(summary_lines(&summary, &[], &[], &[], config, labels, ""));
+        return Ok(Route::default());
+    };
+    let start = match config.start.as_deref() {
+        Some(name) => resolve_node(name, labels)?,
+        None => first,
+    };
+    let criterion = start_criterion(config, labels)?;
+    if let Some(node) = std::iter::once(start)
+        .chain(criterion.iter().flat_map(StartCriterion::nodes))
+        .find(|node| *node >= travel.node_count() || travel.out_degree(*node) == 0)
+    {
+        return Err(PacsamError::NoSuchNode(node));
+    }
+    let traps = one_way_traps(travel, start);
+    if !traps.is_empty() {
+        return Err(PacsamError::OneWayTrap(traps));
+    }
+    balance_directed_over(graph, travel);
+    let coords = turn_coords(config)?;
+    let path = if coords.is_empty() {
+        find_directed_cycle_seeded(graph, start, config.seed, |_| 0)?
+    } else {
+        find_directed_cycle_seeded(graph, start, config.seed, turn_score(&coords))?
+    };
+    // the undirected view of what gets walked, for the helpers that look up a hop's length
+    let walked: RoadGraph = GraphBuilder::new()
+        .edges_with_values(arc_list(graph))
+        .build();
+    let path = start_where(path, &walked, criterion.as_ref());
+    // copies are by arc only with --both-sides, and a one-way street's arc has just the one way to be walked anyway
+    let repeats = repeat_hops(&path, copies, config.both_sides);
+    let summary = RouteSummary {
+        base_feet: base as f64 / WEIGHT_SCALE as f64,
+        total_feet: total_feet(&walked),
+        distinct_edges,
+        total_traversals: graph.edge_count(),
+        repeats: repeats.iter().filter(|repeat| **repeat).count(),
+        doors: hop_doors(&path, doors).iter().sum(),
+        approach_feet: None,
+    };
+    let weights = hop_weights(&path, &walked);
+    report.lines.extend(summary_lines(
+        &summary,
+        &path,
+        &weights,
+        &repeats,
+        config,
+        labels,
+        &shifts_json(&path, &walked, doors, config, labels),
+    ));
+    let minutes = report_estimate(&path, &walked, doors, config, report);
+    if let Some(out_path) = &config.output_path {
+        write_result(&path, &walked, labels, out_path, &config.units)?;
+    }
+    if config.turn_by_turn {
+        let steps = turn_by_turn(
+            &path,
+            &walked,
+            names,
+            labels,
+            &coords,
+            &minutes,
+            config.both_sides,
+        );
+        for step in steps {
+            report.lines.push(step);
+        }
+    }
+    if let OutputFormat::Text = config.output_format {
+        report_shifts(&path, &walked, doors, config, labels, report);
+    }
+    Ok(Route::walked(path, &weights, repeats))
+}
+
+pub fn solve(file_path: &str) -> Result<Route, PacsamError> {
+    // the route the route command finds for a map file, one-way and optional streets, header and format all read
+    // the same way, for callers that want the result rather than what the command prints
+    let mut report = Report::default();
+    run_into(route_config(file_path), &mut report)?;
+    Ok(report.route)
+}
+
+fn route_config(file_path: &str) -> Config {
+    // the route command for file_path with every option left as it comes. the path is put in afterwards so one
+    // that looks like an option isn't read as one
+    let args = ["pacsam", "route", "map"].map(String::from);
+    let mut config = Config::build(args.into_iter()).expect("a map path alone is a route command");
+    config.file_path = file_path.to_string();
+    config
+}
+
+pub fn solve_all(
+    inputs: impl IntoIterator<Item = (String, String)>,
+) -> Vec<(String, Result<Route, PacsamError>)> {
+    // the route for each (name, map file contents), like solve, handed back by name in the order they came in. the
+    // name only shows in the log, so it needn't be a file. the maps are independent of each other, so with the
+    // parallel feature they're solved on rayon's thread pool
+    let inputs: Vec<(String, String)> = inputs.into_iter().collect();
+    let solve_one = |(name, contents): &(String, String)| {
+        let mut report = Report::default();
+        let route = run_map(
+            route_config(name),
+            (contents.clone(), None),
+            Stopwatch::start(),
+            &mut report,
+        );
+        (name.clone(), route.map(|_| report.route))
+    };
+    #[cfg(feature = "parallel")]
+    let inputs_iter = inputs.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let inputs_iter = inputs.iter();
+    inputs_iter.map(solve_one).collect()
+}
+
+fn solve_dir(
+    dir: &str,
+    output_dir: Option<&str>,
+    units: &Distance,
+    report: &mut Report,
+) -> Result<(), PacsamError> {
+    // solve-all: every file in dir, in name order, skipping hidden files and routes an earlier run wrote there.
+    // each route goes to NAME.route.txt like write_result writes it, then a table of the lot goes to stdout. a
+    // map that fails is reported in the table and the rest still get solved, and the first failure is returned
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    files.retain(|file| {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        file.is_file() && !name.starts_with('.') && !name.ends_with(".route.txt")
+    });
+    files.sort();
+    let mut inputs = vec![];
+    for file in &files {
+        let name = file
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        inputs.push((name, fs::read_to_string(file)?));
+    }
+    let labels: Vec<BTreeMap<usize, String>> = inputs
+        .iter()
+        .map(|(_, contents)| file_labels(contents))
+        .collect();
+    // turfs get compared by how many doors they reach for the walking too, when any of them counts its doors
+    let doors: Vec<BTreeMap<(usize, usize), usize>> = inputs
+        .iter()
+        .map(|(_, contents)| file_doors(contents))
+        .collect();
+    let with_doors = doors.iter().any(|doors| !doors.is_empty());
+    let output_dir = Path::new(output_dir.unwrap_or(dir));
+    let suffix = distance_suffix(units);
+    let width = inputs
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(["turf".len()])
+        .max()
+        .unwrap_or_default();
+    let per = format!("doors/{suffix}");
+    match with_doors {
+        true => report.lines.push(format!(
+            "{:<width$}  {:>10}  {:>9}  {:>6}  {:>8}",
+            "turf", suffix, "re-walked", "doors", per
+        )),
+        false => report.lines.push(format!(
+            "{:<width$}  {:>10}  {:>9}",
+            "turf", suffix, "re-walked"
+        )),
+    }
+    let mut total_feet = 0.0;
+    let mut total_street_feet = 0.0;
+    let mut total_rewalked = 0;
+    let mut total_doors = 0;
+    let mut first_error = None;
+    for (((name, route), labels), doors) in solve_all(inputs).into_iter().zip(labels).zip(doors) {
+        let route = match route {
+            Ok(route) => route,
+            Err(e) => {
+                report.lines.push(format!("{name:<width$}  failed: {e}"));
+                first_error.get_or_insert(e);
+                continue;
+            }
+        };
+        let stem = name
+            .rsplit_once('.')
+            .map_or(name.as_str(), |(stem, _)| stem);
+        fs::write(
+            output_dir.join(format!("{stem}.route.txt")),
+            format!(
+                "{}\n{} {suffix}\n",
+                label_nodes(&route.path, &labels),
+                convert_length(route.total_feet, &Unit::Feet, units)
+            ),
+        )?;
+        // the streets themselves are the hops that aren't repeats
+        let street_feet = route
+            .edges
+            .iter()
+            .zip(&route.repeats)
+            .filter(|(_, repeat)| !**repeat)
+            .map(|((_, _, weight), _)| *weight as f64 / WEIGHT_SCALE as f64)
+            .sum::<f64>();
+        let reached: usize = hop_doors(&route.path, &doors).iter().sum();
+        let mileage = format!(
+            "{name:<width$}  {:>10.2}  {:>9}",
+            convert_length(route.total_feet, &Unit::Feet, units),
+            route.duplicated_edge_count
+        );
+        match with_doors {
+            true => report.lines.push(format!(
+                "{mileage}  {reached:>6}  {:>8.1}",
+                doors_per(reached, street_feet, units)
+            )),
+            false => report.lines.push(mileage),
+        }
+        total_feet += route.total_feet;
+        total_street_feet += street_feet;
+        total_rewalked += route.duplicated_edge_count;
+        total_doors += reached;
+    }
+    let mileage = format!(
+        "{:<width$}  {:>10.2}  {:>9}",
+        "total",
+        convert_length(total_feet, &Unit::Feet, units),
+        total_rewalked
+    );
+    match with_doors {
+        true => report.lines.push(format!(
+            "{mileage}  {total_doors:>6}  {:>8.1}",
+            doors_per(total_doors, total_street_feet, units)
+        )),
+        false => report.lines.push(mileage),
+    }
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+fn file_doors(contents: &str) -> BTreeMap<(usize, usize), usize> {
+    // the door counts in an adjacency or edge list map file, keyed like street_names
+    let (_, contents) = split_header(contents);
+    if contents.contains(':') {
+        street_doors(contents)
+    } else {
+        edge_list_doors(contents)
+    }
+}
+
+fn file_labels(contents: &str) -> BTreeMap<usize, String> {
+    // the node names in an adjacency or edge list map file
+    let (_, contents) = split_header(contents);
+    if contents.contains(':') {
+        node_labels(contents)
+    } else {
+        edge_list_labels(contents)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Route {
+    // the circuit as node indices, ending where it starts
+    pub path: Vec<usize>,
+    // each hop of path as (from, to, weight), with the weight in hundredths of a foot like the graph stores it
+    pub edges: Vec<(usize, usize, usize)>,
+    pub total_feet: f64,
+    pub total_miles: f64,
+    // the streets driven a second time, cul de sac returns included
+    pub duplicated_edge_count: usize,
+    // for each of edges, whether it goes over a street again rather than covering it (see repeat_hops)
+    pub repeats: Vec<bool>,
+}
+
+impl Route {
+    fn walked(path: Vec<usize>, weights: &[usize], repeats: Vec<bool>) -> Route {
+        // a route the route command worked out, with weights the stored length of each hop of path
+        let total_feet =
+            weights.iter().map(|weight| *weight as u128).sum::<u128>() as f64 / WEIGHT_SCALE as f64;
+        Route {
+            edges: path
+                .windows(2)
+                .zip(weights)
+                .map(|(hop, weight)| (hop[0], hop[1], *weight))
+                .collect(),
+            total_feet,
+            total_miles: feet_to_miles(total_feet),
+            duplicated_edge_count: repeats.iter().filter(|repeat| **repeat).count(),
+            path,
+            repeats,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        // lengths in feet, the way the struct holds them
+        let nodes: Vec<String> = self.path.iter().map(|node| node.to_string()).collect();
+        let edges: Vec<String> = self
+            .edges
+            .iter()
+            .zip(&self.repeats)
+            .map(|((from, to, weight), repeat)| {
+                format!(
+                    "{{\"from\":{from},\"to\":{to},\"feet\":{},\"repeat\":{repeat}}}",
+                    format_weight(*weight)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"path\":[{}],\"edges\":[{}],\"total_feet\":{},\"total_miles\":{},\"duplicated_edge_count\":{}}}",
+            nodes.join(","),
+            edges.join(","),
+            self.total_feet,
+            self.total_miles,
+            self.duplicated_edge_count
+        )
+    }
+
+    pub fn from_json(input: &str) -> Result<Route, PacsamError> {
+        // reads back what to_json wrote, so a solved route can be cached on disk
+        let json = parse_json(input)?;
+        let mut edges = vec![];
+        let mut repeats = vec![];
+        for edge in json.field("edges")?.items("edges")? {
+            edges.push((
+                edge.field("from")?.index("from")?,
+                edge.field("to")?.index("to")?,
+                edge.field("feet")?.weight("feet")?,
+            ));
+            // routes cached before repeats were marked don't have them, and nothing in those is marked
+            repeats.push(match edge.field("repeat") {
+                Ok(repeat) => repeat.flag("repeat")?,
+                Err(_) => false,
+            });
+        }
+        Ok(Route {
+            path: json
+                .field("path")?
+                .items("path")?
+                .iter()
+                .map(|node| node.index("path"))
+                .collect::<Result<_, _>>()?,
+            edges,
+            total_feet: json.field("total_feet")?.number("total_feet")?,
+            total_miles: json.field("total_miles")?.number("total_miles")?,
+            duplicated_edge_count: json
+                .field("duplicated_edge_count")?
+                .index("duplicated_edge_count")?,
+            repeats,
+        })
+    }
+
+    pub fn to_gpx(&self, coords: &[Option<(f64, f64)>]) -> String {
+        to_gpx(&self.path, coords, &BTreeMap::new())
+    }
+
+    pub fn to_geojson(&self, coords: &[Option<(f64, f64)>]) -> String {
+        route_geojson(
+            &self.path,
+            coords,
+            &BTreeMap::new(),
+            &self.repeats,
+            &BTreeMap::new(),
+            None,
+        )
+    }
+}
+
+pub struct Solver {
+    graph: RoadGraph,
+    // all_pairs_distances of graph, worked out by the first route and then kept up to date by add_edge and
+    // remove_edge, so re-solving after a small edit doesn't start the distances over
+    distances: OnceCell<Vec<Vec<usize>>>,
+    objective: ObjectiveKind,
+    seed: Option<u64>,
+    // what the streets walked again are picked to keep down, when it isn't their length
+    cost: Option<Box<dyn EdgeCost>>,
+}
+
+fn solver_supports(contents: &str) -> Result<(), PacsamError> {
+    // Solver only holds a map of two-way streets that all have to be covered. anything in the file that would
+    // route differently is an error rather than being dropped, and solve is there for those maps
+    let avoid = avoid_directives(contents)?;
+    let (_, body) = split_header(contents);
+    let tokens = || body.lines().flat_map(|line| line.split(','));
+    let unsupported = [
+        ("one-way streets", tokens().any(is_one_way)),
+        ("optional streets", tokens().any(is_optional)),
+        ("early streets", !early_streets(body).is_empty()),
+        (
+            "#avoid lines",
+            !avoid.nodes.is_empty() || !avoid.streets.is_empty(),
+        ),
+        (
+            "#connector lines",
+            !connector_directives(contents)?.is_empty(),
+        ),
+        (
+            "OpenStreetMap, GeoJSON or DOT maps",
+            body.contains("<osm") || body.trim_start().starts_with('{') || is_dot(body),
+        ),
+    ];
+    match unsupported.into_iter().find(|(_, found)| *found) {
+        Some((what, _)) => Err(PacsamError::Unsupported(what)),
+        None => Ok(()),
+    }
+}
+
+impl Solver {
+    pub fn from_graph(graph: RoadGraph) -> Self {
+        Solver {
+            graph,
+            distances: OnceCell::new(),
+            objective: ObjectiveKind::MinDistance,
+            seed: None,
+            cost: None,
+        }
+    }
+    pub fn with_objective(mut self, objective: ObjectiveKind) -> Self {
+        self.objective = objective;
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        // ties between equally good routes are broken at random for seed instead of always the same way, like
+        // --seed. solving again with other seeds is how to look for a route that suits better at the same length
+        self.seed = Some(seed);
+        self
+    }
+    pub fn with_cost(mut self, cost: impl EdgeCost + 'static) -> Self {
+        // eulerizes by cost rather than the stored lengths, like TimeCost to keep the minutes down. the route is
+        // still measured in feet
+        self.cost = Some(Box::new(cost));
+        self
+    }
+    pub fn from_file(path: &str) -> Result<Self, PacsamError> {
+        // parses once, for trying several solves against the same map without rereading it. a streamed map has
+        // none of the markers, but its header can still ask for something
+        match read_map_file(path, None, &WeightFormat::Feet)? {
+            (header, Some(map)) => {
+                solver_supports(&header)?;
+                Ok(Solver::from_graph(map.graph))
+            }
+            (contents, None) => Self::from_contents(&contents),
+        }
+    }
+    pub fn from_contents(contents: &str) -> Result<Self, PacsamError> {
+        // a map file already read into memory, in the adjacency or edge list format
+        solver_supports(contents)?;
+        let (unit, contents) = units_directive(contents)?;
+        let unit = unit.unwrap_or(Unit::Feet);
+        check_indices(contents)?;
+        let graph = if contents.contains(':') {
+            build_graph_in(without_doors(contents), &unit)?
+        } else {
+            scaled_to_feet(build_graph_edge_list(contents.to_string())?, &unit)
+        };
+        Ok(Solver::from_graph(graph))
+    }
+    pub fn to_json(&self) -> String {
+        // the parsed map, for caching one that's slow to read, like a big OpenStreetMap extract. lengths are in
+        // feet like Route::to_json, and nodes counts the ones without streets too so the indices keep lining up
+        let edges: Vec<String> = edge_list(&self.graph)
+            .iter()
+            .map(|(from, to, weight)| {
+                format!(
+                    "{{\"from\":{from},\"to\":{to},\"feet\":{}}}",
+                    format_weight(*weight)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"nodes\":{},\"edges\":[{}]}}",
+            self.graph.node_count(),
+            edges.join(",")
+        )
+    }
+    pub fn from_json(input: &str) -> Result<Self, PacsamError> {
+        let json = parse_json(input)?;
+        let mut edges = vec![];
+        for edge in json.field("edges")?.items("edges")? {
+            edges.push((
+                edge.field("from")?.index("from")?,
+                edge.field("to")?.index("to")?,
+                edge.field("feet")?.weight("feet")?,
+            ));
+        }
+        let nodes = json.field("nodes")?.index("nodes")?;
+        Ok(Solver::from_graph(graph_with_nodes(edges, nodes)))
+    }
+    pub fn solve(&self) -> Result<Vec<usize>, PacsamError> {
+        self.route().map(|route| route.path)
+    }
+    pub fn route(&self) -> Result<Route, PacsamError> {
+        // eulerize adds its duplicate streets to whatever graph it's handed, so each solve works on its own copy.
+        // otherwise every call would pile more duplicates onto the last one's
+        let graph = match &self.cost {
+            Some(cost) => with_cost(&self.graph, cost.as_ref()),
+            None => copy_graph(&self.graph),
+        };
+        if graph.edge_count() == 0 {
+            return Ok(Route {
+                path: vec![],
+                edges: vec![],
+                total_feet: 0.0,
+                total_miles: 0.0,
+                duplicated_edge_count: 0,
+                repeats: vec![],
+            });
+        }
+        if !is_connected(&graph) {
+            return Err(PacsamError::Disconnected(connected_components(&graph)));
+        }
+        fix_culdesacs(&graph, &BoundaryPolicy::TreatAsCuldesac);
+        // the cul de sac returns are copies of streets already there, so they don't change any distance. the kept
+        // distances are lengths, so counting streets works its own out each time
+        let odd = shuffled(odd_degree_nodes(&graph), self.seed);
+        match self.objective {
+            ObjectiveKind::MinDistance if self.cost.is_none() => {
+                let distances = self
+                    .distances
+                    .get_or_init(|| all_pairs_distances(&self.graph));
+                duplicate_paths_using(&graph, &graph, &odd, distances);
+            }
+            objective => {
+                duplicate_paths_for(&graph, &graph, &odd, objective, &[]);
+            }
+        }
+        // node 0 can have no streets at all, so the circuit starts at the first node that has some, as in route()
+        let start = (0..graph.node_count())
+            .find(|node| graph.degree(*node) > 0)
+            .unwrap_or(0);
+        let path = find_cycle_seeded(&graph, start, self.seed, |_| 0)?;
+        // with a cost the graph's weights aren't lengths, so the route is measured on the map, each of several
+        // streets between the same corners walked in turn the way audit_walk does
+        let (measured, feet) = match &self.cost {
+            Some(_) => (
+                &self.graph,
+                walk_streets(&self.graph, &path).weight as f64 / WEIGHT_SCALE as f64,
+            ),
+            None => (&graph, total_feet(&graph)),
+        };
+        let edges = path
+            .windows(2)
+            .zip(hop_weights(&path, measured))
+            .map(|(hop, weight)| (hop[0], hop[1], weight))
+            .collect();
+        Ok(Route {
+            total_feet: feet,
+            total_miles: feet_to_miles(feet),
+            duplicated_edge_count: graph.edge_count() - self.graph.edge_count(),
+            repeats: repeat_hops(&path, &street_copies(&self.graph), false),
+            path,
+            edges,
+        })
+    }
+    pub fn length(&self, path: &[usize]) -> f64 {
+        // every street a solved path walks is in the parsed graph, duplicates being copies of an original
+        length(path, &self.graph, &Unit::Feet, &Distance::Miles)
+    }
+    pub fn add_edge(&mut self, a: usize, b: usize, length: f64) -> Result<(), PacsamError> {
+        // a new street of length feet, like an alley that was missing from the map. either end can be the next
+        // node index past the end, for a street out to somewhere new
+        let node_count = self.graph.node_count();
+        if let Some(node) = [a, b].into_iter().find(|node| *node > node_count) {
+            return Err(PacsamError::NoSuchNode(node));
+        }
+        if !(length.is_finite() && length >= 0.0) {
+            return Err(PacsamError::BadLength(length));
+        }
+        let weight = (length * WEIGHT_SCALE as f64).round() as usize;
+        let mut edges = edge_list(&self.graph);
+        edges.push((a, b, weight));
+        self.graph = graph_with_nodes(edges, node_count.max(a + 1).max(b + 1));
+        // a new node starts out only reaching itself. then any distance the street shortens goes through it one way
+        // or the other
+        if let Some(distances) = self.distances.get_mut() {
+            let n = self.graph.node_count();
+            for (node, row) in distances.iter_mut().enumerate() {
+                row.resize(n, usize::MAX);
+                row[node] = 0;
+            }
+            while distances.len() < n {
+                let mut row = vec![usize::MAX; n];
+                row[distances.len()] = 0;
+                distances.push(row);
+            }
+            let (to_a, to_b): (Vec<usize>, Vec<usize>) =
+                distances.iter().map(|row| (row[a], row[b])).unzip();
+            for (row, (from_a, from_b)) in distances.iter_mut().zip(to_a.iter().zip(&to_b)) {
+                for (distance, (a_to, b_to)) in row.iter_mut().zip(to_a.iter().zip(&to_b)) {
+                    let via_ab = from_a.saturating_add(weight).saturating_add(*b_to);
+                    let via_ba = from_b.saturating_add(weight).saturating_add(*a_to);
+                    *distance = (*distance).min(via_ab).min(via_ba);
+                }
+            }
+        }
+        Ok(())
+    }
+    pub fn remove_edge(&mut self, a: usize, b: usize) -> Result<(), PacsamError> {
+        // takes out the street from a to b, like one that's closed. with parallel streets between them only the
+        // longest goes, so a shorter one left behind means no distance changes
+        let mut edges = edge_list(&self.graph);
+        let removed = edges
+            .iter()
+            .enumerate()
+            .filter(|(_, (u, v, _))| (*u, *v) == (a.min(b), a.max(b)))
+            .max_by_key(|(_, (_, _, weight))| *weight)
+            .map(|(i, _)| i)
+            .ok_or(PacsamError::NoSuchStreet(a, b))?;
+        let (_, _, weight) = edges.remove(removed);
+        let node_count = self.graph.node_count();
+        self.graph = graph_with_nodes(edges, node_count);
+        let Some(distances) = self.distances.get_mut() else {
+            return Ok(());
+        };
+        if neighbors_vec(&self.graph, a)
+            .iter()
+            .any(|(node, other)| *node == b && *other <= weight)
+        {
+            return Ok(());
+        }
+        // only a node whose shortest paths could cross the street has distances that change, and those are the
+        // ones where it's exactly as far to one end as to the other end and along it. everything else stays put
+        let stale: Vec<usize> = (0..node_count)
+            .filter(|node| {
+                let (to_a, to_b) = (distances[*node][a], distances[*node][b]);
+                to_a != usize::MAX
+                    && (to_a.saturating_add(weight) == to_b || to_b.saturating_add(weight) == to_a)
+            })
+            .collect();
+        for node in stale {
+            let row = &all_pairs_row(&self.graph, node);
+            for (other, distance) in row.iter().enumerate() {
+                distances[node][other] = *distance;
+                distances[other][node] = *distance;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct NeighborhoodGraph {
+    // a map put together in memory rather than read from a file. nodes are numbered in the order they're added,
+    // which is what the route's path is in
+    labels: Vec<String>,
+    edges: Vec<(usize, usize, usize)>,
+}
+
+impl NeighborhoodGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add_node(&mut self, label: &str) -> usize {
+        self.labels.push(label.to_string());
+        self.labels.len() - 1
+    }
+    pub fn add_edge(&mut self, a: usize, b: usize, length: f64) -> Result<(), PacsamError> {
+        // a street between two added nodes, its length in feet
+        if let Some(node) = [a, b].into_iter().find(|node| *node >= self.labels.len()) {
+            return Err(PacsamError::NoSuchNode(node));
+        }
+        if !(length.is_finite() && length >= 0.0) {
+            return Err(PacsamError::BadLength(length));
+        }
+        let weight = (length * WEIGHT_SCALE as f64).round() as usize;
+        self.edges.push((a, b, weight));
+        Ok(())
+    }
+    pub fn node(&self, label: &str) -> Option<usize> {
+        // the first node added with this label
+        self.labels.iter().position(|other| other == label)
+    }
+    pub fn label(&self, node: usize) -> Option<&str> {
+        self.labels.get(node).map(String::as_str)
+    }
+    pub fn solver(&self) -> Solver {
+        Solver::from_graph(graph_with_nodes(self.edges.clone(), self.labels.len()))
+    }
+    pub fn solve(&self) -> Result<Route, PacsamError> {
+        self.solver().route()
+    }
+}
+
+#[derive(Debug)]
+pub enum PacsamError {
+    // the input file or an output file couldn't be read or written
+    Io(std::io::Error),
+    // a token in the input file that doesn't fit the format, with where it is (1-based) and what should have
+    // been there
+    Parse {
+        line: usize,
+        column: usize,
+        token: String,
+        expected: &'static str,
+    },
+    // a node index too far past the end of the file to be anything but a typo
+    IndexOutOfRange {
+        line: usize,
+        index: usize,
+    },
+    // every separate piece of the network, by its nodes
+    Disconnected(Vec<Vec<usize>>),
+    // the route would walk a street more often than --max-traversal allows
+    TraversalLimitExceeded {
+        edge: (usize, usize),
+        count: usize,
+        limit: usize,
+    },
+    // eulerization left the graph without a circuit, which means a bug rather than bad input
+    NoEulerCircuit,
+    // find_cycle was given a graph no circuit covers: the nodes left with odd degree, or when there are none, how
+    // many streets are in pieces the walk from the start can't reach
+    NotEulerizable {
+        odd_nodes: Vec<usize>,
+        unreached: usize,
+    },
+    // a node asked for by index, like an --open endpoint, that no street reaches
+    NoSuchNode(usize),
+    // a node named on the command line that isn't an index, letters or a name from the input
+    UnknownNode(String),
+    // a street length handed to NeighborhoodGraph that's negative or not a number
+    BadLength(f64),
+    // Solver::remove_edge was asked for a street between two nodes that don't have one
+    NoSuchStreet(usize, usize),
+    // nodes that one-way streets make impossible to get to, or to get back from, starting at the first node
+    OneWayTrap(Vec<usize>),
+    // more than one problem with the input, each one a Parse, and how many more there were past the ones kept
+    Many {
+        errors: Vec<PacsamError>,
+        unlisted: usize,
+    },
+    // pinned stretches that between them go down a street more times than the route they're pinned in does
+    OverPinned {
+        street: (usize, usize),
+        walked: usize,
+    },
+    // something in a map file that Solver can't route the way the route command would
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for PacsamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PacsamError::Io(e) => write!(f, "{e}"),
+            PacsamError::Parse {
+                line,
+                column,
+                token,
+                expected,
+            } => write!(
+                f,
+                "line {line}, column {column}: could not parse '{token}', expected {expected}"
+            ),
+            PacsamError::IndexOutOfRange { line, index } => write!(
+                f,
+                "line {line}: node index {index} is too large for a file this size \
+                 (pass --allow-sparse-indices if the gaps are intended)"
+            ),
+            PacsamError::Disconnected(components) => {
+                let components: Vec<String> = components
+                    .iter()
+                    .map(|nodes| {
+                        let nodes: Vec<String> =
+                            nodes.iter().map(|node| node.to_string()).collect();
+                        format!("{{{}}}", nodes.join(", "))
+                    })
+                    .collect();
+                write!(
+                    f,
+                    "graph is not connected, it splits into {} pieces: {} \
+                     (pass --per-component to route each piece on its own)",
+                    components.len(),
+                    components.join(", ")
+                )
+            }
+            PacsamError::TraversalLimitExceeded {
+                edge: (u, v),
+                count,
+                limit,
+            } => write!(
+                f,
+                "street {u}-{v} would be traversed {count} times, over the limit of {limit}"
+            ),
+            PacsamError::NoEulerCircuit => write!(f, "no euler circuit exists for this graph"),
+            PacsamError::NotEulerizable {
+                odd_nodes,
+                unreached,
+            } => match odd_nodes.is_empty() {
+                true => write!(
+                    f,
+                    "no euler circuit: {unreached} streets are unreachable from the start"
+                ),
+                false => write!(
+                    f,
+                    "no euler circuit: nodes {odd_nodes:?} have odd degree, eulerize the graph first"
+                ),
+            },
+            PacsamError::NoSuchNode(node) => write!(f, "node {node} is not on any street"),
+            PacsamError::UnknownNode(name) => write!(f, "no node is called '{name}'"),
+            PacsamError::BadLength(length) => write!(f, "{length} is not a street length in feet"),
+            PacsamError::NoSuchStreet(a, b) => write!(f, "there's no street from {a} to {b}"),
+            PacsamError::OneWayTrap(nodes) => {
+                let nodes: Vec<String> = nodes.iter().map(|node| node.to_string()).collect();
+                write!(
+                    f,
+                    "one-way streets leave nodes {{{}}} impossible to reach or to leave",
+                    nodes.join(", ")
+                )
+            }
+            PacsamError::OverPinned {
+                street: (u, v),
+                walked,
+            } => write!(
+                f,
+                "the pins go down street {u}-{v} more than the {walked} times the route does"
+            ),
+            PacsamError::Unsupported(what) => write!(
+                f,
+                "Solver can't take {what}, solve routes them like the route command does"
+            ),
+            PacsamError::Many { errors, unlisted } => {
+                write!(f, "{} problems with the input:", errors.len() + unlisted)?;
+                for error in errors {
+                    write!(f, "\n  {error}")?;
+                }
+                match unlisted {
+                    0 => Ok(()),
+                    _ => write!(f, "\n  and {unlisted} more"),
+                }
+            }
+        }
+    }
+}
+
+// a file with something wrong on every line would print millions of them, and nobody reads past the first few
+const LISTED_ERRORS: usize = 50;
+
+#[derive(Default)]
+struct ParseErrors {
+    // the problems found in the input so far, for a parser that keeps going to report all of them at once
+    listed: Vec<PacsamError>,
+    unlisted: usize,
+}
+
+impl ParseErrors {
+    fn push(&mut self, error: PacsamError) {
+        match self.listed.len() < LISTED_ERRORS {
+            true => self.listed.push(error),
+            false => self.unlisted += 1,
+        }
+    }
+    fn into_result(mut self) -> Result<(), PacsamError> {
+        // one problem on its own is reported as itself
+        match (self.listed.len(), self.unlisted) {
+            (0, _) => Ok(()),
+            (1, 0) => Err(self.listed.remove(0)),
+            _ => Err(PacsamError::Many {
+                errors: self.listed,
+                unlisted: self.unlisted,
+            }),
+        }
+    }
+}
+
+impl Error for PacsamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PacsamError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PacsamError {
+    fn from(e: std::io::Error) -> Self {
+        PacsamError::Io(e)
+    }
+}
+
+pub fn write_result(
+    path: &[usize],
+    graph: &RoadGraph,
+    labels: &BTreeMap<usize, String>,
+    out_path: &str,
+    unit: &Distance,
+) -> Result<(), PacsamError> {
+    // the labelled route on the first line and its length in unit on the second, replacing whatever was in the file
+    let distance = length(path, graph, &Unit::Feet, unit);
+    fs::write(
+        out_path,
+        format!(
+            "{}\n{distance} {}\n",
+            label_nodes(path, labels),
+            distance_suffix(unit)
+        ),
+    )?;
+    Ok(())
+}
+
+fn summary_lines(
+    summary: &RouteSummary,
+    path: &[usize],
+    weights: &[usize],
+    repeats: &[bool],
+    config: &Config,
+    labels: &BTreeMap<usize, String>,
+    splits: &str,
+) -> Vec<String> {
+    // the route's summary, a line at a time. splits are more members for the json, like the walkers and shifts,
+    // which text shows after everything else
+    if let OutputFormat::Json = config.output_format {
+        return vec![summary_json(
+            summary, path, weights, repeats, config, labels, splits,
+        )];
+    }
+    let mut lines = vec![];
+    match config.weight_format {
+        WeightFormat::Feet => lines.push(summary.describe_in(&config.units)),
+        WeightFormat::Duration => lines.push(format!(
+            "base road network: {}, deadheading added: {}, total route: {}, efficiency: {:.1}, \
+             streets: {} distinct, {} traversals, {} repeats",
+            format_duration(summary.base_feet.round() as usize),
+            format_duration((summary.total_feet - summary.base_feet).round() as usize),
+            format_duration(summary.total_feet.round() as usize),
+            efficiency_score(summary),
+            summary.distinct_edges,
+            summary.total_traversals,
+            summary.repeats
+        )),
+    }
+    // only for maps that count their doors, and by length only when the weights are lengths
+    if summary.doors > 0 {
+        match config.weight_format {
+            WeightFormat::Feet => lines.push(format!(
+                "doors: {} ({:.1} per {suffix} of street, {:.1} per {suffix} walked)",
+                summary.doors,
+                doors_per(summary.doors, summary.base_feet, &config.units),
+                doors_per(summary.doors, summary.total_feet, &config.units),
+                suffix = distance_suffix(&config.units)
+            )),
+            WeightFormat::Duration => lines.push(format!("doors: {}", summary.doors)),
+        }
+    }
+    if let Some((out, back)) = summary.approach_feet {
+        let convert = |feet: f64| match config.weight_format {
+            WeightFormat::Feet => format!(
+                "{} {}",
+                convert_length(feet, &Unit::Feet, &config.units),
+                distance_suffix(&config.units)
+            ),
+            WeightFormat::Duration => format_duration(feet.round() as usize),
+        };
+        lines.push(format!(
+            "to and from the depot: {} out, {} back, {} on the turf",
+            convert(out),
+            convert(back),
+            convert(summary.total_feet - out - back)
+        ));
+    }
+    lines
+}
+
+pub enum Warning {
+    // a street with no length, usually a typo in the weight
+    ZeroWeightEdge(usize, usize),
+    // a street that starts and ends at the same intersection
+    SelfLoop(usize),
+    // an index with no streets, usually a gap in the numbering
+    IsolatedNode(usize),
+    // taking out the avoided streets left the map in this many pieces
+    AvoidDisconnects(usize),
+}
+
+impl Warning {
+    pub fn to_json(&self) -> String {
+        match self {
+            Warning::ZeroWeightEdge(u, v) => {
+                format!("{{\"kind\":\"ZeroWeightEdge\",\"edge\":[{u},{v}]}}")
+            }
+            Warning::SelfLoop(node) => format!("{{\"kind\":\"SelfLoop\",\"node\":{node}}}"),
+            Warning::IsolatedNode(node) => {
+                format!("{{\"kind\":\"IsolatedNode\",\"node\":{node}}}")
+            }
+            Warning::AvoidDisconnects(pieces) => {
+                format!("{{\"kind\":\"AvoidDisconnects\",\"pieces\":{pieces}}}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::ZeroWeightEdge(u, v) => write!(f, "street {u}-{v} has zero length"),
+            Warning::SelfLoop(node) => write!(f, "street at node {node} loops back on itself"),
+            Warning::IsolatedNode(node) => write!(f, "node {node} has no streets"),
+            Warning::AvoidDisconnects(pieces) => write!(
+                f,
+                "the avoided streets split the map into {pieces} pieces that one route can't join"
+            ),
+        }
+    }
+}
+
+pub fn input_warnings(graph: &RoadGraph) -> Vec<Warning> {
+    // things that parse fine but are probably mistakes in the input file
+    let mut warnings: Vec<Warning> = vec![];
+    // a file with no streets at all still builds a graph with a node 0, which isn't worth warning about
+    for node in 0..graph.node_count() {
+        if graph.degree(node) == 0 && graph.edge_count() > 0 {
+            warnings.push(Warning::IsolatedNode(node));
+        }
+    }
+    for (u, v, weight) in edge_list(graph) {
+        if u == v {
+            warnings.push(Warning::SelfLoop(u));
+        }
+        if weight == 0 {
+            warnings.push(Warning::ZeroWeightEdge(u, v));
+        }
+    }
+    warnings
+}
+
+pub struct Diagnosis {
+    // where in the file, both counted from 1
+    pub line: usize,
+    pub column: usize,
+    pub symptom: Symptom,
+}
+
+pub enum Symptom {
+    // the same street listed from both its ends with a different length each time
+    AsymmetricWeights {
+        street: (usize, usize),
+        weight: usize,
+        other_line: usize,
+        other_weight: usize,
+    },
+    // the same street listed again, which puts it in the map twice
+    DuplicateStreet {
+        street: (usize, usize),
+        other_line: usize,
+    },
+    ZeroLength(usize, usize),
+    SelfLoop(usize),
+    // many times the length of the typical street, often a weight with a digit too many
+    ImplausiblyLong {
+        street: (usize, usize),
+        weight: usize,
+        median: usize,
+    },
+    // a node without a line of its own, numbered past others that nothing mentions
+    UndefinedNode(usize),
+}
+
+impl Diagnosis {
+    pub fn shifted(self, lines: usize) -> Self {
+        // the same diagnosis with every line it mentions moved down by lines, for a file that has a header
+        let symptom = match self.symptom {
+            Symptom::AsymmetricWeights {
+                street,
+                weight,
+                other_line,
+                other_weight,
+            } => Symptom::AsymmetricWeights {
+                street,
+                weight,
+                other_line: other_line + lines,
+                other_weight,
+            },
+            Symptom::DuplicateStreet { street, other_line } => Symptom::DuplicateStreet {
+                street,
+                other_line: other_line + lines,
+            },
+            other => other,
+        };
+        Diagnosis {
+            line: self.line + lines,
+            column: self.column,
+            symptom,
+        }
+    }
+    pub fn to_json(&self) -> String {
+        let (kind, details) = match &self.symptom {
+            Symptom::AsymmetricWeights {
+                street: (u, v),
+                weight,
+                other_line,
+                other_weight,
+            } => (
+                "AsymmetricWeights",
+                format!(
+                    "\"edge\":[{u},{v}],\"feet\":{},\"other_line\":{other_line},\"other_feet\":{}",
+                    format_weight(*weight),
+                    format_weight(*other_weight)
+                ),
+            ),
+            Symptom::DuplicateStreet {
+                street: (u, v),
+                other_line,
+            } => (
+                "DuplicateStreet",
+                format!("\"edge\":[{u},{v}],\"other_line\":{other_line}"),
+            ),
+            Symptom::ZeroLength(u, v) => ("ZeroLength", format!("\"edge\":[{u},{v}]")),
+            Symptom::SelfLoop(node) => ("SelfLoop", format!("\"node\":{node}")),
+            Symptom::ImplausiblyLong {
+                street: (u, v),
+                weight,
+                median,
+            } => (
+                "ImplausiblyLong",
+                format!(
+                    "\"edge\":[{u},{v}],\"feet\":{},\"median_feet\":{}",
+                    format_weight(*weight),
+                    format_weight(*median)
+                ),
+            ),
+            Symptom::UndefinedNode(node) => ("UndefinedNode", format!("\"node\":{node}")),
+        };
+        format!(
+            "{{\"line\":{},\"column\":{},\"kind\":\"{kind}\",{details}}}",
+            self.line, self.column
+        )
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.symptom {
+            Symptom::AsymmetricWeights {
+                street: (u, v),
+                weight,
+                other_line,
+                other_weight,
+            } => write!(
+                f,
+                "street {u}-{v} is {} ft here but {} ft on line {other_line}, where it's listed from its other end",
+                format_weight(*weight),
+                format_weight(*other_weight)
+            ),
+            Symptom::DuplicateStreet {
+                street: (u, v),
+                other_line,
+            } => write!(
+                f,
+                "street {u}-{v} is already listed on line {other_line}, so the map has it twice"
+            ),
+            Symptom::ZeroLength(u, v) => write!(f, "street {u}-{v} has zero length"),
+            Symptom::SelfLoop(node) => write!(f, "street at node {node} loops back on itself"),
+            Symptom::ImplausiblyLong {
+                street: (u, v),
+                weight,
+                median,
+            } => write!(
+                f,
+                "street {u}-{v} is {} ft, over {LONG_STREET_FACTOR} times the typical street's {} ft",
+                format_weight(*weight),
+                format_weight(*median)
+            ),
+            Symptom::UndefinedNode(node) => write!(
+                f,
+                "node {node} has no line of its own and comes after numbers nothing mentions, so it may be a typo"
+            ),
+        }
+    }
+}
+
+// a street more than this many times the median length gets flagged by diagnose
+const LONG_STREET_FACTOR: usize = 10;
+
+pub fn diagnose(input: &str, unit: &Unit) -> Vec<Diagnosis> {
+    // what in an adjacency format map looks like a mistake even though it parses, with where each one is. lines
+    // are counted from the first after any header. input should already be through without_doors and
+    // without_early, like build_graph_in gets it, and tokens that don't parse are left to build_graph_in to report
+    let locations = node_locations(input).unwrap_or_default();
+    // (line, column, from, to, weight, one way), all counted from 0
+    let mut tokens: Vec<(usize, usize, usize, usize, usize, bool)> = vec![];
+    for (line_counter, line) in input.lines().enumerate() {
+        let mut offset = 0;
+        for token in line.split(',') {
+            let column = offset + token.len() - token.trim_start().len();
+            offset += token.len() + 1;
+            let edge = token.trim_end().trim_end_matches('*');
+            if edge.trim_start().starts_with(['@', '=']) {
+                continue;
+            }
+            let mut fields = edge.split(':');
+            let Ok(vertex) = fields.next().unwrap_or_default().trim().parse::<usize>() else {
+                continue;
+            };
+            let Some(field) = fields.next() else {
+                continue;
+            };
+            let Some((there, back)) = edge_weight(field, &locations, line_counter, vertex, unit)
+            else {
+                continue;
+            };
+            tokens.push((
+                line_counter,
+                column,
+                line_counter,
+                vertex,
+                (there + back) / 2,
+                is_one_way(token),
+            ));
+        }
+    }
+    let mut weights: Vec<usize> = tokens.iter().map(|token| token.4).collect();
+    weights.sort_unstable();
+    let median = weights.get(weights.len() / 2).copied().unwrap_or(0);
+    let mut diagnoses: Vec<Diagnosis> = vec![];
+    let mut found = |line: usize, column: usize, symptom: Symptom| {
+        diagnoses.push(Diagnosis {
+            line: line + 1,
+            column: column + 1,
+            symptom,
+        })
+    };
+    // a two-way street is the same whichever end lists it, a one-way one only the way it goes
+    let mut listed: BTreeMap<(usize, usize, bool), Vec<usize>> = BTreeMap::new();
+    for (index, (line, column, from, to, weight, one_way)) in tokens.iter().copied().enumerate() {
+        let street = (from.min(to), from.max(to));
+        if from == to {
+            found(line, column, Symptom::SelfLoop(from));
+        }
+        if weight == 0 {
+            found(line, column, Symptom::ZeroLength(street.0, street.1));
+        }
+        if median > 0 && weight > LONG_STREET_FACTOR * median {
+            found(
+                line,
+                column,
+                Symptom::ImplausiblyLong {
+                    street,
+                    weight,
+                    median,
+                },
+            );
+        }
+        let key = match one_way {
+            true => (from, to, true),
+            false => (street.0, street.1, false),
+        };
+        let earlier = listed.entry(key).or_default();
+        // from its other end it's a duplicate either way, but twice on the same line with different lengths is
+        // more likely two different streets between the same corners
+        let other_end = earlier
+            .iter()
+            .map(|k| tokens[*k])
+            .find(|other| other.2 != from);
+        let same_line = earlier
+            .iter()
+            .map(|k| tokens[*k])
+            .find(|other| other.2 == from && other.4 == weight);
+        match (other_end, same_line) {
+            (Some(other), _) if other.4 != weight => found(
+                line,
+                column,
+                Symptom::AsymmetricWeights {
+                    street,
+                    weight,
+                    other_line: other.0 + 1,
+                    other_weight: other.4,
+                },
+            ),
+            (Some(other), _) | (None, Some(other)) => found(
+                line,
+                column,
+                Symptom::DuplicateStreet {
+                    street,
+                    other_line: other.0 + 1,
+                },
+            ),
+            (None, None) => {}
+        }
+        earlier.push(index);
+    }
+    // nodes past the last line are fine as long as they carry on the numbering from it, like the last node in a
+    // file whose streets were all listed from the other end. anything past a gap is likely a mistyped index
+    let line_count = input.lines().count();
+    let mut beyond: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+    for (line, column, _, to, _, _) in &tokens {
+        if *to >= line_count {
+            beyond.entry(*to).or_insert((*line, *column));
+        }
+    }
+    let mut next = line_count;
+    while beyond.contains_key(&next) {
+        next += 1;
+    }
+    for (node, (line, column)) in beyond.range(next..) {
+        found(*line, *column, Symptom::UndefinedNode(*node));
+    }
+    diagnoses.sort_by_key(|diagnosis| (diagnosis.line, diagnosis.column));
+    diagnoses
+}
+
+pub fn alphabetize(path: &[usize]) -> String {
+    // nodes are numeric but the graph I create in Google earth uses letters for the nodes. this converts back
+    // for easier readability
+    path.iter()
+        .map(|node| node_letters(*node))
+        .collect::<Vec<String>>()
+        .join(" -- ")
+}
+
+pub fn label_nodes(path: &[usize], labels: &BTreeMap<usize, String>) -> String {
+    // like alphabetize, but a node named in the input goes by that name
+    path.iter()
+        .map(|node| node_label(*node, labels))
+        .collect::<Vec<String>>()
+        .join(" -- ")
+}
+
+fn node_label(node: usize, labels: &BTreeMap<usize, String>) -> String {
+    labels
+        .get(&node)
+        .cloned()
+        .unwrap_or_else(|| node_letters(node))
+}
+
+fn node_letters(node: usize) -> String {
+    // spreadsheet column style: A..Z, then AA..AZ, BA.., so big networks don't run out of letters
+    let mut letters: Vec<u8> = vec![];
+    let mut n = node + 1;
+    while n > 0 {
+        n -= 1;
+        letters.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).expect("only ascii letters")
+}
+
+pub enum LabelStyle {
+    // letters, matching the nodes drawn in Google Earth
+    Alpha,
+    // letters while the graph has at most this many nodes, N<index> beyond that
+    Hybrid(usize),
+}
+
+pub fn label_path(path: &[usize], node_count: usize, style: &LabelStyle) -> String {
+    match style {
+        LabelStyle::Alpha => alphabetize(path),
+        LabelStyle::Hybrid(threshold) if node_count <= *threshold => alphabetize(path),
+        LabelStyle::Hybrid(_) => path
+            .iter()
+            .map(|node| format!("N{node}"))
+            .collect::<Vec<String>>()
+            .join(" -- "),
+    }
+}
+
+pub struct DirectionTemplate {
+    // one line per street walked. {from} and {to} are replaced with the node labels and {feet} with the length
+    pub step: String,
+    // added to the end of a step when both of its ends have coordinates. {bearing} is a compass direction like NE
+    pub heading: String,
+}
+
+impl Default for DirectionTemplate {
+    fn default() -> Self {
+        DirectionTemplate {
+            step: "From {from}, take the street to {to} ({feet} ft)".to_string(),
+            heading: ", heading {bearing}".to_string(),
+        }
+    }
+}
+
+pub fn directions(
+    path: &[usize],
+    graph: &RoadGraph,
+    labels: &[String],
+    template: &DirectionTemplate,
+) -> Vec<String> {
+    // the wording comes from the template so the directions can be handed out in whatever language the walker reads
+    directions_with_bearings(path, graph, labels, &[], template)
+}
+
+pub fn directions_with_bearings(
+    path: &[usize],
+    graph: &RoadGraph,
+    labels: &[String],
+    coords: &[Option<(f64, f64)>],
+    template: &DirectionTemplate,
+) -> Vec<String> {
+    // coords are (latitude, longitude) by node. a step only gets a heading when both of its ends are located, so a
+    // few missing coordinates just drop the heading from the steps that touch them
+    path.windows(2)
+        .zip(hop_weights(path, graph))
+        .map(|(hop, feet)| {
+            let mut step = template
+                .step
+                .replace("{from}", &labels[hop[0]])
+                .replace("{to}", &labels[hop[1]])
+                .replace("{feet}", &format_weight(feet));
+            if let (Some(from), Some(to)) = (
+                coords.get(hop[0]).copied().flatten(),
+                coords.get(hop[1]).copied().flatten(),
+            ) {
+                step.push_str(&template.heading.replace("{bearing}", compass(from, to)));
+            }
+            step
+        })
+        .collect()
+}
+
+pub fn turn_by_turn(
+    path: &[usize],
+    graph: &RoadGraph,
+    names: &BTreeMap<(usize, usize), String>,
+    labels: &BTreeMap<usize, String>,
+    coords: &[Option<(f64, f64)>],
+    minutes: &[f64],
+    sides: bool,
+) -> Vec<String> {
+    // one line per street walked, by name where the input gave one and by its end intersections where it didn't.
+    // hops that stay on the same named street are one step, and each step says how far the route has come so far.
+    // coords are (latitude, longitude) by node and can be empty. when the intersections either side of a corner
+    // are located, the step after it says which way to turn. minutes, when there are any, are hop_minutes for
+    // path and give each step its time. with sides, each step also says which side of the street it covers: the
+    // one on the walker's right, as a compass side when both ends are located
+    let name_of = |hop: &[usize]| names.get(&(hop[0].min(hop[1]), hop[0].max(hop[1])));
+    let weights = hop_weights(path, graph);
+    // each step is the range of hops it covers
+    let mut steps: Vec<(usize, usize)> = vec![];
+    for i in 0..weights.len() {
+        match steps.last_mut() {
+            Some((_, end))
+                if name_of(&path[i..i + 2]).is_some()
+                    && name_of(&path[i..i + 2]) == name_of(&path[i - 1..i + 1])
+                    && path[i + 1] != path[i - 1] =>
+            {
+                *end = i + 1
+            }
+            _ => steps.push((i, i + 1)),
+        }
+    }
+    let mut walked = 0;
+    steps
+        .into_iter()
+        .map(|(first, last)| {
+            let feet: usize = weights[first..last].iter().sum();
+            walked += feet;
+            let (from, to) = (
+                node_label(path[first], labels),
+                node_label(path[last], labels),
+            );
+            let located = |i: usize| coords.get(path[i]).copied().flatten();
+            let side = match (located(first), located(first + 1)) {
+                _ if !sides => None,
+                (Some(here), Some(next)) => Some(right_side(bearing(here, next))),
+                _ => Some("right-hand side"),
+            };
+            let street = match (name_of(&path[first..first + 2]), side) {
+                (Some(name), Some(side)) => format!("{name}, {side}, from {from} to {to}"),
+                (Some(name), None) => format!("{name} from {from} to {to}"),
+                (None, Some(side)) => format!("the {side} from {from} to {to}"),
+                (None, None) => format!("from {from} to {to}"),
+            };
+            let turn = match (first.checked_sub(1).and_then(located), located(first)) {
+                (Some(before), Some(corner)) => located(first + 1)
+                    .map(|after| turn_direction(bearing(before, corner), bearing(corner, after))),
+                _ => None,
+            };
+            let step = match turn {
+                Some(turn) => format!("{turn} and walk {street}"),
+                None => format!("Walk {street}"),
+            };
+            let time = match minutes.get(first..last) {
+                Some(minutes) => format!(", {}", format_minutes(minutes.iter().sum())),
+                None => String::new(),
+            };
+            format!(
+                "{step} ({} mi, {} mi so far{time})",
+                feet_to_miles(feet as f64 / WEIGHT_SCALE as f64),
+                feet_to_miles(walked as f64 / WEIGHT_SCALE as f64)
+            )
+        })
+        .collect()
+}
+
+fn turn_direction(heading_in: f64, heading_out: f64) -> &'static str {
+    // how far the walker has to turn at a corner, from the bearings of the streets either side of it. anything
+    // within 30 degrees of straight on counts as straight on
+    let turn = (heading_out - heading_in + 540.0).rem_euclid(360.0) - 180.0;
+    match turn {
+        t if t.abs() <= 30.0 => "Continue straight",
+        t if t.abs() >= 150.0 => "Turn around",
+        t if t > 0.0 => "Turn right",
+        _ => "Turn left",
+    }
+}
+
+fn right_side(heading: f64) -> &'static str {
+    // the side of a street on the right of someone walking along it on this bearing, to the nearest of four
+    let sides = ["north side", "east side", "south side", "west side"];
+    sides[((heading + 90.0 + 45.0) / 90.0) as usize % 4]
+}
+
+fn both_ways(graph: &RoadGraph, costs: &BTreeMap<(usize, usize), usize>) -> DirectedRoadGraph {
+    // every street as an arc each way, so a circuit walks each one once in each direction. a street with a cost
+    // each way (see direction_costs) gets its own on each arc
+    let cost =
+        |u: usize, v: usize, weight: usize| (u, v, costs.get(&(u, v)).copied().unwrap_or(weight));
+    let arcs = edge_list(graph)
+        .into_iter()
+        .flat_map(|(u, v, weight)| [cost(u, v, weight), cost(v, u, weight)])
+        .collect();
+    directed_with_nodes(arcs, graph.node_count())
+}
+
+fn bearing(from: (f64, f64), to: (f64, f64)) -> f64 {
+    // initial great circle bearing between two (latitude, longitude) points, in degrees clockwise from north
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let delta_lon = (to.1 - from.1).to_radians();
+    let y = delta_lon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * delta_lon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+fn compass(from: (f64, f64), to: (f64, f64)) -> &'static str {
+    // the bearing rounded to one of eight directions
+    let points = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    points[((bearing(from, to) + 22.5) / 45.0) as usize % 8]
+}
+
+#[derive(Clone, Copy)]
+pub enum Unit {
+    // what the edge weights in the input file are measured in. they're converted to feet as the file is read, so
+    // everything after parsing works in feet
+    Feet,
+    Meters,
+}
+
+pub enum Distance {
+    // what lengths get reported in
+    Miles,
+    Kilometers,
+}
+
+pub fn length(path: &[usize], graph: &RoadGraph, input_unit: &Unit, output_unit: &Distance) -> f64 {
+    // finds each edge along the final path and sums their weights, then returns the total in the output unit
+    // (truncated to two decimal places)
+    let total = total_feet_u128(path, graph) as f64 / WEIGHT_SCALE as f64;
+    convert_length(total, input_unit, output_unit)
+}
+
+pub fn total_feet_u128(path: &[usize], graph: &RoadGraph) -> u128 {
+    // the exact length of the path in hundredths of a foot, the unit weights are stored in. usize is only 32 bits
+    // on some targets, so a long enough route could overflow it, but not a u128
+    hop_weights(path, graph)
+        .into_iter()
+        .map(|weight| weight as u128)
+        .sum()
+}
+
+pub fn hop_weights(path: &[usize], graph: &RoadGraph) -> Vec<usize> {
+    // the weight of each hop along path, 0 where there's no street. after eulerization two nodes can have several
+    // streets between them, and an original one and a parallel street can differ in length, so the streets between
+    // a pair are numbered in the order the lower node lists them and the k-th crossing between the pair walks the
+    // k-th one (wrapping round). an euler circuit crosses a pair once per street, so each is counted exactly once
+    let mut crossings: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    path.windows(2)
+        .map(|hop| {
+            let pair = (hop[0].min(hop[1]), hop[0].max(hop[1]));
+            let mut streets: Vec<usize> = neighbors_vec(graph, pair.0)
+                .into_iter()
+                .filter(|(target, _)| *target == pair.1)
+                .map(|(_, weight)| weight)
+                .collect();
+            // a self loop is listed twice on its node
+            if pair.0 == pair.1 {
+                streets = streets.into_iter().step_by(2).collect();
+            }
+            let crossed = crossings.entry(pair).or_insert(0);
+            let weight = match streets.len() {
+                0 => 0,
+                count => streets[*crossed % count],
+            };
+            *crossed += 1;
+            weight
+        })
+        .collect()
+}
+
+pub fn deadhead_miles(added_feet: f64) -> f64 {
+    // converts the length eulerization added, with the same rounding as length
+    feet_to_miles(added_feet)
+}
+
+fn feet_to_miles(ft: f64) -> f64 {
+    convert_length(ft, &Unit::Feet, &Distance::Miles)
+}
+
+fn convert_length(value: f64, input_unit: &Unit, output_unit: &Distance) -> f64 {
+    let converted = match (input_unit, output_unit) {
+        (Unit::Feet, Distance::Miles) => value / 5280.0,
+        (Unit::Feet, Distance::Kilometers) => value * 0.3048 / 1000.0,
+        (Unit::Meters, Distance::Miles) => value / 1609.344,
+        (Unit::Meters, Distance::Kilometers) => value / 1000.0,
+    };
+    f64::trunc(converted * 100.0) / 100.0
+}
+
+pub fn total_feet(graph: &RoadGraph) -> f64 {
+    // the length of every street in the graph, counting each parallel copy of a street separately. summed in the
+    // stored hundredths first so the fractions don't pick up float error along the way
+    let total: u128 = edge_list(graph)
+        .iter()
+        .map(|(_, _, weight)| *weight as u128)
+        .sum();
+    total as f64 / WEIGHT_SCALE as f64
+}
+
+pub fn traversal_counts(graph: &RoadGraph) -> Vec<((usize, usize), usize)> {
+    // once the graph is eulerized, every parallel copy of a street is one more trip down it, so the number of
+    // copies between two nodes is how many times the route traverses that street
+    let mut counts: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (u, v, _) in edge_list(graph) {
+        *counts.entry((u, v)).or_insert(0) += 1;
+    }
+    counts.into_iter().collect()
+}
+
+pub fn max_traversal(graph: &RoadGraph) -> usize {
+    traversal_counts(graph)
+        .into_iter()
+        .map(|(_, count)| count)
+        .max()
+        .unwrap_or(0)
+}
+
+pub struct RouteSummary {
+    pub base_feet: f64,
+    pub total_feet: f64,
+    // streets in the input, and street walks in the route. the difference is the number of duplicated streets
+    pub distinct_edges: usize,
+    pub total_traversals: usize,
+    // the street walks that go over a street again rather than covering it (see repeat_hops). optional streets
+    // walked once count in total_traversals but aren't repeats
+    pub repeats: usize,
+    // the doors along the streets the route walks, each street's counted once (see hop_doors)
+    pub doors: usize,
+    // for a route from a depot off the turf, the feet walked out to the turf and back again (see approach_legs)
+    pub approach_feet: Option<(f64, f64)>,
+}
+
+impl fmt::Display for RouteSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.describe_in(&Distance::Miles))
+    }
+}
+
+impl RouteSummary {
+    pub fn describe_in(&self, unit: &Distance) -> String {
+        // the summary line with lengths in the given unit
+        let (convert, suffix) = (
+            |feet: f64| convert_length(feet, &Unit::Feet, unit),
+            distance_suffix(unit),
+        );
+        format!(
+            "base road network: {} {suffix}, deadheading added: {} {suffix}, total route: {} {suffix}, \
+             efficiency: {:.1}, deadhead: {}, streets: {} distinct, {} traversals, {} repeats",
+            convert(self.base_feet),
+            convert(self.total_feet - self.base_feet),
+            convert(self.total_feet),
+            efficiency_score(self),
+            format_percent(deadhead_ratio(self), 1),
+            self.distinct_edges,
+            self.total_traversals,
+            self.repeats
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Pace {
+    pub mph: f64,
+    // the time spent at each door along a street, on top of walking it
+    pub minutes_per_door: f64,
+}
+
+#[derive(Clone, Copy)]
+pub enum Effort {
+    // tobler's hiking function gives walking speed as 6e^(-3.5|grade + 0.05|) km/h, so a street costs however much
+    // longer it takes than on the flat. a gentle descent comes out a little cheaper than the flat
+    Tobler,
+    // each percent of climb adds this percent of the street's length, and going down costs the same as the flat
+    Grade(f64),
+}
+
+impl Effort {
+    pub fn multiplier(&self, grade: f64) -> f64 {
+        // how many times its length walking a street at this grade (rise over run, negative downhill) costs
+        match self {
+            Effort::Tobler => (3.5 * ((grade + 0.05).abs() - 0.05)).exp(),
+            Effort::Grade(percent) => 1.0 + grade.max(0.0) * percent,
+        }
+    }
+}
+
+pub trait EdgeCost: Sync {
+    // what walking the street from u to v costs, given its weight in hundredths of a foot as the map stores it.
+    // the solver pairs up odd corners and picks the streets to walk again by this instead of the length, so it has
+    // to be whole and add up the way lengths do
+    fn cost(&self, u: usize, v: usize, weight: usize) -> usize;
+}
+
+pub struct LengthCost;
+
+impl EdgeCost for LengthCost {
+    fn cost(&self, _: usize, _: usize, weight: usize) -> usize {
+        weight
+    }
+}
+
+pub struct TimeCost {
+    // hundredths of a second to walk the street at pace, and stop at its doors, keyed like street_names
+    pub pace: Pace,
+    pub doors: BTreeMap<(usize, usize), usize>,
+}
+
+impl EdgeCost for TimeCost {
+    fn cost(&self, u: usize, v: usize, weight: usize) -> usize {
+        let doors = self.doors.get(&(u.min(v), u.max(v))).copied().unwrap_or(0);
+        let minutes = walking_minutes(weight as f64 / WEIGHT_SCALE as f64, &self.pace)
+            + doors as f64 * self.pace.minutes_per_door;
+        (minutes * 60.0 * WEIGHT_SCALE as f64).round() as usize
+    }
+}
+
+pub struct EffortCost {
+    // the length weighed by the grade between the street's ends, elevations in feet by node, the way --effort
+    // does it (see with_effort). a street with an end that has no elevation costs its length
+    pub elevations: Vec<Option<f64>>,
+    pub effort: Effort,
+}
+
+impl EdgeCost for EffortCost {
+    fn cost(&self, u: usize, v: usize, weight: usize) -> usize {
+        let elevation = |node: usize| self.elevations.get(node).copied().flatten();
+        match (elevation(u), elevation(v)) {
+            (Some(from), Some(to)) if weight > 0 => {
+                let grade = (to - from) / (weight as f64 / WEIGHT_SCALE as f64);
+                (weight as f64 * self.effort.multiplier(grade)).round() as usize
+            }
+            _ => weight,
+        }
+    }
+}
+
+pub struct DoorCost {
+    // the length plus feet_per_door for each of the street's doors, keyed like street_names, so the streets walked
+    // again are the quiet ones where they can be, away from the doors already knocked
+    pub doors: BTreeMap<(usize, usize), usize>,
+    pub feet_per_door: f64,
+}
+
+impl EdgeCost for DoorCost {
+    fn cost(&self, u: usize, v: usize, weight: usize) -> usize {
+        let doors = self.doors.get(&(u.min(v), u.max(v))).copied().unwrap_or(0);
+        weight + (doors as f64 * self.feet_per_door * WEIGHT_SCALE as f64).round() as usize
+    }
+}
+
+pub fn with_cost(graph: &RoadGraph, cost: &dyn EdgeCost) -> RoadGraph {
+    // the map with each street weighed by cost instead of its length, for eulerizing by something else. a street
+    // can cost more one way than the other, and since either way round will do, it gets the average like with_effort
+    let edges = edge_list(graph)
+        .into_iter()
+        .map(|(u, v, weight)| {
+            (
+                u,
+                v,
+                (cost.cost(u, v, weight) + cost.cost(v, u, weight)) / 2,
+            )
+        })
+        .collect();
+    graph_with_nodes(edges, graph.node_count())
+}
+
+pub fn with_effort(
+    graph: &RoadGraph,
+    costs: &mut BTreeMap<(usize, usize), usize>,
+    elevations: &[Option<f64>],
+    effort: &Effort,
+) -> RoadGraph {
+    // the map with each street whose ends both have an elevation (in feet) weighed by the effort of walking it.
+    // its cost each way goes into costs (see direction_costs) and the map gets the average, the same as a street
+    // written with a weight each way. those already say what they cost, so they're left as they are
+    let elevation = |node: usize| elevations.get(node).copied().flatten();
+    let edges = edge_list(graph)
+        .into_iter()
+        .map(|(u, v, weight)| {
+            let (Some(from), Some(to)) = (elevation(u), elevation(v)) else {
+                return (u, v, weight);
+            };
+            if weight == 0 || costs.contains_key(&(u, v)) {
+                return (u, v, weight);
+            }
+            let cost = EffortCost {
+                elevations: vec![Some(from), Some(to)],
+                effort: *effort,
+            };
+            let (there, back) = (cost.cost(0, 1, weight), cost.cost(1, 0, weight));
+            costs.insert((u, v), there);
+            costs.insert((v, u), back);
+            (u, v, (there + back) / 2)
+        })
+        .collect();
+    graph_with_nodes(edges, graph.node_count())
+}
+
+pub fn walking_minutes(feet: f64, pace: &Pace) -> f64 {
+    if pace.mph <= 0.0 {
+        return 0.0;
+    }
+    feet / 5280.0 / pace.mph * 60.0
+}
+
+pub fn hop_minutes(
+    path: &[usize],
+    graph: &RoadGraph,
+    doors: &BTreeMap<(usize, usize), usize>,
+    pace: &Pace,
+) -> Vec<f64> {
+    // the time each hop of path takes: walking it, plus its doors (see hop_doors)
+    hop_weights(path, graph)
+        .into_iter()
+        .zip(hop_doors(path, doors))
+        .map(|(weight, count)| {
+            walking_minutes(weight as f64 / WEIGHT_SCALE as f64, pace)
+                + count as f64 * pace.minutes_per_door
+        })
+        .collect()
+}
+
+pub fn approach_legs(
+    path: &[usize],
+    weights: &[usize],
+    optional: &BTreeSet<(usize, usize)>,
+) -> (f64, f64) {
+    // the feet walked before the first street of path that needs covering and after the last, which for a route
+    // from a depot off the turf are the walks out to the turf and back. weights are path's hops, like hop_weights
+    let required: Vec<bool> = path
+        .windows(2)
+        .map(|hop| !optional.contains(&(hop[0].min(hop[1]), hop[0].max(hop[1]))))
+        .collect();
+    let first = required
+        .iter()
+        .position(|hop| *hop)
+        .unwrap_or(weights.len());
+    let last = required
+        .iter()
+        .rposition(|hop| *hop)
+        .map_or(first, |last| last + 1);
+    let feet = |hops: &[usize]| {
+        hops.iter().map(|weight| *weight as u128).sum::<u128>() as f64 / WEIGHT_SCALE as f64
+    };
+    (feet(&weights[..first]), feet(&weights[last..]))
+}
+
+pub fn hop_doors(path: &[usize], doors: &BTreeMap<(usize, usize), usize>) -> Vec<usize> {
+    // the doors each hop of path reaches: a street's doors the first time the route is on it, since re-walking a
+    // street to get somewhere doesn't mean knocking again. doors are keyed like street_names
+    knock(path, doors, &mut BTreeSet::new())
+}
+
+fn knock(
+    path: &[usize],
+    doors: &BTreeMap<(usize, usize), usize>,
+    knocked: &mut BTreeSet<(usize, usize)>,
+) -> Vec<usize> {
+    // hop_doors, leaving out the streets already in knocked and adding the ones path reaches, so a route that's
+    // been split up can be gone through a piece at a time without counting a street's doors twice
+    path.windows(2)
+        .map(|hop| {
+            let pair = (hop[0].min(hop[1]), hop[0].max(hop[1]));
+            match doors.get(&pair) {
+                Some(count) if knocked.insert(pair) => *count,
+                _ => 0,
+            }
+        })
+        .collect()
+}
+
+pub fn doors_per(doors: usize, feet: f64, unit: &Distance) -> f64 {
+    // doors per mile or kilometer over feet, for comparing turfs by how many people they reach for the walking.
+    // unrounded, unlike convert_length, and nothing to walk reaches nobody
+    let length = match unit {
+        Distance::Miles => feet / 5280.0,
+        Distance::Kilometers => feet * 0.3048 / 1000.0,
+    };
+    match length > 0.0 {
+        true => doors as f64 / length,
+        false => 0.0,
+    }
+}
+
+pub fn format_minutes(minutes: f64) -> String {
+    // rounded to the minute, as 45 min or 2 h 05 min
+    let minutes = minutes.max(0.0).round() as usize;
+    if minutes < 60 {
+        format!("{minutes} min")
+    } else {
+        format!("{} h {:02} min", minutes / 60, minutes % 60)
+    }
+}
+
+pub struct RouteStats {
+    // lengths in feet. the route covers every street once plus the deadheading
+    pub street_feet: f64,
+    pub route_feet: f64,
+    pub deadhead_feet: f64,
+    // the streets walked a second time, as (lower node, higher node, weight)
+    pub duplicated: Vec<(usize, usize, usize)>,
+    pub odd_nodes_before: usize,
+    pub odd_nodes_after: usize,
+    // no route can be shorter than this (see lower_bound), and whether it's the shortest one there is
+    pub lower_bound_feet: f64,
+    pub bound_is_exact: bool,
+}
+
+impl RouteStats {
+    pub fn deadhead_ratio(&self) -> f64 {
+        if self.route_feet <= 0.0 {
+            return 0.0;
+        }
+        self.deadhead_feet / self.route_feet
+    }
+
+    pub fn optimality_gap(&self) -> f64 {
+        // how much longer the route is than the lower bound, 0.0 for a route that meets it
+        if self.lower_bound_feet <= 0.0 {
+            return 0.0;
+        }
+        self.route_feet / self.lower_bound_feet - 1.0
+    }
+
+    pub fn describe(&self, labels: &BTreeMap<usize, String>, unit: &Distance) -> String {
+        // one fact per line in the given unit, with the duplicated streets listed last in feet
+        let convert = |feet: f64| convert_length(feet, &Unit::Feet, unit);
+        let suffix = distance_suffix(unit);
+        let duplicated: Vec<String> = self
+            .duplicated
+            .iter()
+            .map(|(u, v, weight)| {
+                format!(
+                    "  {} -- {} ({} ft)",
+                    node_label(*u, labels),
+                    node_label(*v, labels),
+                    format_weight(*weight)
+                )
+            })
+            .collect();
+        let mut lines = vec![
+            format!("street length: {} {suffix}", convert(self.street_feet)),
+            format!("route length: {} {suffix}", convert(self.route_feet)),
+            format!(
+                "deadheading: {} {suffix} ({})",
+                convert(self.deadhead_feet),
+                format_percent(self.deadhead_ratio(), 1)
+            ),
+            format!(
+                "odd-degree intersections: {} before routing, {} after",
+                self.odd_nodes_before, self.odd_nodes_after
+            ),
+            format!(
+                "lower bound: {} {suffix} ({}), route is {} over it",
+                convert(self.lower_bound_feet),
+                match self.bound_is_exact {
+                    true => "the shortest possible route",
+                    false => "too many odd intersections to match exactly, so a looser bound",
+                },
+                format_percent(self.optimality_gap(), 1)
+            ),
+            format!("streets walked twice: {}", self.duplicated.len()),
+        ];
+        lines.extend(duplicated);
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self, unit: &Distance) -> String {
+        // lengths in the given unit, except the duplicated streets, which keep feet like describe
+        let convert = |feet: f64| convert_length(feet, &Unit::Feet, unit);
+        let duplicated: Vec<String> = self
+            .duplicated
+            .iter()
+            .map(|(u, v, weight)| {
+                format!(
+                    "{{\"from\":{u},\"to\":{v},\"feet\":{}}}",
+                    format_weight(*weight)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"unit\":\"{}\",\"street_length\":{},\"route_length\":{},\"deadhead\":{},\
+             \"deadhead_ratio\":{:.4},\"odd_nodes_before\":{},\"odd_nodes_after\":{},\"lower_bound\":{},\
+             \"bound_is_exact\":{},\"optimality_gap\":{:.4},\"duplicated\":[{}]}}",
+            distance_suffix(unit),
+            convert(self.street_feet),
+            convert(self.route_feet),
+            convert(self.deadhead_feet),
+            self.deadhead_ratio(),
+            self.odd_nodes_before,
+            self.odd_nodes_after,
+            convert(self.lower_bound_feet),
+            self.bound_is_exact,
+            self.optimality_gap(),
+            duplicated.join(",")
+        )
+    }
+}
+
+pub fn street_copies(graph: &RoadGraph) -> BTreeMap<(usize, usize), usize> {
+    // how many streets join each pair of intersections, keyed like street_names. more than one is parallel streets
+    // in the map, or a street routing has added a repeat of
+    let mut copies = BTreeMap::new();
+    for (u, v, _) in edge_list(graph) {
+        *copies.entry((u, v)).or_insert(0) += 1;
+    }
+    copies
+}
+
+pub fn arc_copies(graph: &DirectedRoadGraph) -> BTreeMap<(usize, usize), usize> {
+    // street_copies for one-way streets, keyed by the way each one goes
+    let mut copies = BTreeMap::new();
+    for (u, v, _) in arc_list(graph) {
+        *copies.entry((u, v)).or_insert(0) += 1;
+    }
+    copies
+}
+
+pub fn repeat_hops(
+    path: &[usize],
+    copies: &BTreeMap<(usize, usize), usize>,
+    directed: bool,
+) -> Vec<bool> {
+    // for each hop of path, whether it walks a street again rather than covering it. copies are the map's own
+    // streets from street_copies, or arc_copies when directed, taken before routing added any. a street the map
+    // has n of is covered by the first n walks down it and every one after is a repeat, since parallel copies of
+    // a street can't be told apart by the nodes alone
+    let mut left = copies.clone();
+    path.windows(2)
+        .map(|hop| {
+            let key = match directed {
+                true => (hop[0], hop[1]),
+                false => (hop[0].min(hop[1]), hop[0].max(hop[1])),
+            };
+            match left.get_mut(&key) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            }
+        })
+        .collect()
+}
+
+pub fn route_stats(graph: &RoadGraph, policy: &BoundaryPolicy) -> RouteStats {
+    // what routing adds to the graph, worked out on a copy so the graph passed in is left alone. the streets it
+    // added are whatever the eulerized copy has more of than the original, street by street
+    let eulerized = copy_graph(graph);
+    fix_culdesacs(&eulerized, policy);
+    eulerize(&eulerized);
+    let mut original: BTreeMap<(usize, usize, usize), usize> = BTreeMap::new();
+    for edge in edge_list(graph) {
+        *original.entry(edge).or_insert(0) += 1;
+    }
+    let mut duplicated = vec![];
+    for edge in edge_list(&eulerized) {
+        match original.get_mut(&edge) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => duplicated.push(edge),
+        }
+    }
+    let street_feet = total_feet(graph);
+    let route_feet = total_feet(&eulerized);
+    let (lower_bound_feet, bound_is_exact) = lower_bound(graph);
+    RouteStats {
+        street_feet,
+        route_feet,
+        deadhead_feet: route_feet - street_feet,
+        duplicated,
+        odd_nodes_before: odd_degree_nodes(graph).len(),
+        odd_nodes_after: odd_degree_nodes(&eulerized).len(),
+        lower_bound_feet,
+        bound_is_exact,
+    }
+}
+
+pub fn lower_bound(graph: &RoadGraph) -> (f64, bool) {
+    // the shortest a circuit covering every street could possibly be, in feet: each street once (the trivial bound)
+    // plus the cheapest pairing of the odd intersections, since every route has to get between them somehow. up to
+    // EXACT_MATCHING_LIMIT odd nodes that pairing is exact and so is the bound, it's the length of the best route.
+    // past that the matching is a heuristic, so each odd node is charged half the way to its nearest odd neighbor
+    // instead, which no pairing can come in under. the bool says which it was
+    let odd_nodes = odd_degree_nodes(graph);
+    let distance = odd_node_distances(graph, &odd_nodes);
+    let exact = odd_nodes.len() <= EXACT_MATCHING_LIMIT;
+    let pairing: u128 = if exact {
+        pair_odd_nodes_by(&odd_nodes, &distance)
+            .iter()
+            .map(|(u, v)| distance(*u, *v) as u128)
+            .sum()
+    } else {
+        let nearest: u128 = odd_nodes
+            .iter()
+            .filter_map(|u| {
+                odd_nodes
+                    .iter()
+                    .filter(|v| *v != u)
+                    .map(|v| distance(*u, *v))
+                    .filter(|distance| *distance != u64::MAX)
+                    .min()
+            })
+            .map(|distance| distance as u128)
+            .sum();
+        nearest / 2
+    };
+    (
+        total_feet(graph) + pairing as f64 / WEIGHT_SCALE as f64,
+        exact,
+    )
+}
+
+fn distance_suffix(unit: &Distance) -> &'static str {
+    match unit {
+        Distance::Miles => "mi",
+        Distance::Kilometers => "km",
+    }
+}
+
+fn summary_json(
+    summary: &RouteSummary,
+    path: &[usize],
+    weights: &[usize],
+    repeats: &[bool],
+    config: &Config,
+    labels: &BTreeMap<usize, String>,
+    splits: &str,
+) -> String {
+    // lengths are in --units, or in seconds with --durations. weights are the hops of path as hop_weights gives them,
+    // and each edge keeps its exact weight rather than being rounded to the unit. repeats are repeat_hops for path
+    let (convert, unit): (Box<dyn Fn(f64) -> f64>, &str) = match config.weight_format {
+        WeightFormat::Feet => (
+            Box::new(|feet| convert_length(feet, &Unit::Feet, &config.units)),
+            distance_suffix(&config.units),
+        ),
+        WeightFormat::Duration => (Box::new(|seconds| seconds.round()), "s"),
+    };
+    let weight_key = match config.weight_format {
+        WeightFormat::Feet => "feet",
+        WeightFormat::Duration => "seconds",
+    };
+    let nodes: Vec<String> = path.iter().map(|node| node.to_string()).collect();
+    let labels = labels_json(path, labels);
+    let edges: Vec<String> = path
+        .windows(2)
+        .zip(weights)
+        .zip(repeats)
+        .map(|((hop, weight), repeat)| {
+            format!(
+                "{{\"from\":{},\"to\":{},\"{weight_key}\":{},\"repeat\":{repeat}}}",
+                hop[0],
+                hop[1],
+                format_weight(*weight)
+            )
+        })
+        .collect();
+    // doors per length of street, which durations don't have
+    let density = match config.weight_format {
+        WeightFormat::Feet => format!(
+            ",\"doors_per_{unit}\":{:.1}",
+            doors_per(summary.doors, summary.base_feet, &config.units)
+        ),
+        WeightFormat::Duration => String::new(),
+    };
+    let approach = match summary.approach_feet {
+        Some((out, back)) => format!(
+            ",\"approach\":{{\"out\":{},\"back\":{}}}",
+            convert(out),
+            convert(back)
+        ),
+        None => String::new(),
+    };
+    format!(
+        "{{\"unit\":\"{unit}\",\"base\":{},\"deadhead\":{},\"total\":{},\"distinct_streets\":{},\
+         \"traversals\":{},\"repeats\":{},\"doors\":{}{density}{approach}{splits},\"path\":[{}],\"labels\":[{}],\
+         \"edges\":[{}]}}",
+        convert(summary.base_feet),
+        convert(summary.total_feet - summary.base_feet),
+        convert(summary.total_feet),
+        summary.distinct_edges,
+        summary.total_traversals,
+        summary.repeats,
+        summary.doors,
+        nodes.join(","),
+        labels,
+        edges.join(",")
+    )
+}
+
+pub fn describe(graph: &RoadGraph) -> String {
+    // a one-sentence summary of the network for quick human consumption. the backtracking figure comes from running
+    // the cul de sac and eulerize steps on a copy, so the graph passed in is left alone
+    let dead_ends = (0..graph.node_count())
+        .filter(|node| graph.degree(*node) == 1)
+        .count();
+    let base_feet = total_feet(graph);
+    let eulerized = copy_graph(graph);
+    fix_culdesacs(&eulerized, &BoundaryPolicy::TreatAsCuldesac);
+    eulerize(&eulerized);
+    let extra_feet = total_feet(&eulerized) - base_feet;
+    format!(
+        "{} intersections, {} streets totaling {} miles, {} dead ends, {} odd-degree junctions; \
+         an Euler circuit requires {} extra miles of backtracking.",
+        graph.node_count(),
+        graph.edge_count(),
+        feet_to_miles(base_feet),
+        dead_ends,
+        odd_degree_nodes(graph).len(),
+        feet_to_miles(extra_feet)
+    )
+}
+
+pub fn efficiency_score(summary: &RouteSummary) -> f64 {
+    // 100 means the route covers every street exactly once. every foot of backtracking added on top of the base
+    // network pulls the score down proportionally
+    if summary.total_feet <= 0.0 {
+        return 100.0;
+    }
+    summary.base_feet / summary.total_feet * 100.0
+}
+
+pub fn deadhead_ratio(summary: &RouteSummary) -> f64 {
+    // the share of the route spent re-walking streets, from 0 (every street once) towards 1
+    if summary.total_feet <= 0.0 {
+        return 0.0;
+    }
+    (summary.total_feet - summary.base_feet).max(0.0) / summary.total_feet
+}
+
+pub fn format_percent(ratio: f64, decimals: usize) -> String {
+    // 0.1234 with 1 decimal is "12.3%". anything that isn't a real number shows as 0 rather than NaN%
+    let ratio = if ratio.is_finite() { ratio } else { 0.0 };
+    format!("{:.*}%", decimals, ratio * 100.0)
+}
+
+pub fn neighbors_vec(graph: &RoadGraph, node: usize) -> Vec<(usize, usize)> {
+    // neighbors_with_values holds the lock on the node's neighbor list for as long as the iterator lives, and the
+    // references it hands out are only good until an edge is added to that node. copying the (target, weight) pairs
+    // out avoids both problems, and a node outside the graph just has no neighbors. the graph is built on rayon's
+    // thread pool, so the lists come in whatever order the threads got to them, and they're sorted so everything
+    // that walks them settles ties the same way every run
+    if node >= graph.node_count() {
+        return vec![];
+    }
+    let mut neighbors: Vec<(usize, usize)> = graph
+        .neighbors_with_values(node)
+        .map(|neighbor| (neighbor.target, neighbor.value))
+        .collect();
+    neighbors.sort_unstable();
+    neighbors
+}
+
+pub fn fix_culdesacs(graph: &RoadGraph, policy: &BoundaryPolicy) {
+    // each node with degree 1 is a cul de sac / dead end, and the only way to include a cul de sac on an euler cycle is to
+    // go into it, then come back out. this function adds those returning edges to each cul de sac before running the rest
+    // of the algorithm.
+    if let BoundaryPolicy::TreatAsEndpoint = policy {
+        return;
+    }
+    let mut nodes_with_degree_one: Vec<usize> = vec![];
+    for i in 0..graph.node_count() {
+        if graph.degree(i) == 1 {
+            nodes_with_degree_one.push(i);
+        }
+    }
+    // the degrees are all read before any edge is added, so each cul de sac gets exactly one return edge. a lone
+    // street between two dead ends is both ends' only street, and doubling it once already makes both ends even,
+    // so only its lower end adds the copy
+    let mut returns: Vec<(usize, usize, usize)> = vec![];
+    for node in &nodes_with_degree_one {
+        if let Some(&(target, weight)) = neighbors_vec(graph, *node).first() {
+            if graph.degree(target) == 1 && target < *node {
+                continue;
+            }
+            returns.push((*node, target, weight));
+        }
+    }
+    debug!(
+        "{} dead ends, {} return streets added ({} ft)",
+        nodes_with_degree_one.len(),
+        returns.len(),
+        format_weight(returns.iter().map(|(_, _, weight)| weight).sum())
+    );
+    for (node, target, weight) in returns {
+        let _ = graph.add_edge_with_value(node, target, weight);
+    }
+}
+
+pub fn degree_parity(graph: &RoadGraph) -> Vec<bool> {
+    // true for each node with an odd number of streets, i.e. the nodes eulerize has to pair up
+    (0..graph.node_count())
+        .map(|node| graph.degree(node) % 2 != 0)
+        .collect()
+}
+
+pub fn odd_degree_nodes(graph: &RoadGraph) -> Vec<usize> {
+    degree_parity(graph)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, odd)| *odd)
+        .map(|(node, _)| node)
+        .collect()
+}
+
+pub struct Matching {
+    pairs: Vec<(usize, usize)>,
+    total_cost: usize,
+}
+
+impl Matching {
+    pub fn new(pairs: Vec<(usize, usize)>, total_cost: usize) -> Self {
+        Matching { pairs, total_cost }
+    }
+    pub fn pairs(&self) -> &[(usize, usize)] {
+        &self.pairs
+    }
+    pub fn cost(&self) -> usize {
+        self.total_cost
+    }
+    pub fn is_perfect(&self, odd_nodes: &[usize]) -> bool {
+        // every odd node has to be in exactly one pair, and nothing else can be paired
+        let mut matched: Vec<usize> = self.pairs.iter().flat_map(|(u, v)| [*u, *v]).collect();
+        let mut expected = odd_nodes.to_vec();
+        matched.sort();
+        expected.sort();
+        matched == expected
+    }
+}
+
+pub struct EulerizationReport {
+    pub odd_node_count: usize,
+    pub duplicated_edge_count: usize,
+    pub duplicated_feet: f64,
+}
+
+pub fn eulerize(graph: &RoadGraph) -> EulerizationReport {
+    // the neighborhoods will not usually have an euler cycle immediately.
+    // we use the following method to create one by duplicating edges until there are no odd-degree nodes
+    duplicate_paths(graph, graph, &odd_degree_nodes(graph))
+}
+
+pub fn eulerize_located(graph: &RoadGraph, coords: &[Option<(f64, f64)>]) -> EulerizationReport {
+    // eulerize, with the shortest paths found by a* between the odd nodes when coords locates every intersection
+    // with a street. the same length gets added either way, it's just quicker on a big map
+    duplicate_paths_located(graph, graph, &odd_degree_nodes(graph), coords)
+}
+
+pub fn eulerize_open(graph: &RoadGraph, start: usize, end: usize) -> EulerizationReport {
+    // like eulerize, but leaves start and end as the only odd nodes so the route can be an euler trail between them
+    duplicate_paths(graph, graph, &trail_odd_nodes(graph, start, end))
+}
+
+fn route_odd_nodes(graph: &RoadGraph, kind: &RouteKind) -> Vec<usize> {
+    match kind {
+        RouteKind::Circuit => odd_degree_nodes(graph),
+        RouteKind::OpenPath { start, end } => trail_odd_nodes(graph, *start, *end),
+    }
+}
+
+fn trail_odd_nodes(graph: &RoadGraph, start: usize, end: usize) -> Vec<usize> {
+    // the nodes whose parity has to change for a trail from start to end: the odd ones, except that start and end
+    // need to be odd, so they flip. with start == end the flips cancel and it's a circuit
+    let mut parity = degree_parity(graph);
+    if start != end {
+        parity[start] = !parity[start];
+        parity[end] = !parity[end];
+    }
+    (0..parity.len()).filter(|node| parity[*node]).collect()
+}
+
+fn duplicate_paths(
+    graph: &RoadGraph,
+    network: &RoadGraph,
+    nodes_with_odd_degree: &[usize],
+) -> EulerizationReport {
+    // adds to graph the shortest paths through network that pair off the given nodes. network is the map the paths
+    // are driven on, usually graph itself. only the distances between the nodes being paired matter, so they come
+    // from a dijkstra run per node rather than between every two intersections of the map
+    duplicate_paths_by(
+        graph,
+        nodes_with_odd_degree,
+        odd_node_distances(network, nodes_with_odd_degree),
+        |u, v| path_edges(network, &dijkstra(network, u), v),
+    )
+}
+
+fn odd_node_distances(network: &RoadGraph, nodes: &[usize]) -> impl Fn(usize, usize) -> u64 {
+    // the shortest distance between two of the given nodes, u64::MAX with no path between them. one dijkstra per
+    // node, which are independent, so with the parallel feature they run on rayon's thread pool
+    let started = Stopwatch::start();
+    #[cfg(feature = "parallel")]
+    let rows_iter = nodes.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let rows_iter = nodes.iter();
+    let distances: Vec<Vec<u64>> = rows_iter
+        .map(|u| {
+            let row = all_pairs_row(network, *u);
+            nodes.iter().map(|v| wide_distance(row[*v])).collect()
+        })
+        .collect();
+    debug!(
+        "shortest paths between {} odd intersections in {}",
+        nodes.len(),
+        started
+    );
+    let position: BTreeMap<usize, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (*node, i))
+        .collect();
+    move |u, v| distances[position[&u]][position[&v]]
+}
+
+// what each street counts for when repeats are kept down by number instead of length. it's more than any route's
+// total length, so one street fewer always wins, and the length underneath still breaks ties
+const STREET_COST: u64 = 1 << 40;
+
+fn duplicate_paths_for(
+    graph: &RoadGraph,
+    network: &RoadGraph,
+    nodes_with_odd_degree: &[usize],
+    objective: ObjectiveKind,
+    coords: &[Option<(f64, f64)>],
+) -> EulerizationReport {
+    // duplicate_paths_located, choosing the paths for objective. counting streets is the same matching with every
+    // street costing STREET_COST on top of its length. that doesn't fit a graph's usize weights on a 32 bit target,
+    // so the counted paths come from their own search in u64 and only the real streets go into graph
+    let ObjectiveKind::MinRepeatedEdges = objective else {
+        return duplicate_paths_located(graph, network, nodes_with_odd_degree, coords);
+    };
+    let position: BTreeMap<usize, usize> = nodes_with_odd_degree
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (*node, i))
+        .collect();
+    #[cfg(feature = "parallel")]
+    let trees_iter = nodes_with_odd_degree.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let trees_iter = nodes_with_odd_degree.iter();
+    let trees: Vec<Vec<(u64, Option<usize>)>> = trees_iter
+        .map(|u| fewest_streets_tree(network, *u))
+        .collect();
+    duplicate_paths_by(
+        graph,
+        nodes_with_odd_degree,
+        |u, v| trees[position[&u]][v].0,
+        |u, v| {
+            let tree = &trees[position[&u]];
+            let mut path = vec![v];
+            let mut node = v;
+            while let Some(previous) = tree[node].1 {
+                path.push(previous);
+                node = previous;
+            }
+            path.reverse();
+            hop_edges(network, &path)
+        },
+    )
+}
+
+fn fewest_streets_tree(network: &RoadGraph, from: usize) -> Vec<(u64, Option<usize>)> {
+    // dijkstra where every street costs STREET_COST on top of its length, so the fewest streets win and length only
+    // breaks ties. entry v is the cost of getting to v and the node before it there, u64::MAX and None if it can't
+    // be reached
+    let mut best = vec![(u64::MAX, None); network.node_count()];
+    let mut heap = BinaryHeap::new();
+    best[from].0 = 0;
+    heap.push(Reverse((0, from)));
+    while let Some(Reverse((cost, node))) = heap.pop() {
+        // a node is pushed again every time it gets closer, so older entries for it are stale
+        if cost > best[node].0 {
+            continue;
+        }
+        for (next, weight) in neighbors_vec(network, node) {
+            let through = cost.saturating_add(STREET_COST + weight as u64);
+            if through < best[next].0 {
+                best[next] = (through, Some(node));
+                heap.push(Reverse((through, next)));
+            }
+        }
+    }
+    best
+}
+
+fn wide_distance(distance: usize) -> u64 {
+    // a graph distance as the matching's u64 costs, no path staying no path on a 32 bit target too
+    if distance == usize::MAX {
+        u64::MAX
+    } else {
+        distance as u64
+    }
+}
+
+fn matching_cost_text(cost: u64) -> String {
+    // a matching cost for the log, which with STREET_COST in it is a count of streets as well as a length
+    match cost / STREET_COST {
+        0 => format!("{} ft", format_weight(cost as usize)),
+        streets => format!(
+            "{streets} streets, {} ft",
+            format_weight((cost % STREET_COST) as usize)
+        ),
+    }
+}
+
+fn duplicate_paths_using(
+    graph: &RoadGraph,
+    network: &RoadGraph,
+    nodes_with_odd_degree: &[usize],
+    distances: &[Vec<usize>],
+) -> EulerizationReport {
+    // duplicate_paths, with network's all_pairs_shortest_paths already worked out. the distance matrix doesn't say
+    // which streets a path uses, so each pair gets its own dijkstra run for that
+    duplicate_paths_by(
+        graph,
+        nodes_with_odd_degree,
+        |u, v| wide_distance(distances[u][v]),
+        |u, v| path_edges(network, &dijkstra(network, u), v),
+    )
+}
+
+fn duplicate_paths_located(
+    graph: &RoadGraph,
+    network: &RoadGraph,
+    nodes_with_odd_degree: &[usize],
+    coords: &[Option<(f64, f64)>],
+) -> EulerizationReport {
+    // duplicate_paths, but where every intersection with a street is located the distances come from an a* search
+    // per pair of odd nodes instead of between every pair of intersections, which is far less work on a big map
+    // with few dead ends and odd corners. anything unlocated falls back to duplicate_paths
+    let Some(scale) = straight_line_scale(network, coords) else {
+        return duplicate_paths(graph, network, nodes_with_odd_degree);
+    };
+    let started = Stopwatch::start();
+    let position: BTreeMap<usize, usize> = nodes_with_odd_degree
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (*node, i))
+        .collect();
+    let search = |from: usize, to: usize| {
+        let goal = coords[to].unwrap_or_default();
+        astar(network, from, to, |node| {
+            let here = coords[node].unwrap_or(goal);
+            (haversine_feet(here, goal) * WEIGHT_SCALE as f64 * scale) as usize
+        })
+    };
+    let k = nodes_with_odd_degree.len();
+    #[cfg(feature = "parallel")]
+    let rows_iter = (0..k).into_par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let rows_iter = 0..k;
+    let upper: Vec<Vec<usize>> = rows_iter
+        .map(|i| {
+            (i + 1..k)
+                .map(|j| {
+                    search(nodes_with_odd_degree[i], nodes_with_odd_degree[j])
+                        .map_or(usize::MAX, |(distance, _)| distance)
+                })
+                .collect()
+        })
+        .collect();
+    debug!(
+        "shortest paths between {k} odd intersections by a* in {}",
+        started
+    );
+    duplicate_paths_by(
+        graph,
+        nodes_with_odd_degree,
+        |u, v| {
+            let (i, j) = (position[&u], position[&v]);
+            wide_distance(match i.cmp(&j) {
+                std::cmp::Ordering::Less => upper[i][j - i - 1],
+                std::cmp::Ordering::Greater => upper[j][i - j - 1],
+                std::cmp::Ordering::Equal => 0,
+            })
+        },
+        |u, v| {
+            hop_edges(
+                network,
+                &search(u, v).map(|(_, path)| path).unwrap_or_default(),
+            )
+        },
+    )
+}
+
+fn straight_line_scale(network: &RoadGraph, coords: &[Option<(f64, f64)>]) -> Option<f64> {
+    // how much of the straight line distance between two intersections a search can count on still having to go,
+    // for astar. a street can be written shorter than the straight line, by a typo or in meters or minutes, so it's
+    // the least any street's length is of its straight line, and no more than all of it. that keeps the estimate
+    // from ever running past the real distance, which is what keeps a* exact. None if any intersection with a
+    // street isn't located
+    let located = |node: usize| coords.get(node).copied().flatten();
+    let mut scale: f64 = 1.0;
+    for (u, v, weight) in edge_list(network) {
+        let (from, to) = (located(u)?, located(v)?);
+        let straight = haversine_feet(from, to) * WEIGHT_SCALE as f64;
+        if straight > 0.0 {
+            scale = scale.min(weight as f64 / straight);
+        }
+    }
+    // a little under, so rounding the estimate can't tip it over
+    Some(scale * 0.999)
+}
+
+fn duplicate_paths_by(
+    graph: &RoadGraph,
+    nodes_with_odd_degree: &[usize],
+    distance: impl Fn(usize, usize) -> u64,
+    path: impl Fn(usize, usize) -> Vec<(usize, usize, usize)> + Sync,
+) -> EulerizationReport {
+    // adds to graph the path(u, v) between each pair of a cheapest pairing of the odd nodes by distance(u, v)
+    if nodes_with_odd_degree.is_empty() {
+        return EulerizationReport {
+            odd_node_count: 0,
+            duplicated_edge_count: 0,
+            duplicated_feet: 0.0,
+        };
+    }
+    // pair the odd nodes up as cheaply as possible, then walk the street along the shortest path between each pair
+    // a second time. that adds one to the degree of both ends and two to everything in between, so every node
+    // comes out even. the paths are independent, so with the parallel feature they're found on rayon's thread pool
+    let started = Stopwatch::start();
+    let pairs = pair_odd_nodes_by(nodes_with_odd_degree, &distance);
+    let matching_cost: u64 = pairs.iter().map(|(u, v)| distance(*u, *v)).sum();
+    info!(
+        "paired {} odd intersections for {} in {}",
+        nodes_with_odd_degree.len(),
+        matching_cost_text(matching_cost),
+        started
+    );
+    for (u, v) in &pairs {
+        trace!(
+            "paired {u} with {v}, {} apart",
+            matching_cost_text(distance(*u, *v))
+        );
+    }
+    #[cfg(feature = "parallel")]
+    let pairs_iter = pairs.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let pairs_iter = pairs.iter();
+    let duplicates: Vec<(usize, usize, usize)> = pairs_iter
+        .map(|(u, v)| path(*u, *v))
+        .collect::<Vec<Vec<(usize, usize, usize)>>>()
+        .concat();
+    let added: usize = duplicates.iter().map(|(_, _, weight)| weight).sum();
+    info!(
+        "duplicated {} streets ({} ft) along the matched paths",
+        duplicates.len(),
+        format_weight(added)
+    );
+    let report = EulerizationReport {
+        odd_node_count: nodes_with_odd_degree.len(),
+        duplicated_edge_count: duplicates.len(),
+        duplicated_feet: added as f64 / WEIGHT_SCALE as f64,
+    };
+    for (u, v, weight) in duplicates {
+        let _ = graph.add_edge_with_value(u, v, weight);
+    }
+    report
+}
+
+fn required_edges(
+    graph: &RoadGraph,
+    optional: &BTreeSet<(usize, usize)>,
+) -> Vec<(usize, usize, usize)> {
+    edge_list(graph)
+        .into_iter()
+        .filter(|(u, v, _)| !optional.contains(&(*u, *v)))
+        .collect()
+}
+
+pub fn eulerize_required(
+    graph: &RoadGraph,
+    optional: &BTreeSet<(usize, usize)>,
+) -> Result<RoadGraph, PacsamError> {
+    let route = join_required(graph, optional, None)?;
+    duplicate_paths(&route, graph, &odd_degree_nodes(&route));
+    Ok(route)
+}
+
+pub fn eulerize_from_depot(
+    graph: &RoadGraph,
+    optional: &BTreeSet<(usize, usize)>,
+    depot: usize,
+) -> Result<RoadGraph, PacsamError> {
+    // eulerize_required for a route that also passes through depot, which can be off the turf with only optional
+    // streets (see connector_edges) out to it. a circuit from the depot then covers the turf and comes back
+    let route = join_required(graph, optional, Some(depot))?;
+    duplicate_paths(&route, graph, &odd_degree_nodes(&route));
+    Ok(route)
+}
+
+fn join_required(
+    graph: &RoadGraph,
+    optional: &BTreeSet<(usize, usize)>,
+    visit: Option<usize>,
+) -> Result<RoadGraph, PacsamError> {
+    // the rural postman version of eulerize. only streets not in optional have to be covered, and the rest are
+    // there as shortcuts. the returned graph holds every traversal of the route: each required street once, plus
+    // whatever streets of graph get driven to join things up. first the pieces of the required network are joined
+    // into one by the shortest paths between them, cheapest link first, then the odd nodes of that are paired up
+    // over graph the same way eulerize does it. dead ends come out odd and get paired too, so there's no separate
+    // cul de sac pass. a node to visit that no required street touches, like a depot off the turf, is joined in as
+    // a piece of its own
+    let mut traversals = required_edges(graph, optional);
+    let required: RoadGraph = GraphBuilder::new()
+        .edges_with_values(traversals.clone())
+        .build();
+    let mut pieces = connected_components(&required);
+    if let Some(node) = visit.filter(|node| !pieces.iter().any(|piece| piece.contains(node))) {
+        pieces.push(vec![node]);
+    }
+    if pieces.len() > 1 {
+        let mut piece_of = vec![None; graph.node_count()];
+        for (i, piece) in pieces.iter().enumerate() {
+            for node in piece {
+                piece_of[*node] = Some(i);
+            }
+        }
+        let mut joined = vec![false; pieces.len()];
+        joined[0] = true;
+        let mut sources = pieces[0].clone();
+        for _ in 1..pieces.len() {
+            // one dijkstra out of everything joined so far. it settles nodes nearest first, so the first one in a
+            // piece still out is the end of the cheapest link
+            let sp_tree = dijkstra_from(graph, &sources);
+            let cheapest = sp_tree.iter().find_map(|vertex| {
+                piece_of[vertex.idx]
+                    .filter(|piece| !joined[*piece] && vertex.distance_from_u != usize::MAX)
+                    .map(|piece| (piece, vertex.idx))
+            });
+            let Some((piece, v)) = cheapest else {
+                // whatever isn't joined yet can't be reached from the rest, which counts as one piece
+                let (reached, unreached): (Vec<_>, Vec<_>) =
+                    pieces.iter().zip(&joined).partition(|(_, joined)| **joined);
+                let mut components = vec![reached
+                    .into_iter()
+                    .flat_map(|(piece, _)| piece.clone())
+                    .collect::<Vec<usize>>()];
+                components.extend(unreached.into_iter().map(|(piece, _)| piece.clone()));
+                return Err(PacsamError::Disconnected(components));
+            };
+            joined[piece] = true;
+            sources.extend(&pieces[piece]);
+            traversals.extend(path_edges(graph, &sp_tree, v));
+        }
+    }
+    // the shortest paths added later can pass through nodes no required street touches, so the route keeps every
+    // node of graph. add_edge_with_value fails on a node past the end
+    Ok(graph_with_nodes(traversals, graph.node_count()))
+}
+
+pub fn matched_pairs(graph: &RoadGraph) -> Vec<(usize, usize)> {
+    // the odd intersections eulerize would join up, by their node indices in graph. the shortest path between each
+    // pair is what gets driven twice. this is on the graph as given, so run fix_culdesacs on it first to see the
+    // pairs the route command uses
+    pair_odd_nodes(graph, &odd_degree_nodes(graph))
+}
+
+fn pair_odd_nodes(graph: &RoadGraph, nodes_with_odd_degree: &[usize]) -> Vec<(usize, usize)> {
+    pair_odd_nodes_by(
+        nodes_with_odd_degree,
+        odd_node_distances(graph, nodes_with_odd_degree),
+    )
+}
+
+fn pair_odd_nodes_by(
+    nodes_with_odd_degree: &[usize],
+    distance: impl Fn(usize, usize) -> u64,
+) -> Vec<(usize, usize)> {
+    // the cost of pairing up odd nodes i and j is the shortest path between them. pairs with no path between them
+    // stay at u64::MAX, so the matching can never pick them. the matching numbers the odd nodes 0..k, and they're
+    // mapped back after
+    if nodes_with_odd_degree.is_empty() {
+        return vec![];
+    }
+    let k = nodes_with_odd_degree.len();
+    let mut cost = vec![vec![u64::MAX; k]; k];
+    for (i, u) in nodes_with_odd_degree.iter().enumerate() {
+        for (j, v) in nodes_with_odd_degree.iter().enumerate().skip(i + 1) {
+            cost[i][j] = distance(*u, *v);
+            cost[j][i] = cost[i][j];
+        }
+    }
+    minimum_weight_matching(&cost, nodes_with_odd_degree)
+}
+
+fn path_edges(graph: &RoadGraph, sp_tree: &[Vertex], target: usize) -> Vec<(usize, usize, usize)> {
+    // the streets along the shortest path to target
+    hop_edges(graph, &shortest_path(sp_tree, target))
+}
+
+fn hop_edges(graph: &RoadGraph, path: &[usize]) -> Vec<(usize, usize, usize)> {
+    // the streets a path of nodes walks, taking the shorter of any parallel streets on each hop
+    path.windows(2)
+        .filter_map(|hop| {
+            neighbors_vec(graph, hop[0])
+                .into_iter()
+                .filter(|(node, _)| *node == hop[1])
+                .map(|(_, weight)| (hop[0], hop[1], weight))
+                .min_by_key(|(_, _, weight)| *weight)
+        })
+        .collect()
+}
+
+fn shortest_path(sp_tree: &[Vertex], target: usize) -> Vec<usize> {
+    // follows predecessors back from target to the source dijkstra started from. empty if target was never reached
+    let mut path: Vec<usize> = vec![];
+    let mut current = sp_tree.iter().find(|v| v.idx == target);
+    if current.is_some_and(|v| v.distance_from_u == usize::MAX) {
+        return path;
+    }
+    while let Some(vertex) = current {
+        path.push(vertex.idx);
+        current = vertex
+            .predecessor
+            .and_then(|pred| sp_tree.iter().find(|v| v.idx == pred));
+    }
+    path.reverse();
+    path
+}
+
+// past this many odd nodes, trying every pairing takes too long and the matching falls back to a heuristic
+const EXACT_MATCHING_LIMIT: usize = 20;
+
+fn minimum_weight_matching(cost: &[Vec<u64>], odd_nodes: &[usize]) -> Vec<(usize, usize)> {
+    // cost[i][j] is what pairing odd_nodes[i] with odd_nodes[j] costs. up to EXACT_MATCHING_LIMIT odd nodes the
+    // matching is exact, by trying every way of pairing off the lowest unmatched node. past that it's approximate:
+    // greedily take the cheapest pairs, then keep swapping partners between two pairs while that saves distance
+    let k = odd_nodes.len();
+    let pairs = if k <= EXACT_MATCHING_LIMIT {
+        debug!("matching {k} odd nodes exactly");
+        exact_matching(cost)
+    } else {
+        debug!("matching {k} odd nodes greedily, past the exact limit of {EXACT_MATCHING_LIMIT}");
+        greedy_matching(cost)
+    };
+    pairs
+        .into_iter()
+        .map(|(i, j)| (odd_nodes[i], odd_nodes[j]))
+        .collect()
+}
+
+fn exact_matching(cost: &[Vec<u64>]) -> Vec<(usize, usize)> {
+    // best[mask] is the cheapest way to pair off the nodes whose bits are set in mask
+    let k = cost.len();
+    let full = (1usize << k) - 1;
+    let mut best = vec![u64::MAX; full + 1];
+    let mut choice = vec![(0, 0); full + 1];
+    best[0] = 0;
+    for mask in 1..=full {
+        if mask.count_ones() % 2 != 0 {
+            continue;
+        }
+        let i = mask.trailing_zeros() as usize;
+        for (j, weight) in cost[i].iter().enumerate().skip(i + 1) {
+            if mask & (1 << j) == 0 {
+                continue;
+            }
+            let rest = mask & !(1 << i) & !(1 << j);
+            let total = best[rest].saturating_add(*weight);
+            if total < best[mask] || choice[mask] == (0, 0) {
+                best[mask] = total;
+                choice[mask] = (i, j);
+            }
+        }
+    }
+    let mut pairs: Vec<(usize, usize)> = vec![];
+    let mut mask = full;
+    while mask != 0 {
+        let (i, j) = choice[mask];
+        pairs.push((i, j));
+        mask &= !(1 << i) & !(1 << j);
+    }
+    pairs
+}
+
+fn greedy_matching(cost: &[Vec<u64>]) -> Vec<(usize, usize)> {
+    let k = cost.len();
+    let mut candidates: Vec<(u64, usize, usize)> = vec![];
+    for (i, row) in cost.iter().enumerate() {
+        for (j, weight) in row.iter().enumerate().skip(i + 1) {
+            candidates.push((*weight, i, j));
+        }
+    }
+    candidates.sort();
+    let mut matched = vec![false; k];
+    let mut pairs: Vec<(usize, usize)> = vec![];
+    for (_, i, j) in candidates {
+        if !matched[i] && !matched[j] {
+            matched[i] = true;
+            matched[j] = true;
+            pairs.push((i, j));
+        }
+    }
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for p in 0..pairs.len() {
+            for q in p + 1..pairs.len() {
+                let (a, b) = pairs[p];
+                let (c, d) = pairs[q];
+                let current = cost[a][b].saturating_add(cost[c][d]);
+                if cost[a][c].saturating_add(cost[b][d]) < current {
+                    pairs[p] = (a, c);
+                    pairs[q] = (b, d);
+                    improved = true;
+                } else if cost[a][d].saturating_add(cost[b][c]) < current {
+                    pairs[p] = (a, d);
+                    pairs[q] = (b, c);
+                    improved = true;
+                }
+            }
+        }
+    }
+    pairs
+}
+
+struct Vertex {
+    idx: usize,
+    distance_from_u: usize,
+    // the node before this one on the shortest path from u. None for u itself and anything unreachable
+    predecessor: Option<usize>,
+}
+impl Vertex {
+    fn new(idx: usize, distance_from_u: usize) -> Self {
+        Vertex {
+            idx,
+            distance_from_u,
+            predecessor: None,
+        }
+    }
+    fn set_distance(&mut self, distance: usize, predecessor: usize) {
+        self.distance_from_u = distance;
+        self.predecessor = Some(predecessor);
+    }
+}
+impl Ord for Vertex {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // equal distances fall back to the lower index, so ties settle the same way every run and the same
+        // shortest paths get duplicated
+        (self.distance_from_u, self.idx).cmp(&(other.distance_from_u, other.idx))
+    }
+}
+impl PartialOrd for Vertex {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl PartialEq for Vertex {
+    fn eq(&self, other: &Self) -> bool {
+        (self.idx, self.distance_from_u) == (other.idx, other.distance_from_u)
+    }
+}
+impl Eq for Vertex {}
+
+fn dijkstra(graph: &RoadGraph, initial: usize) -> Vec<Vertex> {
+    dijkstra_from(graph, &[initial])
+}
+
+fn dijkstra_from(graph: &RoadGraph, sources: &[usize]) -> Vec<Vertex> {
+    // distances to the nearest of several starting nodes, each of which has no predecessor
+    dijkstra_with(graph.node_count(), sources, |node| {
+        neighbors_vec(graph, node)
+    })
+}
+
+fn directed_dijkstra(graph: &DirectedRoadGraph, initial: usize) -> Vec<Vertex> {
+    // the same search, but only along arcs the right way
+    dijkstra_with(graph.node_count(), &[initial], |node| out_arcs(graph, node))
+}
+
+fn dijkstra_with(
+    node_count: usize,
+    sources: &[usize],
+    neighbors: impl Fn(usize) -> Vec<(usize, usize)>,
+) -> Vec<Vertex> {
+    // settled vertices come out in order of distance. anything left over couldn't be reached and stays at
+    // usize::MAX
+    let mut distances: Vec<Vertex> = (0..node_count)
+        .map(|i| Vertex::new(i, usize::MAX))
+        .collect();
+    let mut visited = vec![false; node_count];
+    let mut sp_tree: Vec<Vertex> = vec![];
+    let mut heap = BinaryHeap::new();
+    for source in sources {
+        distances[*source].distance_from_u = 0;
+        heap.push(Reverse(Vertex::new(*source, 0)));
+    }
+    while let Some(Reverse(vertex)) = heap.pop() {
+        let current = vertex.idx;
+        // a vertex is pushed again every time it gets closer, so older entries for it are stale
+        if visited[current] {
+            continue;
+        }
+        visited[current] = true;
+        let cumulative_dist = distances[current].distance_from_u;
+        for (target, weight) in neighbors(current) {
+            if !visited[target]
+                && weight.saturating_add(cumulative_dist) < distances[target].distance_from_u
+            {
+                distances[target].set_distance(weight.saturating_add(cumulative_dist), current);
+                heap.push(Reverse(Vertex::new(
+                    target,
+                    distances[target].distance_from_u,
+                )));
+            }
+        }
+        sp_tree.push(Vertex {
+            idx: current,
+            distance_from_u: cumulative_dist,
+            predecessor: distances[current].predecessor,
+        });
+    }
+    sp_tree.extend(distances.into_iter().filter(|vertex| !visited[vertex.idx]));
+    sp_tree
+}
+
+fn astar(
+    graph: &RoadGraph,
+    from: usize,
+    to: usize,
+    estimate: impl Fn(usize) -> usize,
+) -> Option<(usize, Vec<usize>)> {
+    // the shortest distance from `from` to `to` and the nodes along the way, or None with no path. it's dijkstra
+    // that settles first whatever looks closest to `to` counting estimate(node) for the rest of the way, which has
+    // to never overshoot and grow by no more than a street's length from one end to the other. ties go to the
+    // lower index like dijkstra's
+    let n = graph.node_count();
+    let mut distance = vec![usize::MAX; n];
+    let mut predecessor: Vec<Option<usize>> = vec![None; n];
+    let mut settled = vec![false; n];
+    let mut heap = BinaryHeap::new();
+    distance[from] = 0;
+    heap.push(Reverse((estimate(from), from)));
+    while let Some(Reverse((_, node))) = heap.pop() {
+        if settled[node] {
+            continue;
+        }
+        settled[node] = true;
+        if node == to {
+            break;
+        }
+        for (target, weight) in neighbors_vec(graph, node) {
+            let through = distance[node].saturating_add(weight);
+            if !settled[target] && through < distance[target] {
+                distance[target] = through;
+                predecessor[target] = Some(node);
+                heap.push(Reverse((through.saturating_add(estimate(target)), target)));
+            }
+        }
+    }
+    if distance[to] == usize::MAX {
+        return None;
+    }
+    let mut path = vec![to];
+    while let Some(before) = predecessor[*path.last().expect("starts with to")] {
+        path.push(before);
+    }
+    path.reverse();
+    Some((distance[to], path))
+}
+
+fn is_connected(graph: &RoadGraph) -> bool {
+    // isolated nodes don't matter, they have no streets to reach
+    connected_components(graph).len() <= 1
+}
+
+pub fn is_tree(graph: &RoadGraph) -> bool {
+    // a connected network with one fewer street than intersections has no loops in it
+    let components = connected_components(graph);
+    components.len() == 1 && graph.edge_count() == components[0].len() - 1
+}
+
+pub fn tree_route(graph: &RoadGraph, root: usize) -> Vec<usize> {
+    // on a tree the best route is a depth first walk that goes down every branch and comes straight back, so each
+    // street is walked exactly twice. children are visited lowest index first
+    let children = |node: usize, parent: Option<usize>| {
+        let mut children: Vec<usize> = neighbors_vec(graph, node)
+            .into_iter()
+            .map(|(target, _)| target)
+            .filter(|target| Some(*target) != parent)
+            .collect();
+        children.sort_by(|a, b| b.cmp(a));
+        children
+    };
+    let mut path = vec![root];
+    let mut stack: Vec<(usize, Vec<usize>)> = vec![(root, children(root, None))];
+    while let Some((node, unvisited)) = stack.last_mut() {
+        let node = *node;
+        if let Some(child) = unvisited.pop() {
+            path.push(child);
+            stack.push((child, children(child, Some(node))));
+        } else {
+            stack.pop();
+            if let Some((parent, _)) = stack.last() {
+                path.push(*parent);
+            }
+        }
+    }
+    path
+}
+
+pub enum StartCriterion {
+    // start where the circuit passes closest to this node
+    NearestTo(usize),
+    // start by walking the street between these two nodes, from the first to the second where the circuit allows
+    PriorityStreet(usize, usize),
+}
+
+impl StartCriterion {
+    fn nodes(&self) -> Vec<usize> {
+        match self {
+            StartCriterion::NearestTo(node) => vec![*node],
+            StartCriterion::PriorityStreet(u, v) => vec![*u, *v],
+        }
+    }
+}
+
+pub fn best_start(path: &[usize], graph: &RoadGraph, criterion: &StartCriterion) -> Option<usize> {
+    // the position in a closed path to start it from so it does best by criterion, or None if the path isn't
+    // closed or never gets near what the criterion asks for. ties go to the earliest position
+    if path.len() < 2 || path[0] != path[path.len() - 1] {
+        return None;
+    }
+    let cycle = &path[..path.len() - 1];
+    match criterion {
+        StartCriterion::NearestTo(node) => {
+            let mut distance = vec![usize::MAX; graph.node_count()];
+            for vertex in dijkstra(graph, *node) {
+                distance[vertex.idx] = vertex.distance_from_u;
+            }
+            (0..cycle.len())
+                .filter(|position| distance[cycle[*position]] != usize::MAX)
+                .min_by_key(|position| distance[cycle[*position]])
+        }
+        StartCriterion::PriorityStreet(u, v) => {
+            let walks = |from: usize, to: usize| {
+                (0..cycle.len())
+                    .find(|position| path[*position] == from && path[position + 1] == to)
+            };
+            walks(*u, *v).or_else(|| walks(*v, *u))
+        }
+    }
+}
+
+pub fn rotate_circuit(path: &[usize], position: usize) -> Vec<usize> {
+    // the same closed path, started from its node at position instead
+    if path.len() < 2 || path[0] != path[path.len() - 1] {
+        return path.to_vec();
+    }
+    let cycle = &path[..path.len() - 1];
+    let start = position % cycle.len();
+    let mut rotated: Vec<usize> = cycle[start..]
+        .iter()
+        .chain(&cycle[..start])
+        .copied()
+        .collect();
+    rotated.push(rotated[0]);
+    rotated
+}
+
+fn start_criterion(
+    config: &Config,
+    labels: &BTreeMap<usize, String>,
+) -> Result<Option<StartCriterion>, PacsamError> {
+    // a priority street wins over a parking spot if both are given
+    if let Some((u, v)) = &config.priority_street {
+        return Ok(Some(StartCriterion::PriorityStreet(
+            resolve_node(u, labels)?,
+            resolve_node(v, labels)?,
+        )));
+    }
+    config
+        .best_start
+        .as_deref()
+        .map(|name| Ok(StartCriterion::NearestTo(resolve_node(name, labels)?)))
+        .transpose()
+}
+
+fn start_where(
+    path: Vec<usize>,
+    graph: &RoadGraph,
+    criterion: Option<&StartCriterion>,
+) -> Vec<usize> {
+    match criterion.and_then(|criterion| best_start(&path, graph, criterion)) {
+        Some(position) => rotate_circuit(&path, position),
+        None => path,
+    }
+}
+
+pub fn resolve_node(name: &str, labels: &BTreeMap<usize, String>) -> Result<usize, PacsamError> {
+    // a node given by a name from the input, by its index, or by the letters the output uses for it, tried in
+    // that order
+    let name = name.trim();
+    if let Some((node, _)) = labels.iter().find(|(_, label)| label.as_str() == name) {
+        return Ok(*node);
+    }
+    if let Ok(node) = name.parse::<usize>() {
+        return Ok(node);
+    }
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_uppercase()) {
+        // the inverse of node_letters: bijective base 26, so A is 0, Z is 25 and AA is 26
+        let number = name
+            .bytes()
+            .fold(0usize, |number, c| number * 26 + (c - b'A') as usize + 1);
+        return Ok(number - 1);
+    }
+    Err(PacsamError::UnknownNode(name.to_string()))
+}
+
+pub fn rotate_to_midpoint(path: &[usize], checkpoint: usize) -> Vec<usize> {
+    // an euler circuit can start anywhere along itself, so it can be rotated until the checkpoint (say, a lunch stop)
+    // sits halfway through. paths that aren't closed or don't pass the checkpoint are returned as they are
+    if path.len() < 2 || path[0] != path[path.len() - 1] {
+        return path.to_vec();
+    }
+    let cycle = &path[..path.len() - 1];
+    let Some(position) = cycle.iter().position(|node| *node == checkpoint) else {
+        return path.to_vec();
+    };
+    let middle = path.len() / 2 % cycle.len();
+    rotate_circuit(path, (position + cycle.len() - middle) % cycle.len())
+}
+
+fn all_pairs_shortest_paths(graph: &RoadGraph) -> Vec<Vec<usize>> {
+    // floyd-warshall: entry [u][v] is the shortest distance between u and v, with usize::MAX where there is no
+    // path. after round k every distance may pass through any of the first k nodes
+    let started = Stopwatch::start();
+    let n = graph.node_count();
+    let mut distances = vec![vec![usize::MAX; n]; n];
+    for (u, row) in distances.iter_mut().enumerate() {
+        row[u] = 0;
+        for (v, weight) in neighbors_vec(graph, u) {
+            row[v] = row[v].min(weight);
+        }
+    }
+    for k in 0..n {
+        let through = distances[k].clone();
+        for row in distances.iter_mut() {
+            let to_k = row[k];
+            if to_k == usize::MAX {
+                continue;
+            }
+            for (distance, from_k) in row.iter_mut().zip(&through) {
+                let candidate = to_k.saturating_add(*from_k);
+                if candidate < *distance {
+                    *distance = candidate;
+                }
+            }
+        }
+    }
+    debug!(
+        "shortest paths between all {n} intersections in {}",
+        started
+    );
+    distances
+}
+
+pub fn all_pairs_distances(graph: &RoadGraph) -> Vec<Vec<usize>> {
+    // row u holds the shortest distance from u to every other node, with usize::MAX where there is no path. a
+    // dijkstra per row costs about edges * log(nodes) each, so on a dense map floyd-warshall's nodes per row is the
+    // cheaper of the two. street maps are sparse and get the dijkstras, run in parallel with the feature on
+    let n = graph.node_count();
+    if graph.edge_count() * (n.max(2).ilog2() as usize) > n * n {
+        return all_pairs_shortest_paths(graph);
+    }
+    #[cfg(feature = "parallel")]
+    let rows = (0..n).into_par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let rows = 0..n;
+    rows.map(|u| all_pairs_row(graph, u)).collect()
+}
+
+fn all_pairs_row(graph: &RoadGraph, u: usize) -> Vec<usize> {
+    let mut row = vec![usize::MAX; graph.node_count()];
+    for vertex in dijkstra(graph, u) {
+        row[vertex.idx] = vertex.distance_from_u;
+    }
+    row
+}
+
+pub fn node_tour(graph: &RoadGraph, required: &[usize]) -> (Vec<usize>, usize) {
+    // for jobs that only need every listed intersection visited, not every street walked. this is a travelling
+    // salesman tour over shortest path distances: built nearest neighbor first, then improved with 2-opt until no
+    // swap helps. the tour returns to where it started, and its total length (in stored hundredths) comes back
+    // alongside it
+    if required.is_empty() {
+        return (vec![], 0);
+    }
+    let distances: Vec<Vec<usize>> = required
+        .iter()
+        .map(|u| {
+            let mut row = vec![usize::MAX; graph.node_count()];
+            for vertex in dijkstra(graph, *u) {
+                row[vertex.idx] = vertex.distance_from_u;
+            }
+            required.iter().map(|v| row[*v]).collect()
+        })
+        .collect();
+    let mut order: Vec<usize> = vec![0];
+    let mut unvisited: Vec<usize> = (1..required.len()).collect();
+    while !unvisited.is_empty() {
+        let last = order[order.len() - 1];
+        let nearest = (0..unvisited.len())
+            .min_by_key(|i| distances[last][unvisited[*i]])
+            .expect("unvisited is not empty");
+        order.push(unvisited.swap_remove(nearest));
+    }
+    order.push(0);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..order.len() - 2 {
+            for j in i + 1..order.len() - 1 {
+                let (a, b, c, d) = (order[i - 1], order[i], order[j], order[j + 1]);
+                let before = distances[a][b].saturating_add(distances[c][d]);
+                let after = distances[a][c].saturating_add(distances[b][d]);
+                if after < before {
+                    order[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+    let length = order.windows(2).fold(0usize, |total, hop| {
+        total.saturating_add(distances[hop[0]][hop[1]])
+    });
+    (order.into_iter().map(|i| required[i]).collect(), length)
+}
+
+pub struct HopContext {
+    pub from: usize,
+    pub to: usize,
+    // the node the walk arrived at `from` by, if it didn't start there
+    pub previous: Option<usize>,
+    // this street's position among the unused streets at `from`, in the order find_cycle takes them by default
+    pub candidate: usize,
+}
+
+pub fn find_cycle(graph: &RoadGraph) -> Result<Vec<usize>, PacsamError> {
+    find_cycle_from(graph, 0)
+}
+
+pub fn find_cycle_from(graph: &RoadGraph, start: usize) -> Result<Vec<usize>, PacsamError> {
+    // with every street scoring the same, the first unused one is taken each time
+    find_cycle_with(graph, start, |_| 0)
+}
+
+pub fn find_cycle_with(
+    graph: &RoadGraph,
+    start: usize,
+    score: impl Fn(&HopContext) -> i64,
+) -> Result<Vec<usize>, PacsamError> {
+    find_cycle_seeded(graph, start, None, score)
+}
+
+fn find_cycle_seeded(
+    graph: &RoadGraph,
+    start: usize,
+    seed: Option<u64>,
+    score: impl Fn(&HopContext) -> i64,
+) -> Result<Vec<usize>, PacsamError> {
+    // same as find_cycle, but whenever the walk has a choice of street the one the score ranks highest is taken
+    // (the earliest candidate on a tie). any choice still leads to a valid circuit, so this only shapes the route.
+    // candidates come lowest node first, or in a random order for seed.
+    // hierholzer's algorithm only gives a real circuit once every node has even degree. on anything else it still
+    // returns a walk, just not a valid one, so refuse up front
+    let odd_nodes = odd_degree_nodes(graph);
+    if !odd_nodes.is_empty() {
+        return Err(PacsamError::NotEulerizable {
+            odd_nodes,
+            unreached: 0,
+        });
+    }
+    // with no streets at all there's nothing to walk, so the circuit is just standing at start
+    if graph.edge_count() == 0 && start < graph.node_count() {
+        return Ok(vec![start]);
+    }
+    // the circuit begins and ends at start, e.g. wherever the car is parked, so it has to be on a street
+    if start >= graph.node_count() || graph.degree(start) == 0 {
+        return Err(PacsamError::NoSuchNode(start));
+    }
+    let started = Stopwatch::start();
+    // get a vec of all edges, represented once each: from the lower end, with each self loop (listed twice on
+    // its node) kept once
+    let edges: Vec<(usize, usize)> = shuffled(
+        edge_list(graph)
+            .into_iter()
+            .map(|(u, v, _)| (u, v))
+            .collect(),
+        seed,
+    );
+    // the unused edges are kept in one list that's trimmed with swap_remove, and their order in it is the order
+    // candidates are offered in. position[id] tracks where edge id currently sits, and incident lists the ids at
+    // each node, so a step only looks at its own node's edges instead of scanning the whole list
+    let mut position: Vec<usize> = (0..edges.len()).collect();
+    let mut unused: Vec<usize> = (0..edges.len()).collect();
+    let mut used = vec![false; edges.len()];
+    let mut incident: Vec<Vec<usize>> = vec![vec![]; graph.node_count()];
+    for (id, (u, v)) in edges.iter().enumerate() {
+        incident[*u].push(id);
+        if u != v {
+            incident[*v].push(id);
+        }
+    }
+    // hierholzer's algorithm finds the euler circuit. the top of the stack is the end of the vec
+    let mut path: Vec<usize> = vec![];
+    let mut vertices_with_unused_edges: Vec<usize> = vec![start];
+    while let Some(&v1) = vertices_with_unused_edges.last() {
+        incident[v1].retain(|id| !used[*id]);
+        let mut neighbors = incident[v1].clone();
+        neighbors.sort_by_key(|id| position[*id]);
+        if neighbors.is_empty() {
+            path.push(v1);
+            vertices_with_unused_edges.pop();
+        } else {
+            let previous = vertices_with_unused_edges
+                .len()
+                .checked_sub(2)
+                .map(|below| vertices_with_unused_edges[below]);
+            let mut best = 0;
+            let mut best_score = i64::MIN;
+            for (candidate, id) in neighbors.iter().enumerate() {
+                let edge = edges[*id];
+                let to = if edge.0 == v1 { edge.1 } else { edge.0 };
+                let candidate_score = score(&HopContext {
+                    from: v1,
+                    to,
+                    previous,
+                    candidate,
+                });
+                if candidate_score > best_score {
+                    best = candidate;
+                    best_score = candidate_score;
+                }
+            }
+            let chosen_edge = edges[neighbors[best]];
+            // parallel copies of a street are interchangeable, so the one taken out is the earliest
+            let removed = *neighbors
+                .iter()
+                .find(|id| edges[**id] == chosen_edge)
+                .expect("exists");
+            // the list is unordered, so swap_remove is faster with no downside
+            let slot = position[removed];
+            unused.swap_remove(slot);
+            if let Some(&moved) = unused.get(slot) {
+                position[moved] = slot;
+            }
+            used[removed] = true;
+            if chosen_edge.0 == v1 {
+                vertices_with_unused_edges.push(chosen_edge.1);
+            } else {
+                vertices_with_unused_edges.push(chosen_edge.0);
+            }
+        }
+    }
+    // anything still unused is in a piece of the graph the walk from start never reached
+    if !unused.is_empty() {
+        return Err(PacsamError::NotEulerizable {
+            odd_nodes: vec![],
+            unreached: unused.len(),
+        });
+    }
+    debug!(
+        "circuit of {} streets from node {start} in {}",
+        path.len() - 1,
+        started
+    );
+    Ok(path)
+}
+
+pub fn downhill_score(costs: &BTreeMap<(usize, usize), usize>) -> impl Fn(&HopContext) -> i64 + '_ {
+    // a score for find_cycle_with that favours walking a street the cheaper way, like down a hill rather than up
+    // it, by the feet saved. costs are direction_costs
+    move |hop| match (
+        costs.get(&(hop.from, hop.to)),
+        costs.get(&(hop.to, hop.from)),
+    ) {
+        (Some(there), Some(back)) => (*back as i64 - *there as i64) / WEIGHT_SCALE as i64,
+        _ => 0,
+    }
+}
+
+fn walked_weights(
+    path: &[usize],
+    graph: &RoadGraph,
+    costs: &BTreeMap<(usize, usize), usize>,
+) -> Vec<usize> {
+    // hop_weights, but a street with a cost each way is charged for the way the hop walks it
+    path.windows(2)
+        .zip(hop_weights(path, graph))
+        .map(|(hop, weight)| costs.get(&(hop[0], hop[1])).copied().unwrap_or(weight))
+        .collect()
+}
+
+fn early_reach(
+    path: &[usize],
+    weights: &[usize],
+    early: &BTreeSet<(usize, usize)>,
+) -> Vec<((usize, usize), usize)> {
+    // each early street path walks, in the order they're first reached, with how far (in stored hundredths) the
+    // route has come by the end of that first walk. weights are path's hops
+    let mut walked = 0;
+    let mut reached = vec![];
+    let mut seen = BTreeSet::new();
+    for (hop, weight) in path.windows(2).zip(weights) {
+        walked += weight;
+        let street = (hop[0].min(hop[1]), hop[0].max(hop[1]));
+        if early.contains(&street) && seen.insert(street) {
+            reached.push((street, walked));
+        }
+    }
+    reached
+}
+
+fn early_first(
+    path: Vec<usize>,
+    graph: &RoadGraph,
+    costs: &BTreeMap<(usize, usize), usize>,
+    early: &BTreeSet<(usize, usize)>,
+    start: Option<usize>,
+) -> Vec<usize> {
+    // the way round and starting point for the circuit that gets every early street walked soonest, meaning the
+    // least distance covered before the last of them is first reached. with a start asked for, only the starting
+    // points at that node count. ties keep the circuit as it came
+    let reversed: Vec<usize> = path.iter().rev().copied().collect();
+    let ways = [path, reversed];
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (way, path) in ways.iter().enumerate() {
+        let weights = walked_weights(path, graph, costs);
+        let hops = weights.len();
+        let wanted = early_reach(path, &weights, early).len();
+        for position in
+            (0..hops).filter(|position| start.is_none_or(|start| path[*position] == start))
+        {
+            // walks the circuit from position, wrapping around, until the last early street turns up
+            let mut seen = BTreeSet::new();
+            let mut walked = 0;
+            for i in (position..hops).chain(0..position) {
+                if seen.len() == wanted {
+                    break;
+                }
+                walked += weights[i];
+                let street = (path[i].min(path[i + 1]), path[i].max(path[i + 1]));
+                if early.contains(&street) {
+                    seen.insert(street);
+                }
+            }
+            if best.is_none_or(|(least, _, _)| walked < least) {
+                best = Some((walked, way, position));
+            }
+        }
+    }
+    match best {
+        Some((_, way, position)) => rotate_circuit(&ways[way], position),
+        None => ways[0].clone(),
+    }
+}
+
+fn report_early(
+    path: &[usize],
+    weights: &[usize],
+    early: &BTreeSet<(usize, usize)>,
+    names: &BTreeMap<(usize, usize), String>,
+    labels: &BTreeMap<usize, String>,
+    config: &Config,
+    report: &mut Report,
+) {
+    // how far into the route the early streets are reached, each of them and all of them together, against
+    // --early-fraction
+    let reached = early_reach(path, weights, early);
+    let Some((_, last)) = reached.last() else {
+        return;
+    };
+    let total: usize = weights.iter().sum();
+    let fraction = *last as f64 / total.max(1) as f64;
+    let in_units = |walked: usize| {
+        format!(
+            "{} {}",
+            convert_length(
+                walked as f64 / WEIGHT_SCALE as f64,
+                &Unit::Feet,
+                &config.units
+            ),
+            distance_suffix(&config.units)
+        )
+    };
+    report.lines.push(format!(
+        "early streets: all reached by {}, {} of the way through the route ({} the first {} asked for)",
+        in_units(*last),
+        format_percent(fraction, 0),
+        if fraction <= config.early_fraction { "within" } else { "past" },
+        format_percent(config.early_fraction, 0)
+    ));
+    for ((u, v), walked) in &reached {
+        let street = format!("{} -- {}", node_label(*u, labels), node_label(*v, labels));
+        match names.get(&(*u, *v)) {
+            Some(name) => report
+                .lines
+                .push(format!("  {name} ({street}) at {}", in_units(*walked))),
+            None => report
+                .lines
+                .push(format!("  {street} at {}", in_units(*walked))),
+        }
+    }
+}
+
+fn cheaper_way_round(
+    path: Vec<usize>,
+    graph: &RoadGraph,
+    costs: &BTreeMap<(usize, usize), usize>,
+) -> Vec<usize> {
+    // a circuit can be walked either way round, and streets that cost more one way can make the other cheaper
+    let cost = |path: &[usize]| -> u128 {
+        walked_weights(path, graph, costs)
+            .iter()
+            .map(|weight| *weight as u128)
+            .sum()
+    };
+    let reversed: Vec<usize> = path.iter().rev().copied().collect();
+    if cost(&reversed) < cost(&path) {
+        reversed
+    } else {
+        path
+    }
+}
+
+pub fn turn_score(coords: &[Option<(f64, f64)>]) -> impl Fn(&HopContext) -> i64 + '_ {
+    // a score for find_cycle_with that ranks going straight on highest and sharper turns lower, by the degrees
+    // turned. doubling straight back down the street just walked is the worst of all, though out of a cul de sac
+    // it's the only street left so it still gets taken. a corner that isn't located counts as an ordinary turn
+    move |hop| {
+        let Some(previous) = hop.previous else {
+            return 0;
+        };
+        if hop.to == previous {
+            return -360;
+        }
+        let located = |node: usize| coords.get(node).copied().flatten();
+        match (located(previous), located(hop.from), located(hop.to)) {
+            (Some(before), Some(corner), Some(after)) => {
+                let turn = (bearing(corner, after) - bearing(before, corner) + 540.0)
+                    .rem_euclid(360.0)
+                    - 180.0;
+                -(turn.abs() as i64)
+            }
+            _ => -90,
+        }
+    }
+}
+
+pub fn find_trail(graph: &RoadGraph, start: usize, end: usize) -> Result<Vec<usize>, PacsamError> {
+    // an euler trail from start to end, for a graph where those are the only odd nodes (eulerize_open makes one).
+    // a made-up node joined to both ends turns the trail into a circuit, and cutting that node back out of the
+    // circuit leaves the trail
+    let extra = graph.node_count();
+    let mut edges = edge_list(graph);
+    edges.push((end, extra, 0));
+    edges.push((extra, start, 0));
+    let closed: RoadGraph = GraphBuilder::new().edges_with_values(edges).build();
+    let mut path = find_cycle_from(&closed, extra)?;
+    path.pop();
+    path.remove(0);
+    // the circuit can leave the extra node toward either end
+    if path.first() != Some(&start) {
+        path.reverse();
+    }
+    Ok(path)
+}
+
+pub fn euler_path_between(
+    graph: &RoadGraph,
+    start: usize,
+    end: usize,
+) -> Result<Vec<usize>, PacsamError> {
+    // a route covering every street that's dropped off at start and picked up at end. eulerize_open runs on a copy,
+    // so the graph passed in is left alone
+    if let Some(node) = [start, end]
+        .into_iter()
+        .find(|node| *node >= graph.node_count() || graph.degree(*node) == 0)
+    {
+        return Err(PacsamError::NoSuchNode(node));
+    }
+    let eulerized = copy_graph(graph);
+    eulerize_open(&eulerized, start, end);
+    find_trail(&eulerized, start, end)
+}
+
+pub fn split_routes(graph: &RoadGraph, max_feet: usize) -> (Vec<Vec<usize>>, f64) {
+    // breaks the euler circuit of an already eulerized graph into shifts that each start and end where it does
+    // and stay under max_feet where possible. the circuit is walked in order, and whenever the next street plus
+    // the drive back would go over the cap the shift heads home and the next one drives out to pick up where it
+    // left off. a shift always covers at least one street, even one too long for the cap on its own. also returns
+    // the feet those extra drives out and back add
+    let circuit = match find_cycle(graph) {
+        Ok(circuit) if circuit.len() > 1 => circuit,
+        _ => return (vec![], 0.0),
+    };
+    let depot = circuit[0];
+    let sp_tree = dijkstra(graph, depot);
+    let mut distance = vec![usize::MAX; graph.node_count()];
+    for vertex in &sp_tree {
+        distance[vertex.idx] = vertex.distance_from_u;
+    }
+    let cap = max_feet.saturating_mul(WEIGHT_SCALE);
+    let mut routes: Vec<Vec<usize>> = vec![];
+    let mut route = vec![depot];
+    let mut route_length = 0;
+    let mut covered_any = false;
+    let mut deadhead = 0;
+    for (hop, weight) in circuit.windows(2).zip(hop_weights(&circuit, graph)) {
+        if covered_any && route_length + weight + distance[hop[1]] > cap {
+            let way_out = shortest_path(&sp_tree, hop[0]);
+            route.extend(way_out.iter().rev().skip(1));
+            routes.push(route);
+            route = way_out;
+            route_length = distance[hop[0]];
+            deadhead += 2 * distance[hop[0]];
+        }
+        route.push(hop[1]);
+        route_length += weight;
+        covered_any = true;
+    }
+    routes.push(route);
+    (routes, deadhead as f64 / WEIGHT_SCALE as f64)
+}
+
+pub enum ShiftLimit {
+    // the most one shift can cover, in the --units unit
+    Distance(f64),
+    Minutes(f64),
+}
+
+pub fn cut_route(costs: &[f64], limit: f64) -> Vec<(usize, usize)> {
+    // cuts a route whose hops cost costs (feet, minutes, anything that adds up) into consecutive shifts that each
+    // cost at most limit, as ranges of hops: a shift (first, last) walks from node first of the path to node last.
+    // a hop that's over the limit on its own gets a shift to itself. it uses as few shifts as the limit allows,
+    // then evens them out by finding the smallest limit that still needs no more, so the last walker isn't left
+    // with a scrap
+    let greedy = |cap: f64| {
+        let mut shifts: Vec<(usize, usize)> = vec![];
+        let mut first = 0;
+        let mut cost = 0.0;
+        for (hop, hop_cost) in costs.iter().enumerate() {
+            if hop > first && cost + hop_cost > cap {
+                shifts.push((first, hop));
+                first = hop;
+                cost = 0.0;
+            }
+            cost += hop_cost;
+        }
+        if !costs.is_empty() {
+            shifts.push((first, costs.len()));
+        }
+        shifts
+    };
+    let fewest = greedy(limit).len();
+    let (mut low, mut high) = (0.0, limit);
+    for _ in 0..60 {
+        let middle = (low + high) / 2.0;
+        if greedy(middle).len() <= fewest {
+            high = middle;
+        } else {
+            low = middle;
+        }
+    }
+    greedy(high)
+}
+
+pub struct WalkerRoute {
+    // starts and ends at the depot
+    pub path: Vec<usize>,
+    pub feet: f64,
+    // the part of feet spent getting from the depot to the walker's share of the circuit and back
+    pub deadhead_feet: f64,
+}
+
+impl fmt::Display for WalkerRoute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.describe(&BTreeMap::new(), &Distance::Miles))
+    }
+}
+
+impl WalkerRoute {
+    pub fn describe(&self, labels: &BTreeMap<usize, String>, unit: &Distance) -> String {
+        format!(
+            "{} {suffix} ({} {suffix} to and from the depot): {}",
+            convert_length(self.feet, &Unit::Feet, unit),
+            convert_length(self.deadhead_feet, &Unit::Feet, unit),
+            label_nodes(&self.path, labels),
+            suffix = distance_suffix(unit)
+        )
+    }
+}
+
+pub fn split_route(
+    graph: &RoadGraph,
+    circuit: &[usize],
+    k: usize,
+    depot: usize,
+) -> Vec<WalkerRoute> {
+    // shares a route over graph out among k walkers who all start and end at depot. it's the route as chosen, with
+    // whatever restarts and street orders went into it, cut at the intersections closest to each k-th of its
+    // length, and each piece gets the shortest way out from the depot to its start and back from its end. a walker
+    // can be left with nothing but the depot when there are more walkers than streets
+    if circuit.len() < 2 {
+        return vec![];
+    }
+    let k = k.max(1);
+    let hop_weights = hop_weights(circuit, graph);
+    // along[i] is how far into the circuit its i-th node is
+    let mut along = vec![0];
+    for weight in &hop_weights {
+        along.push(along[along.len() - 1] + weight);
+    }
+    let total = along[along.len() - 1];
+    let mut cuts = vec![0];
+    for walker in 1..k {
+        let target = total * walker / k;
+        let after = along.partition_point(|distance| *distance < target);
+        let nearest = if after > 0 && target - along[after - 1] < along[after] - target {
+            after - 1
+        } else {
+            after
+        };
+        cuts.push(nearest.max(cuts[cuts.len() - 1]));
+    }
+    cuts.push(circuit.len() - 1);
+    let sp_tree = dijkstra(graph, depot);
+    let mut distance = vec![0; graph.node_count()];
+    for vertex in &sp_tree {
+        distance[vertex.idx] = vertex.distance_from_u;
+    }
+    cuts.windows(2)
+        .map(|piece| {
+            let (from, to) = (piece[0], piece[1]);
+            if from == to {
+                return WalkerRoute {
+                    path: vec![depot],
+                    feet: 0.0,
+                    deadhead_feet: 0.0,
+                };
+            }
+            let way_out = shortest_path(&sp_tree, circuit[from]);
+            let mut path = way_out.clone();
+            path.extend(&circuit[from + 1..=to]);
+            path.extend(shortest_path(&sp_tree, circuit[to]).iter().rev().skip(1));
+            let deadhead = distance[circuit[from]] + distance[circuit[to]];
+            WalkerRoute {
+                path,
+                feet: (along[to] - along[from] + deadhead) as f64 / WEIGHT_SCALE as f64,
+                deadhead_feet: deadhead as f64 / WEIGHT_SCALE as f64,
+            }
+        })
+        .collect()
+}
+
+pub fn parse_walk(
+    input: &str,
+    labels: &BTreeMap<usize, String>,
+) -> Result<Vec<usize>, PacsamError> {
+    // a walk recorded in the field, like one exported from a phone app: the intersections it went through in
+    // order, by anything resolve_node takes, split by lines, commas or the -- the route prints between them. lines
+    // starting with # are comments, and the length write_result puts under a route is skipped
+    let mileage = |line: &str| {
+        let line = line.trim();
+        line.strip_suffix(" mi")
+            .or_else(|| line.strip_suffix(" km"))
+            .is_some_and(|distance| distance.parse::<f64>().is_ok())
+    };
+    input
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#') && !mileage(line))
+        .flat_map(|line| line.split([',', '\t']).flat_map(|part| part.split("--")))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| resolve_node(name, labels))
+        .collect()
+}
+
+pub struct WalkAudit {
+    // the required streets as (u, v, weight) with u < v, like edge_list, split by whether the walk went down them.
+    // a street the map has twice has to be walked twice to count both
+    pub covered: Vec<(usize, usize, usize)>,
+    pub missed: Vec<(usize, usize, usize)>,
+    // hops of the walk between intersections with no street between them, like where a phone lost its signal.
+    // they count as the shortest way between the two, without covering anything
+    pub jumps: Vec<(usize, usize)>,
+    pub walked_feet: f64,
+    // the best route over the same streets, between the walk's ends when they're different, or None when the map
+    // can't be walked in one go
+    pub optimal_feet: Option<f64>,
+}
+
+pub fn audit_walk(
+    graph: &RoadGraph,
+    optional: &BTreeSet<(usize, usize)>,
+    walk: &[usize],
+) -> WalkAudit {
+    // how a recorded walk measures up against the map, for checking a route was really done. optional streets
+    // can be walked but don't have to be. an open walk is held up against the best open route between its ends,
+    // except on a map with optional streets, where there's only the best circuit to go by
+    let WalkedStreets {
+        times: mut walked,
+        jumps,
+        weight: walked_weight,
+    } = walk_streets(graph, walk);
+    let (mut covered, mut missed) = (vec![], vec![]);
+    for (u, v, weight) in required_edges(graph, optional) {
+        match walked.get_mut(&(u, v)) {
+            Some(times) if *times > 0 => {
+                *times -= 1;
+                covered.push((u, v, weight));
+            }
+            _ => missed.push((u, v, weight)),
+        }
+    }
+    // the best route the way route finds it, dead ends walked out and back first
+    let best = copy_graph(graph);
+    let ends = match (walk.first(), walk.last()) {
+        (Some(start), Some(end)) if graph.degree(*start) > 0 && graph.degree(*end) > 0 => {
+            Some((*start, *end))
+        }
+        _ => None,
+    };
+    let optimal = match ends {
+        _ if !optional.is_empty() => eulerize_required(&best, optional).ok(),
+        _ if !is_connected(graph) => None,
+        Some((start, end)) if start != end => {
+            fix_culdesacs(&best, &BoundaryPolicy::TreatAsCuldesac);
+            eulerize_open(&best, start, end);
+            Some(best)
+        }
+        _ => {
+            fix_culdesacs(&best, &BoundaryPolicy::TreatAsCuldesac);
+            eulerize(&best);
+            Some(best)
+        }
+    };
+    WalkAudit {
+        covered,
+        missed,
+        jumps,
+        walked_feet: walked_weight as f64 / WEIGHT_SCALE as f64,
+        optimal_feet: optimal.map(|optimal| total_feet(&optimal)),
+    }
+}
+
+struct WalkedStreets {
+    // how many times a walk goes down each street, keyed u < v, the hops with no street under them, and the
+    // length of the lot in hundredths of a foot
+    times: BTreeMap<(usize, usize), usize>,
+    jumps: Vec<(usize, usize)>,
+    weight: u128,
+}
+
+fn walk_streets(graph: &RoadGraph, walk: &[usize]) -> WalkedStreets {
+    let mut copies: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for (u, v, weight) in edge_list(graph) {
+        copies.entry((u, v)).or_default().push(weight);
+    }
+    let mut walked: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    let mut jumps = vec![];
+    let mut walked_weight: u128 = 0;
+    for hop in walk.windows(2) {
+        let (u, v) = (hop[0], hop[1]);
+        match copies.contains_key(&(u.min(v), u.max(v))) {
+            true => *walked.entry((u.min(v), u.max(v))).or_insert(0) += 1,
+            false => {
+                jumps.push((u, v));
+                let shortest = dijkstra(graph, u)
+                    .into_iter()
+                    .find(|vertex| vertex.idx == v)
+                    .map_or(0, |vertex| vertex.distance_from_u);
+                if shortest != usize::MAX {
+                    walked_weight += shortest as u128;
+                }
+            }
+        }
+    }
+    // walking between two intersections the map has more than one street between goes down each of them in turn,
+    // shortest first, and the shortest again once they've all been walked
+    for (street, times) in &walked {
+        let lengths = &copies[street];
+        walked_weight += (0..*times)
+            .map(|time| lengths.get(time).unwrap_or(&lengths[0]))
+            .map(|weight| *weight as u128)
+            .sum::<u128>();
+    }
+    WalkedStreets {
+        times: walked,
+        jumps,
+        weight: walked_weight,
+    }
+}
+
+impl WalkAudit {
+    pub fn extra_feet(&self) -> Option<f64> {
+        // how much further the walk went than the best route, negative when it came up short by skipping streets
+        self.optimal_feet.map(|optimal| self.walked_feet - optimal)
+    }
+    pub fn describe(
+        &self,
+        names: &BTreeMap<(usize, usize), String>,
+        labels: &BTreeMap<usize, String>,
+        unit: &Distance,
+    ) -> String {
+        let in_units = |feet: f64| {
+            format!(
+                "{} {}",
+                convert_length(feet, &Unit::Feet, unit),
+                distance_suffix(unit)
+            )
+        };
+        let feet = |streets: &[(usize, usize, usize)]| {
+            streets
+                .iter()
+                .map(|(_, _, weight)| *weight as u128)
+                .sum::<u128>() as f64
+                / WEIGHT_SCALE as f64
+        };
+        let mut lines = vec![format!(
+            "covered: {} of {} streets, {} of {}",
+            self.covered.len(),
+            self.covered.len() + self.missed.len(),
+            convert_length(feet(&self.covered), &Unit::Feet, unit),
+            in_units(feet(&self.covered) + feet(&self.missed))
+        )];
+        if !self.missed.is_empty() {
+            lines.push(format!("missed {} streets:", self.missed.len()));
+        }
+        for (u, v, weight) in &self.missed {
+            let street = format!("{} -- {}", node_label(*u, labels), node_label(*v, labels));
+            let length = in_units(*weight as f64 / WEIGHT_SCALE as f64);
+            lines.push(match names.get(&(*u, *v)) {
+                Some(name) => format!("  {name} ({street}), {length}"),
+                None => format!("  {street}, {length}"),
+            });
+        }
+        lines.push(match (self.optimal_feet, self.extra_feet()) {
+            (Some(optimal), Some(extra)) => format!(
+                "walked: {}, {} {} than the best route of {}",
+                in_units(self.walked_feet),
+                in_units(extra.abs()),
+                if extra < 0.0 { "less" } else { "more" },
+                in_units(optimal)
+            ),
+            _ => format!("walked: {}", in_units(self.walked_feet)),
+        });
+        if !self.jumps.is_empty() {
+            lines.push(format!(
+                "jumps between intersections with no street between them: {}",
+                self.jumps.len()
+            ));
+        }
+        for (u, v) in &self.jumps {
+            lines.push(format!(
+                "  {} -- {}",
+                node_label(*u, labels),
+                node_label(*v, labels)
+            ));
+        }
+        lines.join("\n")
+    }
+    pub fn to_json(&self, unit: &Distance) -> String {
+        // lengths in the given unit, except the missed streets, which keep feet like RouteStats::to_json
+        let convert = |feet: f64| convert_length(feet, &Unit::Feet, unit);
+        let missed: Vec<String> = self
+            .missed
+            .iter()
+            .map(|(u, v, weight)| {
+                format!(
+                    "{{\"from\":{u},\"to\":{v},\"feet\":{}}}",
+                    format_weight(*weight)
+                )
+            })
+            .collect();
+        let jumps: Vec<String> = self
+            .jumps
+            .iter()
+            .map(|(u, v)| format!("{{\"from\":{u},\"to\":{v}}}"))
+            .collect();
+        let or_null =
+            |feet: Option<f64>| feet.map_or("null".to_string(), |feet| convert(feet).to_string());
+        format!(
+            "{{\"unit\":\"{}\",\"streets\":{},\"covered\":{},\"missed\":[{}],\"walked\":{},\"optimal\":{},\
+             \"extra\":{},\"jumps\":[{}]}}",
+            distance_suffix(unit),
+            self.covered.len() + self.missed.len(),
+            self.covered.len(),
+            missed.join(","),
+            convert(self.walked_feet),
+            or_null(self.optimal_feet),
+            or_null(self.extra_feet()),
+            jumps.join(",")
+        )
+    }
+}
+
+// past this many cells the table that lines two routes up would be too big to hold, and the part of them that
+// differs is reported as one changed stretch instead
+const DIFF_CELLS: usize = 25_000_000;
+
+pub struct RouteChange {
+    // a stretch where two routes go different ways: the stops of the first starting at before_at were replaced by
+    // the stops of the second starting at after_at. either side can be empty, for stops only one route has
+    pub before_at: usize,
+    pub after_at: usize,
+    pub before: Vec<usize>,
+    pub after: Vec<usize>,
+}
+
+pub struct RouteDiff {
+    pub before_feet: f64,
+    pub after_feet: f64,
+    // streets as (u, v) with u < v that only the second route walks, and only the first
+    pub added: Vec<(usize, usize)>,
+    pub removed: Vec<(usize, usize)>,
+    // streets both walk but a different number of times, as the street, times before and times after
+    pub rewalked: Vec<((usize, usize), usize, usize)>,
+    pub changes: Vec<RouteChange>,
+}
+
+pub fn diff_routes(graph: &RoadGraph, before: &[usize], after: &[usize]) -> RouteDiff {
+    // how a second route over graph differs from a first, like after closing a street or re-cutting the turf.
+    // lengths are measured the way audit_walk measures a walk, and the order by lining the stops of the two up
+    // where they agree, so a street added early on doesn't make the rest of the route count as changed
+    let (before_walked, after_walked) = (walk_streets(graph, before), walk_streets(graph, after));
+    let (mut added, mut removed, mut rewalked) = (vec![], vec![], vec![]);
+    let streets: BTreeSet<&(usize, usize)> = before_walked
+        .times
+        .keys()
+        .chain(after_walked.times.keys())
+        .collect();
+    for street in streets {
+        match (
+            before_walked.times.get(street),
+            after_walked.times.get(street),
+        ) {
+            (None, Some(_)) => added.push(*street),
+            (Some(_), None) => removed.push(*street),
+            (Some(then), Some(now)) if then != now => rewalked.push((*street, *then, *now)),
+            _ => {}
+        }
+    }
+    RouteDiff {
+        before_feet: before_walked.weight as f64 / WEIGHT_SCALE as f64,
+        after_feet: after_walked.weight as f64 / WEIGHT_SCALE as f64,
+        added,
+        removed,
+        rewalked,
+        changes: changed_stretches(before, after),
+    }
+}
+
+fn changed_stretches(before: &[usize], after: &[usize]) -> Vec<RouteChange> {
+    // the stretches outside the longest run of stops the two routes have in common, in order. the start and end
+    // they share come off first, which is usually most of two routes over the same map
+    let shared_start = before.iter().zip(after).take_while(|(u, v)| u == v).count();
+    let shared_end = before[shared_start..]
+        .iter()
+        .rev()
+        .zip(after[shared_start..].iter().rev())
+        .take_while(|(u, v)| u == v)
+        .count();
+    let a = &before[shared_start..before.len() - shared_end];
+    let b = &after[shared_start..after.len() - shared_end];
+    if a.is_empty() && b.is_empty() {
+        return vec![];
+    }
+    if (a.len() + 1).saturating_mul(b.len() + 1) > DIFF_CELLS {
+        return vec![RouteChange {
+            before_at: shared_start,
+            after_at: shared_start,
+            before: a.to_vec(),
+            after: b.to_vec(),
+        }];
+    }
+    // common[i][j] is the most stops a[i..] and b[j..] have in common, in order
+    let width = b.len() + 1;
+    let mut common = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i * width + j] = match a[i] == b[j] {
+                true => common[(i + 1) * width + j + 1] + 1,
+                false => common[(i + 1) * width + j].max(common[i * width + j + 1]),
+            };
+        }
+    }
+    let mut changes: Vec<RouteChange> = vec![];
+    let mut change: Option<RouteChange> = None;
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            changes.extend(change.take());
+            (i, j) = (i + 1, j + 1);
+            continue;
+        }
+        let open = change.get_or_insert_with(|| RouteChange {
+            before_at: shared_start + i,
+            after_at: shared_start + j,
+            before: vec![],
+            after: vec![],
+        });
+        if j == b.len() || (i < a.len() && common[(i + 1) * width + j] >= common[i * width + j + 1])
+        {
+            open.before.push(a[i]);
+            i += 1;
+        } else {
+            open.after.push(b[j]);
+            j += 1;
+        }
+    }
+    changes.extend(change);
+    changes
+}
+
+impl RouteDiff {
+    pub fn delta_feet(&self) -> f64 {
+        // how much longer the second route is, negative when it's shorter
+        self.after_feet - self.before_feet
+    }
+    pub fn describe(
+        &self,
+        names: &BTreeMap<(usize, usize), String>,
+        labels: &BTreeMap<usize, String>,
+        unit: &Distance,
+    ) -> String {
+        let in_units = |feet: f64| {
+            format!(
+                "{} {}",
+                convert_length(feet, &Unit::Feet, unit),
+                distance_suffix(unit)
+            )
+        };
+        let street = |(u, v): &(usize, usize)| {
+            let ends = format!("{} -- {}", node_label(*u, labels), node_label(*v, labels));
+            match names.get(&(*u, *v)) {
+                Some(name) => format!("{name} ({ends})"),
+                None => ends,
+            }
+        };
+        let stops = |nodes: &[usize]| match nodes.is_empty() {
+            true => "nothing".to_string(),
+            false => label_nodes(nodes, labels),
+        };
+        let mut lines = vec![format!(
+            "length: {} before, {} after, {}{}",
+            in_units(self.before_feet),
+            in_units(self.after_feet),
+            if self.delta_feet() < 0.0 { "-" } else { "+" },
+            in_units(self.delta_feet().abs())
+        )];
+        for (streets, which) in [(&self.added, "after"), (&self.removed, "before")] {
+            if !streets.is_empty() {
+                lines.push(format!("streets only walked {which}: {}", streets.len()));
+            }
+            lines.extend(streets.iter().map(|pair| format!("  {}", street(pair))));
+        }
+        if !self.rewalked.is_empty() {
+            lines.push(format!(
+                "streets walked a different number of times: {}",
+                self.rewalked.len()
+            ));
+        }
+        for (pair, then, now) in &self.rewalked {
+            lines.push(format!(
+                "  {}, {then} times before and {now} after",
+                street(pair)
+            ));
+        }
+        lines.push(match self.changes.len() {
+            0 => "order: unchanged".to_string(),
+            changed => format!("order: {changed} stretches changed"),
+        });
+        for change in &self.changes {
+            lines.push(format!(
+                "  at stop {}: {} became {}",
+                change.before_at + 1,
+                stops(&change.before),
+                stops(&change.after)
+            ));
+        }
+        lines.join("\n")
+    }
+    pub fn to_json(&self, unit: &Distance) -> String {
+        // lengths in the given unit, and stops by their index in each route counting from 0
+        let convert = |feet: f64| convert_length(feet, &Unit::Feet, unit);
+        let streets = |streets: &[(usize, usize)]| {
+            streets
+                .iter()
+                .map(|(u, v)| format!("{{\"from\":{u},\"to\":{v}}}"))
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        let rewalked: Vec<String> = self
+            .rewalked
+            .iter()
+            .map(|((u, v), then, now)| {
+                format!("{{\"from\":{u},\"to\":{v},\"before\":{then},\"after\":{now}}}")
+            })
+            .collect();
+        let nodes = |nodes: &[usize]| {
+            nodes
+                .iter()
+                .map(|node| node.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        let changes: Vec<String> = self
+            .changes
+            .iter()
+            .map(|change| {
+                format!(
+                    "{{\"before_at\":{},\"before\":[{}],\"after_at\":{},\"after\":[{}]}}",
+                    change.before_at,
+                    nodes(&change.before),
+                    change.after_at,
+                    nodes(&change.after)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"unit\":\"{}\",\"before\":{},\"after\":{},\"delta\":{},\"added\":[{}],\"removed\":[{}],\
+             \"rewalked\":[{}],\"changes\":[{}]}}",
+            distance_suffix(unit),
+            convert(self.before_feet),
+            convert(self.after_feet),
+            convert(self.delta_feet()),
+            streets(&self.added),
+            streets(&self.removed),
+            rewalked.join(","),
+            changes.join(",")
+        )
+    }
+}
+
+pub struct Reordered {
+    pub path: Vec<usize>,
+    // the stretches, by their index, that had to be walked the other way round from how they were given
+    pub reversed: Vec<usize>,
+}
+
+pub fn reorder_route(route: &[usize], stretches: &[Vec<usize>]) -> Result<Reordered, PacsamError> {
+    // route walked again with each stretch, a run of stops like a cul de sac loop, walked all in one go, and the
+    // rest of it put back as close to its old order as fits around them. a stretch has to be streets the route
+    // walks, and it's walked the way it's written unless the others leave no way to, when it goes the other way
+    // round. the new walk goes down exactly the streets route does, as many times, so it covers what route did
+    if route.len() < 2 {
+        return Ok(Reordered {
+            path: route.to_vec(),
+            reversed: vec![],
+        });
+    }
+    let street = |hop: &[usize]| (hop[0].min(hop[1]), hop[0].max(hop[1]));
+    // where in route each street is walked, for the stretches to claim a walk of it from, earliest first
+    let mut unclaimed: BTreeMap<(usize, usize), VecDeque<usize>> = BTreeMap::new();
+    for (at, hop) in route.windows(2).enumerate() {
+        unclaimed.entry(street(hop)).or_default().push_back(at);
+    }
+    let walked: BTreeMap<(usize, usize), usize> = unclaimed
+        .iter()
+        .map(|(pair, ats)| (*pair, ats.len()))
+        .collect();
+    // the route in pieces to walk whole: each stretch, and every hop no stretch claimed. each piece sits where
+    // its first hop was in route, and that's the order they're taken in whenever there's a choice
+    let mut pieces: Vec<(usize, &[usize], Option<usize>)> = vec![];
+    for (index, stretch) in stretches
+        .iter()
+        .enumerate()
+        .filter(|(_, stretch)| stretch.len() > 1)
+    {
+        let mut first = usize::MAX;
+        for hop in stretch.windows(2) {
+            let at = unclaimed
+                .get_mut(&street(hop))
+                .and_then(VecDeque::pop_front)
+                .ok_or(PacsamError::OverPinned {
+                    street: street(hop),
+                    walked: walked.get(&street(hop)).copied().unwrap_or(0),
+                })?;
+            first = first.min(at);
+        }
+        pieces.push((first, stretch, Some(index)));
+    }
+    for ats in unclaimed.values() {
+        pieces.extend(ats.iter().map(|at| (*at, &route[*at..*at + 2], None)));
+    }
+    pieces.sort_by_key(|(at, ..)| *at);
+    let nodes = route.iter().max().map_or(0, |node| node + 1);
+    let ends = |id: usize, backwards: bool| {
+        let stops = pieces[id].1;
+        match backwards {
+            true => (stops[stops.len() - 1], stops[0]),
+            false => (stops[0], stops[stops.len() - 1]),
+        }
+    };
+    // each piece is walked from its first stop to its last or backwards. the way they're written every node is
+    // left as often as it's reached, except where a stretch goes against the route. surplus counts how many more
+    // times a node is left than reached, and turning round the pieces along a path from a node left too often to
+    // one reached too often evens both out, like augmenting a flow. stretches are only turned round once turning
+    // the other pieces can't do it
+    let mut backwards = vec![false; pieces.len()];
+    let mut surplus = vec![0i64; nodes];
+    for id in 0..pieces.len() {
+        let (from, to) = ends(id, false);
+        surplus[from] += 1;
+        surplus[to] -= 1;
+    }
+    for turn_stretches in [false, true] {
+        let mut stuck = false;
+        while !stuck {
+            stuck = true;
+            for source in 0..nodes {
+                if surplus[source] <= 0 {
+                    continue;
+                }
+                let mut leaving: Vec<Vec<usize>> = vec![vec![]; nodes];
+                for id in 0..pieces.len() {
+                    if turn_stretches || pieces[id].2.is_none() {
+                        leaving[ends(id, backwards[id]).0].push(id);
+                    }
+                }
+                let mut came_by: Vec<Option<usize>> = vec![None; nodes];
+                let mut seen = vec![false; nodes];
+                seen[source] = true;
+                let mut queue = VecDeque::from([source]);
+                let mut sink = None;
+                while let Some(node) = queue.pop_front() {
+                    if surplus[node] < 0 {
+                        sink = Some(node);
+                        break;
+                    }
+                    for id in &leaving[node] {
+                        let to = ends(*id, backwards[*id]).1;
+                        if !seen[to] {
+                            seen[to] = true;
+                            came_by[to] = Some(*id);
+                            queue.push_back(to);
+                        }
+                    }
+                }
+                let Some(sink) = sink else {
+                    continue;
+                };
+                let mut node = sink;
+                while let Some(id) = came_by[node].take() {
+                    node = ends(id, backwards[id]).0;
+                    backwards[id] = !backwards[id];
+                }
+                surplus[source] -= 2;
+                surplus[sink] += 2;
+                stuck = false;
+            }
+        }
+    }
+    if surplus.iter().any(|surplus| *surplus != 0) {
+        return Err(PacsamError::NoEulerCircuit);
+    }
+    // hierholzer's algorithm over the pieces the way they're now turned. each node's pieces are in route order, so
+    // where there's a choice the earliest comes first
+    let mut leaving: Vec<Vec<usize>> = vec![vec![]; nodes];
+    for id in 0..pieces.len() {
+        leaving[ends(id, backwards[id]).0].push(id);
+    }
+    let start = match leaving[route[0]].is_empty() {
+        // a route that starts partway along a stretch starts where the first piece does instead
+        true => ends(0, backwards[0]).0,
+        false => route[0],
+    };
+    let mut cursor = vec![0; nodes];
+    let mut stack: Vec<(usize, Option<usize>)> = vec![(start, None)];
+    let mut steps = vec![];
+    while let Some(&(node, step)) = stack.last() {
+        match leaving[node].get(cursor[node]) {
+            Some(&id) => {
+                cursor[node] += 1;
+                stack.push((ends(id, backwards[id]).1, Some(id)));
+            }
+            None => {
+                stack.pop();
+                steps.extend(step);
+            }
+        }
+    }
+    steps.reverse();
+    // pieces the walk never got to are cut off from the rest
+    if steps.len() != pieces.len() {
+        return Err(PacsamError::NoEulerCircuit);
+    }
+    let mut path = vec![];
+    let mut reversed = vec![];
+    for id in steps {
+        let (_, stops, stretch) = pieces[id];
+        let backwards = backwards[id];
+        let stops: Vec<usize> = match backwards {
+            true => stops.iter().rev().copied().collect(),
+            false => stops.to_vec(),
+        };
+        if path.is_empty() {
+            path.push(stops[0]);
+        }
+        path.extend(&stops[1..]);
+        if let (true, Some(index)) = (backwards, stretch) {
+            reversed.push(index);
+        }
+    }
+    reversed.sort();
+    Ok(Reordered { path, reversed })
+}
+
+fn review(
+    route: &[usize],
+    graph: &RoadGraph,
+    optional: &BTreeSet<(usize, usize)>,
+    labels: &BTreeMap<usize, String>,
+    unit: &Distance,
+    (input, output): (impl BufRead, &mut impl Write),
+    interactive: bool,
+) -> Result<(), PacsamError> {
+    // the review command: reads pin, fix, unpin, show, write and done lines from input, reworking the route from
+    // scratch with reorder_route after every change so unpinning puts things back. a line that can't be done is
+    // reported and changes nothing, so a typo doesn't end the session. the answers go to output, with a prompt
+    // before each line when someone's typing them
+    let mut stretches: Vec<Vec<usize>> = vec![];
+    let mut path = route.to_vec();
+    let before = audit_walk(graph, optional, route);
+    let mut lines = input.lines();
+    loop {
+        if interactive {
+            write!(output, "review> ")?;
+            output.flush()?;
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let stop = |word: &str, path: &[usize]| {
+            word.parse::<usize>()
+                .ok()
+                .filter(|stop| (1..=path.len()).contains(stop))
+                .ok_or(format!(
+                    "'{word}' isn't a stop of the route, 1 to {}",
+                    path.len()
+                ))
+        };
+        let change: Result<Option<Vec<Vec<usize>>>, String> = match words[..] {
+            [] => Ok(None),
+            [word, ..] if word.starts_with('#') => Ok(None),
+            ["pin", ref nodes @ ..] if nodes.len() > 1 => nodes
+                .iter()
+                .map(|name| resolve_node(name, labels).map_err(|err| err.to_string()))
+                .collect::<Result<Vec<usize>, String>>()
+                .map(|pin| Some([stretches.clone(), vec![pin]].concat())),
+            ["fix", first, last] => stop(first, &path).and_then(|first| {
+                let last = stop(last, &path)?;
+                match first < last {
+                    true => Ok(Some(
+                        [stretches.clone(), vec![path[first - 1..last].to_vec()]].concat(),
+                    )),
+                    false => Err(format!("stop {first} doesn't come before stop {last}")),
+                }
+            }),
+            ["unpin", index] => match index.parse::<usize>() {
+                Ok(index) if (1..=stretches.len()).contains(&index) => {
+                    let mut kept = stretches.clone();
+                    kept.remove(index - 1);
+                    Ok(Some(kept))
+                }
+                _ => Err(format!("there's no pin {index}, 1 to {}", stretches.len())),
+            },
+            ["show"] => {
+                for (index, stretch) in stretches.iter().enumerate() {
+                    writeln!(
+                        output,
+                        "pin {}: {}",
+                        index + 1,
+                        label_nodes(stretch, labels)
+                    )?;
+                }
+                writeln!(output, "{}", label_nodes(&path, labels))?;
+                Ok(None)
+            }
+            ["write", out_path] => write_result(&path, graph, labels, out_path, unit)
+                .map(|_| None)
+                .map_err(|err| err.to_string()),
+            ["done" | "quit"] => break,
+            _ => Err(format!(
+                "'{}' isn't pin NODES, fix I J, unpin N, show, write OUT or done",
+                line.trim()
+            )),
+        };
+        match change {
+            Ok(Some(changed)) => match reorder_route(route, &changed) {
+                Ok(reordered) => {
+                    // the new walk has the same streets by construction, and gets checked against the map anyway
+                    let after = audit_walk(graph, optional, &reordered.path);
+                    if after.missed.len() > before.missed.len()
+                        || after.jumps.len() > before.jumps.len()
+                    {
+                        writeln!(output, "can't: the reworked route stops covering the map")?;
+                    } else {
+                        for index in &reordered.reversed {
+                            writeln!(
+                                output,
+                                "pin {} is walked the other way round, the only way it fits",
+                                index + 1
+                            )?;
+                        }
+                        writeln!(
+                            output,
+                            "ok: {} pins, still a walk of every street, {} {}",
+                            changed.len(),
+                            length(&reordered.path, graph, &Unit::Feet, unit),
+                            distance_suffix(unit)
+                        )?;
+                        (stretches, path) = (changed, reordered.path);
+                    }
+                }
+                Err(err) => writeln!(output, "can't: {err}")?,
+            },
+            Ok(None) => {}
+            Err(err) => writeln!(output, "can't: {err}")?,
+        }
+    }
+    writeln!(output, "{}", label_nodes(&path, labels))?;
+    Ok(())
+}
+
+pub fn is_euler_circuit(path: &[usize], graph: &RoadGraph) -> bool {
+    // a closed walk that uses every street in the graph, duplicates included, exactly as many times as it appears
+    if path.is_empty() {
+        return graph.edge_count() == 0;
+    }
+    if path.first() != path.last() {
+        return false;
+    }
+    let mut walked: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for hop in path.windows(2) {
+        *walked
+            .entry((hop[0].min(hop[1]), hop[0].max(hop[1])))
+            .or_insert(0) += 1;
+    }
+    // a hop between two nodes with no street between them shows up here as a pair the graph doesn't have
+    walked.into_iter().eq(traversal_counts(graph))
+}
+
+// edge weights are stored as whole hundredths of the input unit (feet, or seconds with --durations), so surveyed
+// lengths like 123.45 ft survive intact. keeping them integers means dijkstra, the matching and every sum stay
+// exact and totally ordered, which plain f64 weights (not Ord, and drifting as they're added up) wouldn't be
+pub const WEIGHT_SCALE: usize = 100;
+
+pub fn parse_weight(token: &str) -> Option<usize> {
+    // a length like 120, 123.5 or .25, into hundredths. digits past the second decimal are rounded off
+    let (whole, fraction) = token.split_once('.').unwrap_or((token, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    if !whole
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let whole: usize = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let digits: Vec<usize> = fraction
+        .chars()
+        .map(|c| c as usize - '0' as usize)
+        .chain(std::iter::repeat(0))
+        .take(3)
+        .collect();
+    let round_up = usize::from(digits[2] >= 5);
+    whole
+        .checked_mul(WEIGHT_SCALE)?
+        .checked_add(digits[0] * 10 + digits[1] + round_up)
+}
+
+pub fn format_weight(weight: usize) -> String {
+    // the stored hundredths back as a plain number, without a decimal point for whole lengths
+    let (whole, hundredths) = (weight / WEIGHT_SCALE, weight % WEIGHT_SCALE);
+    if hundredths == 0 {
+        whole.to_string()
+    } else {
+        format!("{whole}.{hundredths:02}")
+            .trim_end_matches('0')
+            .to_string()
+    }
+}
+
+pub fn street_names(input: &str) -> BTreeMap<(usize, usize), String> {
+    // an edge token can carry the street's name after its weight, like 4:530:Maple St. names are keyed by the
+    // street's two ends, lower index first, and streets without one are left out
+    let mut names: BTreeMap<(usize, usize), String> = BTreeMap::new();
+    for (line_counter, line) in input.lines().enumerate() {
+        for edge in line.split(',') {
+            let mut fields = edge.splitn(3, ':');
+            let (Some(vertex), Some(_), Some(name)) = (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let (Ok(vertex), name) = (vertex.trim().parse::<usize>(), name.trim()) else {
+                continue;
+            };
+            // a one-way marker can follow the name, or stand in for it, and an optional marker can follow either
+            let name = name.trim_end_matches('*').trim_end();
+            let name = name.strip_suffix(":>").unwrap_or(name).trim();
+            if !name.is_empty() && name != ">" {
+                let key = (line_counter.min(vertex), line_counter.max(vertex));
+                names.insert(key, name.to_string());
+            }
+        }
+    }
+    names
+}
+
+fn split_doors(edge: &str) -> (&str, Option<usize>) {
+    // an edge token can end in #N, the number of doors along the street, like 4:530:Maple St#12 or 4:530*#3.
+    // location and label tokens are left alone
+    let trimmed = edge.trim_end();
+    if trimmed.trim_start().starts_with(['@', '=']) {
+        return (edge, None);
+    }
+    match trimmed.rsplit_once('#') {
+        Some((rest, count)) => match count.trim().parse::<usize>() {
+            Ok(count) => (rest, Some(count)),
+            Err(_) => (edge, None),
+        },
+        None => (edge, None),
+    }
+}
+
+fn split_early(edge: &str) -> (&str, bool) {
+    // an edge token can end in !, for a street to walk early in the route, like 4:530:Maple St! or 4:530*!. it
+    // goes ahead of any #N door count, which is taken off first. location and label tokens are left alone
+    let trimmed = edge.trim_end();
+    if trimmed.trim_start().starts_with(['@', '=']) {
+        return (edge, false);
+    }
+    match trimmed.strip_suffix('!') {
+        Some(rest) => (rest, true),
+        None => (edge, false),
+    }
+}
+
+pub fn early_streets(input: &str) -> BTreeSet<(usize, usize)> {
+    // the streets marked with a ! to walk early, keyed like street_names
+    let mut early: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for (line_counter, line) in input.lines().enumerate() {
+        for edge in line.split(',').filter(|edge| split_early(edge).1) {
+            if let Ok(vertex) = edge
+                .split(':')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .parse::<usize>()
+            {
+                early.insert((line_counter.min(vertex), line_counter.max(vertex)));
+            }
+        }
+    }
+    early
+}
+
+pub fn without_early(input: &str) -> String {
+    // the adjacency format with the ! of its early streets taken off, for the parsers that don't expect them
+    input
+        .lines()
+        .map(|line| {
+            line.split(',')
+                .map(|edge| split_early(edge).0)
+                .collect::<Vec<&str>>()
+                .join(",")
+                + "\n"
+        })
+        .collect()
+}
+
+pub fn street_doors(input: &str) -> BTreeMap<(usize, usize), usize> {
+    // the #N door counts in the adjacency format, keyed like street_names. streets without one have no doors
+    let mut doors: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (line_counter, line) in input.lines().enumerate() {
+        for edge in line.split(',') {
+            let (edge, Some(count)) = split_doors(edge) else {
+                continue;
+            };
+            if let Ok(vertex) = edge
+                .split(':')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .parse::<usize>()
+            {
+                *doors
+                    .entry((line_counter.min(vertex), line_counter.max(vertex)))
+                    .or_insert(0) += count;
+            }
+        }
+    }
+    doors
+}
+
+pub fn without_doors(input: &str) -> String {
+    // the adjacency format with its #N door counts taken off, for the parsers that don't expect them
+    input
+        .lines()
+        .map(|line| {
+            line.split(',')
+                .map(|edge| split_doors(edge).0)
+                .collect::<Vec<&str>>()
+                .join(",")
+                + "\n"
+        })
+        .collect()
+}
+
+fn is_one_way(edge: &str) -> bool {
+    // a token ending in :> is a street that can only be driven from this line's node to the target
+    edge.trim_end().trim_end_matches('*').ends_with(":>")
+}
+
+fn is_optional(edge: &str) -> bool {
+    // a token ending in * is a street that's there to drive through but doesn't have to be covered
+    edge.trim_end().ends_with('*')
+}
+
+pub fn two_way_streets(input: &str) -> BTreeMap<(usize, usize), usize> {
+    // the streets not marked one-way, keyed like street_names, with how many join the two ends. on a map with
+    // one-way streets these are the ones orient_mixed can walk either way. loops back to the same intersection
+    // are left out, and stay an arc each way
+    let mut two_way: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (line_counter, line) in input.lines().enumerate() {
+        for edge in line
+            .split(',')
+            .filter(|edge| edge.contains(':') && !is_one_way(edge))
+        {
+            if let Ok(vertex) = edge
+                .split(':')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .parse::<usize>()
+            {
+                if vertex != line_counter {
+                    *two_way
+                        .entry((line_counter.min(vertex), line_counter.max(vertex)))
+                        .or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    two_way
+}
+
+pub fn optional_streets(input: &str) -> BTreeSet<(usize, usize)> {
+    // the streets marked optional, like 4:530* or 4:530:Connector Rd*, keyed by their two ends with the lower index
+    // first, the same way as street_names. every street not in here has to be covered
+    let mut optional: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for (line_counter, line) in input.lines().enumerate() {
+        for edge in line.split(',').filter(|edge| is_optional(edge)) {
+            if let Ok(vertex) = edge
+                .split(':')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .parse::<usize>()
+            {
+                optional.insert((line_counter.min(vertex), line_counter.max(vertex)));
+            }
+        }
+    }
+    optional
+}
+
+pub fn build_directed_graph(input: &str) -> Result<DirectedRoadGraph, PacsamError> {
+    build_directed_graph_in(input, &Unit::Feet)
+}
+
+pub fn build_directed_graph_in(input: &str, unit: &Unit) -> Result<DirectedRoadGraph, PacsamError> {
+    // reads the same adjacency format as build_graph, but keeps one-way streets like 4:530:> one way
+    // build_graph reports any token that doesn't parse, so the loop below can skip them quietly
+    build_graph(input.to_string())?;
+    let locations = node_locations(input)?;
+    let mut arcs: Vec<(usize, usize, usize)> = vec![];
+    for (line_counter, line) in input.lines().enumerate() {
+        for edge in line.split(',').filter(|edge| edge.contains(':')) {
+            let mut fields = edge.trim_end().trim_end_matches('*').split(':');
+            let Ok(vertex) = fields.next().unwrap_or_default().trim().parse::<usize>() else {
+                continue;
+            };
+            let weight = edge_weight(
+                fields.next().unwrap_or_default(),
+                &locations,
+                line_counter,
+                vertex,
+                unit,
+            );
+            let Some((there, back)) = weight else {
+                continue;
+            };
+            arcs.push((line_counter, vertex, there));
+            if !is_one_way(edge) {
+                arcs.push((vertex, line_counter, back));
+            }
+        }
+    }
+    Ok(GraphBuilder::new().edges_with_values(arcs).build())
+}
+
+pub fn symmetrize(graph: &DirectedRoadGraph) -> usize {
+    // for a map where some streets were marked :> by mistake: adds the arc back along every one-way street, the
+    // same length as the way there, so each street can be walked both ways. parallel streets are paired off first,
+    // so only the arcs with nothing coming back get a reverse. returns how many arcs it added
+    let mut arcs: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for (u, v, weight) in arc_list(graph).into_iter().filter(|(u, v, _)| u != v) {
+        arcs.entry((u, v)).or_default().push(weight);
+    }
+    let mut added = 0;
+    for ((u, v), weights) in &arcs {
+        let back = arcs.get(&(*v, *u)).map_or(0, Vec::len);
+        for weight in weights.iter().skip(back) {
+            let _ = graph.add_edge_with_value(*v, *u, *weight);
+            added += 1;
+        }
+    }
+    added
+}
+
+pub fn unbalanced_nodes(graph: &DirectedRoadGraph) -> Vec<(usize, isize)> {
+    // a directed euler circuit needs every node entered as often as it's left. lists each node where that fails,
+    // with how many more arcs leave it than enter it (negative when more enter)
+    (0..graph.node_count())
+        .map(|node| {
+            (
+                node,
+                graph.out_degree(node) as isize - graph.in_degree(node) as isize,
+            )
+        })
+        .filter(|(_, surplus)| *surplus != 0)
+        .collect()
+}
+
+fn out_arcs(graph: &DirectedRoadGraph, node: usize) -> Vec<(usize, usize)> {
+    // sorted like neighbors_vec
+    let mut arcs: Vec<(usize, usize)> = graph
+        .out_neighbors_with_values(node)
+        .map(|arc| (arc.target, arc.value))
+        .collect();
+    arcs.sort_unstable();
+    arcs
+}
+
+fn arc_list(graph: &DirectedRoadGraph) -> Vec<(usize, usize, usize)> {
+    (0..graph.node_count())
+        .flat_map(|u| {
+            out_arcs(graph, u)
+                .into_iter()
+                .map(move |(v, weight)| (u, v, weight))
+        })
+        .collect()
+}
+
+fn one_way_traps(graph: &DirectedRoadGraph, start: usize) -> Vec<usize> {
+    // a directed circuit has to be able to get from start to every node with a street and back again. searches
+    // forward along the arcs and backward against them, and lists the nodes either one misses
+    let reach = |backward: bool| {
+        let mut seen = vec![false; graph.node_count()];
+        let mut stack = vec![start];
+        seen[start] = true;
+        while let Some(node) = stack.pop() {
+            let next: Vec<usize> = if backward {
+                graph
+                    .in_neighbors_with_values(node)
+                    .map(|arc| arc.target)
+                    .collect()
+            } else {
+                graph
+                    .out_neighbors_with_values(node)
+                    .map(|arc| arc.target)
+                    .collect()
+            };
+            for target in next {
+                if !seen[target] {
+                    seen[target] = true;
+                    stack.push(target);
+                }
+            }
+        }
+        seen
+    };
+    let (forward, backward) = (reach(false), reach(true));
+    (0..graph.node_count())
+        .filter(|node| graph.out_degree(*node) + graph.in_degree(*node) > 0)
+        .filter(|node| !forward[*node] || !backward[*node])
+        .collect()
+}
+
+pub fn balance_directed(graph: &DirectedRoadGraph) -> EulerizationReport {
+    // the directed version of eulerize. a node entered more often than it's left needs that many extra drives out,
+    // and the other way round, so extra shortest paths run from the first kind of node to the second. which goes
+    // to which is a transportation problem, solved exactly as a min cost flow. every node has to be reachable both
+    // ways (see one_way_traps) or some of the paths won't exist
+    balance_directed_over(graph, graph)
+}
+
+pub fn balance_directed_over(
+    graph: &DirectedRoadGraph,
+    travel: &DirectedRoadGraph,
+) -> EulerizationReport {
+    // balance_directed, with the extra paths run along travel's arcs instead of graph's own and added to graph.
+    // for a mixed map graph is orient_mixed's arcs and travel is the map, so the paths can go either way down a
+    // two-way street whichever way it was oriented
+    let unbalanced = unbalanced_nodes(graph);
+    let senders: Vec<(usize, usize)> = unbalanced
+        .iter()
+        .filter(|(_, surplus)| *surplus < 0)
+        .map(|(node, surplus)| (*node, surplus.unsigned_abs()))
+        .collect();
+    let receivers: Vec<(usize, usize)> = unbalanced
+        .iter()
+        .filter(|(_, surplus)| *surplus > 0)
+        .map(|(node, surplus)| (*node, *surplus as usize))
+        .collect();
+    let sp_trees: Vec<Vec<Vertex>> = senders
+        .iter()
+        .map(|(node, _)| directed_dijkstra(travel, *node))
+        .collect();
+    let cost: Vec<Vec<usize>> = sp_trees
+        .iter()
+        .map(|sp_tree| {
+            let mut row = vec![usize::MAX; receivers.len()];
+            for vertex in sp_tree {
+                if let Some(j) = receivers.iter().position(|(node, _)| *node == vertex.idx) {
+                    row[j] = vertex.distance_from_u;
+                }
+            }
+            row
+        })
+        .collect();
+    let supply: Vec<usize> = senders.iter().map(|(_, units)| *units).collect();
+    let demand: Vec<usize> = receivers.iter().map(|(_, units)| *units).collect();
+    let mut report = EulerizationReport {
+        odd_node_count: unbalanced.len(),
+        duplicated_edge_count: 0,
+        duplicated_feet: 0.0,
+    };
+    for (i, j, units) in min_cost_transport(&supply, &demand, &cost) {
+        let path = shortest_path(&sp_trees[i], receivers[j].0);
+        for hop in path.windows(2) {
+            let weight = out_arcs(travel, hop[0])
+                .into_iter()
+                .filter(|(target, _)| *target == hop[1])
+                .map(|(_, weight)| weight)
+                .min()
+                .unwrap_or(0);
+            for _ in 0..units {
+                let _ = graph.add_edge_with_value(hop[0], hop[1], weight);
+            }
+            report.duplicated_edge_count += units;
+            report.duplicated_feet += (weight * units) as f64 / WEIGHT_SCALE as f64;
+        }
+    }
+    info!(
+        "balanced {} one-way intersections by driving {} arcs ({} ft) again",
+        report.odd_node_count, report.duplicated_edge_count, report.duplicated_feet
+    );
+    report
+}
+
+pub fn orient_mixed(
+    graph: &DirectedRoadGraph,
+    two_way: &BTreeMap<(usize, usize), usize>,
+) -> DirectedRoadGraph {
+    // the arcs a mixed map has to have walked: its one-way streets as they are, and each two-way street, an arc
+    // each way in graph, turned into one arc whichever way leaves the fewest intersections out of balance. it's
+    // the mixed postman heuristic that orients first and balances after (see balance_directed_over), so it isn't
+    // always the shortest route, but every street is walked legally. two_way is two_way_streets, and arcs for a
+    // street it doesn't list stay one-way
+    let mut there: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    let mut back: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    let mut arcs: Vec<(usize, usize, usize)> = vec![];
+    for (u, v, weight) in arc_list(graph) {
+        match (two_way.contains_key(&(u.min(v), u.max(v))), u < v) {
+            (true, true) => there.entry((u, v)).or_default().push(weight),
+            (true, false) => back.entry((v, u)).or_default().push(weight),
+            (false, _) => arcs.push((u, v, weight)),
+        }
+    }
+    // each two-way street is (u, v, the way from u, the way from v), and starts out walked from its lower end
+    let mut streets: Vec<(usize, usize, usize, usize)> = vec![];
+    for ((u, v), count) in two_way {
+        let there = there.remove(&(*u, *v)).unwrap_or_default();
+        let back = back.remove(&(*u, *v)).unwrap_or_default();
+        let paired = (*count).min(there.len()).min(back.len());
+        streets.extend((0..paired).map(|k| (*u, *v, there[k], back[k])));
+        // anything left over, like a one-way street beside a two-way one, is walked the way it goes
+        arcs.extend(there[paired..].iter().map(|weight| (*u, *v, *weight)));
+        arcs.extend(back[paired..].iter().map(|weight| (*v, *u, *weight)));
+    }
+    let node_count = graph.node_count();
+    let mut surplus = vec![0isize; node_count];
+    for (u, v) in arcs
+        .iter()
+        .map(|(u, v, _)| (u, v))
+        .chain(streets.iter().map(|(u, v, _, _)| (u, v)))
+    {
+        surplus[*u] += 1;
+        surplus[*v] -= 1;
+    }
+    let mut forward = vec![true; streets.len()];
+    let mut incident: Vec<Vec<usize>> = vec![vec![]; node_count];
+    for (id, (u, v, _, _)) in streets.iter().enumerate() {
+        incident[*u].push(id);
+        incident[*v].push(id);
+    }
+    // turning round every street along a path of them from a node with too many ways out to one with too many
+    // ways in moves two of the surplus from one end to the other and leaves the nodes between alone. it's only
+    // done when that leaves less out of balance overall, so it has to end
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for from in 0..node_count {
+            if surplus[from] <= 0 {
+                continue;
+            }
+            let mut via: Vec<Option<usize>> = vec![None; node_count];
+            let mut seen = vec![false; node_count];
+            seen[from] = true;
+            let mut queue = VecDeque::from([from]);
+            let mut found = None;
+            while let Some(node) = queue.pop_front() {
+                if surplus[node] < 0 && surplus[from] - surplus[node] > 2 {
+                    found = Some(node);
+                    break;
+                }
+                for id in &incident[node] {
+                    let (u, v, _, _) = streets[*id];
+                    let (tail, head) = if forward[*id] { (u, v) } else { (v, u) };
+                    if tail == node && !seen[head] {
+                        seen[head] = true;
+                        via[head] = Some(*id);
+                        queue.push_back(head);
+                    }
+                }
+            }
+            let Some(to) = found else {
+                continue;
+            };
+            let mut node = to;
+            while let Some(id) = via[node] {
+                forward[id] = !forward[id];
+                let (u, v, _, _) = streets[id];
+                node = if node == u { v } else { u };
+            }
+            surplus[from] -= 2;
+            surplus[to] += 2;
+            improved = true;
+        }
+    }
+    debug!(
+        "oriented {} two-way streets, leaving {} intersections out of balance",
+        streets.len(),
+        surplus.iter().filter(|surplus| **surplus != 0).count()
+    );
+    arcs.extend(
+        streets
+            .iter()
+            .zip(&forward)
+            .map(|((u, v, there, back), forward)| match forward {
+                true => (*u, *v, *there),
+                false => (*v, *u, *back),
+            }),
+    );
+    directed_with_nodes(arcs, node_count)
+}
+
+fn min_cost_transport(
+    supply: &[usize],
+    demand: &[usize],
+    cost: &[Vec<usize>],
+) -> Vec<(usize, usize, usize)> {
+    // ships supply[i] units from each i to cover demand[j] at each j, as cheaply as possible, and returns how many
+    // units go from i to j. successive shortest paths over the flow network source -> i -> j -> sink, with
+    // bellman-ford because the residual arcs have negative costs. pairs with cost usize::MAX can't ship at all
+    let (a, b) = (supply.len(), demand.len());
+    let (source, sink) = (a + b, a + b + 1);
+    // (from, to, capacity, cost), with arc k ^ 1 as the residual of arc k
+    let mut arcs: Vec<(usize, usize, usize, i64)> = vec![];
+    let mut add_arc = |from: usize, to: usize, capacity: usize, cost: i64| {
+        arcs.push((from, to, capacity, cost));
+        arcs.push((to, from, 0, -cost));
+    };
+    for (i, units) in supply.iter().enumerate() {
+        add_arc(source, i, *units, 0);
+    }
+    for (i, row) in cost.iter().enumerate() {
+        for (j, cost) in row
+            .iter()
+            .enumerate()
+            .filter(|(_, cost)| **cost != usize::MAX)
+        {
+            add_arc(i, a + j, usize::MAX, *cost as i64);
+        }
+    }
+    for (j, units) in demand.iter().enumerate() {
+        add_arc(a + j, sink, *units, 0);
+    }
+    loop {
+        let mut distance = vec![i64::MAX; a + b + 2];
+        let mut via: Vec<Option<usize>> = vec![None; a + b + 2];
+        distance[source] = 0;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (k, (from, to, capacity, cost)) in arcs.iter().enumerate() {
+                if *capacity > 0
+                    && distance[*from] != i64::MAX
+                    && distance[*from] + cost < distance[*to]
+                {
+                    distance[*to] = distance[*from] + cost;
+                    via[*to] = Some(k);
+                    changed = true;
+                }
+            }
+        }
+        if distance[sink] == i64::MAX {
+            break;
+        }
+        let mut path = vec![];
+        let mut node = sink;
+        while let Some(k) = via[node] {
+            path.push(k);
+            node = arcs[k].0;
+        }
+        let units = path.iter().map(|k| arcs[*k].2).min().unwrap_or(0);
+        for k in path {
+            arcs[k].2 -= units;
+            arcs[k ^ 1].2 += units;
+        }
+    }
+    // the flow on i -> j is whatever its residual picked up
+    arcs.chunks(2)
+        .filter(|pair| pair[0].0 < a && pair[0].1 >= a && pair[0].1 < a + b)
+        .filter(|pair| pair[1].2 > 0)
+        .map(|pair| (pair[0].0, pair[0].1 - a, pair[1].2))
+        .collect()
+}
+
+pub fn find_directed_cycle(
+    graph: &DirectedRoadGraph,
+    start: usize,
+) -> Result<Vec<usize>, PacsamError> {
+    // with every arc scoring the same, the first unused one is taken each time
+    find_directed_cycle_with(graph, start, |_| 0)
+}
+
+pub fn find_directed_cycle_with(
+    graph: &DirectedRoadGraph,
+    start: usize,
+    score: impl Fn(&HopContext) -> i64,
+) -> Result<Vec<usize>, PacsamError> {
+    find_directed_cycle_seeded(graph, start, None, score)
+}
+
+fn find_directed_cycle_seeded(
+    graph: &DirectedRoadGraph,
+    start: usize,
+    seed: Option<u64>,
+    score: impl Fn(&HopContext) -> i64,
+) -> Result<Vec<usize>, PacsamError> {
+    // hierholzer's algorithm on a balanced directed graph, taking the arc the score ranks highest like
+    // find_cycle_with does. arcs only go one way, so unlike find_cycle there's no second copy of each street to skip
+    let mut rng = seed.map(Rng::new);
+    let mut remaining: Vec<Vec<usize>> = (0..graph.node_count())
+        .map(|node| {
+            let mut targets: Vec<usize> =
+                out_arcs(graph, node).into_iter().map(|(v, _)| v).collect();
+            if let Some(rng) = &mut rng {
+                rng.shuffle(&mut targets);
+            }
+            targets
+        })
+        .collect();
+    let mut stack = vec![start];
+    let mut circuit = vec![];
+    while let Some(&node) = stack.last() {
+        let previous = stack.len().checked_sub(2).map(|below| stack[below]);
+        let best = (0..remaining[node].len())
+            .map(|candidate| {
+                let hop = HopContext {
+                    from: node,
+                    to: remaining[node][candidate],
+                    previous,
+                    candidate,
+                };
+                (score(&hop), candidate)
+            })
+            // the earliest candidate wins a tie
+            .max_by_key(|(hop_score, candidate)| (*hop_score, Reverse(*candidate)));
+        match best {
+            Some((_, candidate)) => {
+                let next = remaining[node].remove(candidate);
+                stack.push(next);
+            }
+            None => {
+                circuit.push(node);
+                stack.pop();
+            }
+        }
+    }
+    circuit.reverse();
+    if circuit.len() != graph.edge_count() + 1 {
+        return Err(PacsamError::NoEulerCircuit);
+    }
+    debug!("circuit of {} arcs from node {start}", circuit.len() - 1);
+    Ok(circuit)
+}
+
+pub fn node_locations(input: &str) -> Result<Vec<Option<(f64, f64)>>, PacsamError> {
+    // a line of the map file can start with its node's location as @latitude longitude, like
+    // `@40.0012 -75.1934, 1:, 2:530`, optionally followed by its elevation (see node_elevations). lines without
+    // one give None
+    input
+        .lines()
+        .enumerate()
+        .map(|(line_counter, line)| {
+            let Some(location) = line
+                .split(',')
+                .find_map(|token| token.trim().strip_prefix('@'))
+            else {
+                return Ok(None);
+            };
+            let parse_error = || PacsamError::Parse {
+                line: line_counter + 1,
+                column: column_of(line, location),
+                token: location.to_string(),
+                expected: "a location as @latitude longitude [elevation]",
+            };
+            let mut degrees = location
+                .split_whitespace()
+                .map(|value| value.parse::<f64>());
+            match (
+                degrees.next(),
+                degrees.next(),
+                degrees.next(),
+                degrees.next(),
+            ) {
+                (Some(Ok(lat)), Some(Ok(lon)), None | Some(Ok(_)), None) => Ok(Some((lat, lon))),
+                _ => Err(parse_error()),
+            }
+        })
+        .collect()
+}
+
+pub fn node_elevations(input: &str, unit: &Unit) -> Result<Vec<Option<f64>>, PacsamError> {
+    // the elevations on the end of the map file's locations, like `@40.0012 -75.1934 212`, in feet. they're in the
+    // file's unit like its weights. nodes without one give None
+    node_locations(input)?;
+    Ok(input
+        .lines()
+        .map(|line| {
+            let location = line
+                .split(',')
+                .find_map(|token| token.trim().strip_prefix('@'))?;
+            let elevation = location.split_whitespace().nth(2)?.parse::<f64>().ok()?;
+            Some(in_feet_f64(elevation, unit))
+        })
+        .collect())
+}
+
+fn in_feet_f64(value: f64, unit: &Unit) -> f64 {
+    match unit {
+        Unit::Feet => value,
+        Unit::Meters => value / 0.3048,
+    }
+}
+
+pub fn node_labels(input: &str) -> BTreeMap<usize, String> {
+    // a line of the map file can name its node with a =name token, like `=Oak & 3rd, 1:530, 2:210`. nodes
+    // without one fall back to letters wherever they're printed
+    let mut labels: BTreeMap<usize, String> = BTreeMap::new();
+    for (line_counter, line) in input.lines().enumerate() {
+        if let Some(label) = line
+            .split(',')
+            .find_map(|token| token.trim().strip_prefix('='))
+        {
+            labels.insert(line_counter, label.trim().to_string());
+        }
+    }
+    labels
+}
+
+pub fn edge_list_labels(input: &str) -> BTreeMap<usize, String> {
+    // the labels of an edge list file by the node index build_graph_edge_list gives them. a file that doesn't
+    // parse has none, build_graph_edge_list reports why
+    read_edge_list(input.as_bytes())
+        .map(|map| map.labels)
+        .unwrap_or_default()
+}
+
+fn column_of(line: &str, token: &str) -> usize {
+    // the 1-based column token starts at, for a token sliced out of line. anything else is put at column 1
+    let offset = (token.as_ptr() as usize).wrapping_sub(line.as_ptr() as usize);
+    if offset <= line.len() {
+        offset + 1
+    } else {
+        1
+    }
+}
+
+fn edge_weight(
+    field: &str,
+    locations: &[Option<(f64, f64)>],
+    from: usize,
+    to: usize,
+    unit: &Unit,
+) -> Option<(usize, usize)> {
+    // the cost of walking a street from `from` to `to` and back. a weight written out always wins, for streets that
+    // curve, and is in the file's unit. it can be two, like 3:420/380, for a street that's harder going one way,
+    // e.g. up a hill, with the first from this line's node. a blank one, like 3:, is the straight line distance
+    // between the two ends' locations, and None if either end doesn't have one
+    let field = field.trim();
+    if !field.is_empty() {
+        let (there, back) = field.split_once('/').unwrap_or((field, field));
+        let there = parse_weight(there.trim())?;
+        let back = parse_weight(back.trim())?;
+        return Some((in_feet(there, unit), in_feet(back, unit)));
+    }
+    let from = locations.get(from).copied().flatten()?;
+    let to = locations.get(to).copied().flatten()?;
+    let feet = (haversine_feet(from, to) * WEIGHT_SCALE as f64).round() as usize;
+    Some((feet, feet))
+}
+
+pub fn direction_costs(input: &str, unit: &Unit) -> BTreeMap<(usize, usize), usize> {
+    // the streets in the adjacency format with a weight each way, like 3:420/380, as the cost of walking each
+    // (from, to). streets with one weight cost the same both ways and are left out
+    let mut costs: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (line_counter, line) in input.lines().enumerate() {
+        for edge in line.split(',') {
+            let mut fields = edge.trim_end().trim_end_matches('*').split(':');
+            let vertex = fields.next().unwrap_or_default().trim().parse::<usize>();
+            let (Ok(vertex), Some(weight)) = (vertex, fields.next()) else {
+                continue;
+            };
+            if !weight.contains('/') {
+                continue;
+            }
+            if let Some((there, back)) = edge_weight(weight, &[], line_counter, vertex, unit) {
+                costs.insert((line_counter, vertex), there);
+                costs.insert((vertex, line_counter), back);
+            }
+        }
+    }
+    costs
+}
+
+fn in_feet(weight: usize, unit: &Unit) -> usize {
+    // a weight in hundredths of the given unit as hundredths of a foot
+    match unit {
+        Unit::Feet => weight,
+        Unit::Meters => (weight as f64 / 0.3048).round() as usize,
+    }
+}
+
+fn scaled_to_feet(graph: RoadGraph, unit: &Unit) -> RoadGraph {
+    // for the formats that can only hold written out weights, which are all in the file's unit
+    if let Unit::Feet = unit {
+        return graph;
+    }
+    let edges = edge_list(&graph)
+        .into_iter()
+        .map(|(u, v, weight)| (u, v, in_feet(weight, unit)))
+        .collect();
+    graph_with_nodes(edges, graph.node_count())
+}
+
+// a map file this big is read a line at a time when it's an edge list, and anything smaller is read whole
+const STREAMED_BYTES: u64 = 64 * 1024 * 1024;
+
+fn read_map_file(
+    path: &str,
+    input_unit: Option<Unit>,
+    weight_format: &WeightFormat,
+) -> Result<(String, Option<EdgeListMap>), PacsamError> {
+    // the map file's contents, except that an edge list or adjacency list of STREAMED_BYTES or more is read as it
+    // streams in (see read_edge_list and read_adjacency) and only its header comes back as the contents, along
+    // with the map read from the rest in feet. which format it is is settled by the first line past the header,
+    // which is where the other formats all give themselves away. a blank one could be the adjacency format's first
+    // node or an edge list's first street, so that file's read whole, and so is one in travel times
+    let file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let mut contents = String::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && line.trim_start_matches([' ', '\t']).starts_with('#')
+    {
+        contents.push_str(&line);
+        line.clear();
+    }
+    let other =
+        line.trim().is_empty() || line.trim_start().starts_with(['<', '{']) || is_dot(&line);
+    let edge_list = !other && !line.contains(':');
+    let adjacency = !other && line.contains(':') && matches!(weight_format, WeightFormat::Feet);
+    if size < STREAMED_BYTES || !(edge_list || adjacency) {
+        contents.push_str(&line);
+        reader.read_to_string(&mut contents)?;
+        return Ok((contents, None));
+    }
+    info!("{path} is {} MB, so it's read a line at a time", size >> 20);
+    // --input-units wins over the file's own #units line, as in run
+    let unit = match input_unit {
+        Some(unit) => unit,
+        None => units_directive(&contents)?.0.unwrap_or(Unit::Feet),
+    };
+    let rest = io::Cursor::new(line).chain(reader);
+    if edge_list {
+        let mut map = read_edge_list(rest)?;
+        map.graph = scaled_to_feet(map.graph, &unit);
+        return Ok((contents, Some(map)));
+    }
+    // a blank weight is the distance between its two ends' locations, and the far end's can be on a later line,
+    // so every location is found on a first pass
+    let header_lines = contents.lines().count();
+    let mut locations = vec![];
+    let lines = BufReader::new(fs::File::open(path)?)
+        .lines()
+        .skip(header_lines);
+    for (line_counter, line) in lines.enumerate() {
+        locations.extend(node_locations(&line?).map_err(|err| at_line(err, line_counter))?);
+    }
+    match read_adjacency(rest, &locations, &unit)? {
+        Some(map) => Ok((contents, Some(map))),
+        // it marks streets in ways only the whole file readers follow, so it's read again for them
+        None => Ok((fs::read_to_string(path)?, None)),
+    }
+}
+
+fn read_adjacency(
+    reader: impl BufRead,
+    locations: &[Option<(f64, f64)>],
+    unit: &Unit,
+) -> Result<Option<EdgeListMap>, PacsamError> {
+    // the adjacency format a line at a time, like read_edge_list, with its streets in feet along with their labels,
+    // names and doors. one-way, optional and early streets and weights that differ each way are read across the
+    // whole file by what comes after, so a map with any of them gives None. every token that doesn't parse is
+    // reported, not just the first
+    let mut edges: Vec<(usize, usize, usize)> = vec![];
+    let mut labels = BTreeMap::new();
+    let mut names = BTreeMap::new();
+    let mut doors = BTreeMap::new();
+    let mut errors = ParseErrors::default();
+    // the per line readers take this line as their first, so their streets come back keyed (0, target)
+    let street = |line_counter: usize, (_, target): (usize, usize)| {
+        (line_counter.min(target), line_counter.max(target))
+    };
+    for (line_counter, line) in reader.lines().enumerate() {
+        let line = line?;
+        let marked = line
+            .split(',')
+            .map(|edge| split_doors(edge).0)
+            .any(|edge| is_one_way(edge) || is_optional(edge) || split_early(edge).1);
+        if marked || !direction_costs(&line, unit).is_empty() {
+            return Ok(None);
+        }
+        labels.extend(
+            node_labels(&line)
+                .into_values()
+                .map(|label| (line_counter, label)),
+        );
+        for (key, count) in street_doors(&line) {
+            *doors.entry(street(line_counter, key)).or_insert(0) += count;
+        }
+        names.extend(
+            street_names(&line)
+                .into_iter()
+                .map(|(key, name)| (street(line_counter, key), name)),
+        );
+        adjacency_line(
+            &without_doors(&line),
+            line_counter,
+            locations,
+            unit,
+            &mut edges,
+            &mut errors,
+        );
+    }
+    errors.into_result()?;
+    Ok(Some(EdgeListMap {
+        graph: GraphBuilder::new().edges_with_values(edges).build(),
+        labels,
+        names,
+        doors,
+    }))
+}
+
+fn at_line(err: PacsamError, line_counter: usize) -> PacsamError {
+    // a parse error from reading one line on its own, put back on its line of the file
+    match err {
+        PacsamError::Parse {
+            column,
+            token,
+            expected,
+            ..
+        } => PacsamError::Parse {
+            line: line_counter + 1,
+            column,
+            token,
+            expected,
+        },
+        err => err,
+    }
+}
+
+fn split_header(input: &str) -> (Vec<&str>, &str) {
+    // a map file can open with header lines starting with #, like `#units m`. they come back apart from the rest
+    // of the file, so they don't shift the node numbering. lines it doesn't know are comments
+    let mut header = vec![];
+    let mut rest = input;
+    while rest.trim_start_matches([' ', '\t']).starts_with('#') {
+        let (line, after) = rest.split_once('\n').unwrap_or((rest, ""));
+        header.push(line.trim_end_matches('\r'));
+        rest = after;
+    }
+    (header, rest)
+}
+
+pub fn units_directive(input: &str) -> Result<(Option<Unit>, &str), PacsamError> {
+    // a `#units m` header line says the file's weights are meters rather than feet. the unit it names comes back
+    // along with the file past its header
+    let (header, rest) = split_header(input);
+    for (line_counter, line) in header.iter().enumerate() {
+        let Some(unit) = line.trim().strip_prefix("#units") else {
+            continue;
+        };
+        let unit = match unit.trim() {
+            "ft" | "feet" => Unit::Feet,
+            "m" | "meters" | "metres" => Unit::Meters,
+            other => {
+                return Err(PacsamError::Parse {
+                    line: line_counter + 1,
+                    column: column_of(line, other),
+                    token: other.to_string(),
+                    expected: "a unit, ft or m",
+                })
+            }
+        };
+        return Ok((Some(unit), rest));
+    }
+    Ok((None, rest))
+}
+
+#[derive(Default)]
+pub struct Avoid {
+    // nodes and streets to leave out of the map before routing, e.g. a street that's being dug up. each end is
+    // anything resolve_node reads
+    pub nodes: Vec<String>,
+    pub streets: Vec<(String, String)>,
+}
+
+pub fn avoid_directives(input: &str) -> Result<Avoid, PacsamError> {
+    // header lines like `#avoid-node Oak&1st` or `#avoid-street 3 4`
+    let mut avoid = Avoid::default();
+    for (line_counter, line) in split_header(input).0.iter().enumerate() {
+        let mut words = line.split_whitespace();
+        let (kind, expected) = match words.next() {
+            Some("#avoid-node") => (1, "a node to avoid"),
+            Some("#avoid-street") => (2, "the two ends of a street to avoid"),
+            _ => continue,
+        };
+        let ends: Vec<&str> = words.collect();
+        match ends[..] {
+            [node] if kind == 1 => avoid.nodes.push(node.to_string()),
+            [u, v] if kind == 2 => avoid.streets.push((u.to_string(), v.to_string())),
+            _ => {
+                return Err(PacsamError::Parse {
+                    line: line_counter + 1,
+                    column: 1,
+                    token: line.trim().to_string(),
+                    expected,
+                })
+            }
+        }
+    }
+    Ok(avoid)
+}
+
+pub struct Connector {
+    // a street out to a depot off the turf, like from the lot the canvassers park in. its ends are anything
+    // resolve_node reads, or a name for a new intersection, and its length is in hundredths of the map's unit
+    pub from: String,
+    pub to: String,
+    pub length: usize,
+}
+
+pub fn connector_directives(input: &str) -> Result<Vec<Connector>, PacsamError> {
+    // header lines like `#connector Lot 3 420`
+    let mut connectors = vec![];
+    for (line_counter, line) in split_header(input).0.iter().enumerate() {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("#connector") {
+            continue;
+        }
+        let words: Vec<&str> = words.collect();
+        match words[..] {
+            [from, to, length] if parse_weight(length).is_some() => connectors.push(Connector {
+                from: from.to_string(),
+                to: to.to_string(),
+                length: parse_weight(length).expect("just checked"),
+            }),
+            _ => {
+                return Err(PacsamError::Parse {
+                    line: line_counter + 1,
+                    column: 1,
+                    token: line.trim().to_string(),
+                    expected: "the two ends of a connector and its length",
+                })
+            }
+        }
+    }
+    Ok(connectors)
+}
+
+pub fn connector_edges(
+    nodes: usize,
+    connectors: &[Connector],
+    unit: &Unit,
+    labels: &mut BTreeMap<usize, String>,
+) -> Vec<(usize, usize, usize)> {
+    // the connectors as streets of a map with this many nodes. an end that isn't one of them becomes a new node
+    // numbered after them, labelled with its name so the next connector and --depot can find it
+    let mut added = nodes;
+    let mut end =
+        |name: &str, labels: &mut BTreeMap<usize, String>| match resolve_node(name, labels) {
+            Ok(node) if node < added => node,
+            _ => {
+                labels.insert(added, name.trim().to_string());
+                added += 1;
+                added - 1
+            }
+        };
+    connectors
+        .iter()
+        .map(|connector| {
+            let (u, v) = (end(&connector.from, labels), end(&connector.to, labels));
+            (u.min(v), u.max(v), in_feet(connector.length, unit))
+        })
+        .collect()
+}
+
+pub fn build_graph(input: String) -> Result<RoadGraph, PacsamError> {
+    build_graph_in(input, &Unit::Feet)
+}
+
+pub fn build_graph_in(input: String, unit: &Unit) -> Result<RoadGraph, PacsamError> {
+    // parse the input file, with its written out weights in unit. every token that doesn't parse is reported,
+    // not just the first
+    let locations = node_locations(&input)?;
+    let mut edges: Vec<(usize, usize, usize)> = vec![];
+    let mut errors = ParseErrors::default();
+    for (line_counter, line) in input.lines().enumerate() {
+        adjacency_line(
+            line,
+            line_counter,
+            &locations,
+            unit,
+            &mut edges,
+            &mut errors,
+        );
+    }
+    errors.into_result()?;
+    Ok(GraphBuilder::new().edges_with_values(edges).build())
+}
+
+fn adjacency_line(
+    line: &str,
+    line_counter: usize,
+    locations: &[Option<(f64, f64)>],
+    unit: &Unit,
+    edges: &mut Vec<(usize, usize, usize)>,
+    errors: &mut ParseErrors,
+) {
+    // the streets out of one line's node, onto edges, and whatever doesn't parse onto errors
+    for edge in line.split(",") {
+        // optional_streets reads the * marker, the graph just needs the street
+        let edge = edge.trim_end().trim_end_matches('*');
+        // node_locations and node_labels read these
+        if edge.trim_start().starts_with(['@', '=']) {
+            continue;
+        }
+        let vertex_and_weight: Vec<&str> = edge.split(":").collect();
+        if vertex_and_weight.len() == 1 {
+            // blank lines and trailing commas leave empty tokens, but anything else is missing its weight
+            if !edge.trim().is_empty() {
+                errors.push(PacsamError::Parse {
+                    line: line_counter + 1,
+                    column: column_of(line, edge.trim_start()),
+                    token: edge.trim().to_string(),
+                    expected: "an edge as target:weight",
+                });
+            }
+            continue;
+        }
+        let Ok(vertex) = vertex_and_weight[0].trim().parse::<usize>() else {
+            errors.push(PacsamError::Parse {
+                line: line_counter + 1,
+                column: column_of(line, vertex_and_weight[0].trim_start()),
+                token: vertex_and_weight[0].trim().to_string(),
+                expected: "a node index",
+            });
+            continue;
+        };
+        // a street that costs more one way is the average of the two here, see direction_costs
+        match edge_weight(vertex_and_weight[1], locations, line_counter, vertex, unit) {
+            Some((there, back)) => edges.push((line_counter, vertex, (there + back) / 2)),
+            None => {
+                // a blank weight with no locations to measure is the whole token's problem
+                let token = match vertex_and_weight[1].trim() {
+                    "" => edge.trim(),
+                    weight => weight,
+                };
+                errors.push(PacsamError::Parse {
+                    line: line_counter + 1,
+                    column: column_of(line, token),
+                    token: token.to_string(),
+                    expected: "a length in feet, one each way like 420/380, or a blank weight between two located nodes",
+                });
+            }
+        }
+    }
+}
+
+pub fn build_graph_edge_list(input: String) -> Result<RoadGraph, PacsamError> {
+    // one street per line, either as `src dst weight`, e.g. `Main&1st Main&2nd 330`, or comma separated as
+    // `from,to,length_ft[,name[,doors]]` the way a spreadsheet exports it, so a file can be edited without counting
+    // lines. if every endpoint is a number those are the node indices, otherwise each label gets the next index
+    // the first time it shows up
+    Ok(read_edge_list(input.as_bytes())?.graph)
+}
+
+pub struct EdgeListMap {
+    pub graph: RoadGraph,
+    // the node labels by index, empty when every endpoint is a number and those are the indices
+    pub labels: BTreeMap<usize, String>,
+    // from the fourth and fifth columns of a comma separated edge list, keyed like street_names
+    pub names: BTreeMap<(usize, usize), String>,
+    pub doors: BTreeMap<(usize, usize), usize>,
+}
+
+pub fn read_edge_list(reader: impl BufRead) -> Result<EdgeListMap, PacsamError> {
+    // an edge list (see build_graph_edge_list) a line at a time, so a file of hundreds of megabytes never has to
+    // be in memory whole, only its streets and the labels of its nodes. a line starting with # is a comment, and
+    // in the space separated form so is everything from a word starting with #. every line that doesn't parse is
+    // reported, not just the first
+    let mut edges: Vec<(usize, usize, usize)> = vec![];
+    let mut names = BTreeMap::new();
+    let mut doors = BTreeMap::new();
+    // None for as long as every endpoint has been a number
+    let mut lookup: Option<BTreeMap<String, usize>> = None;
+    let mut errors = ParseErrors::default();
+    for (line_counter, line) in reader.lines().enumerate() {
+        let line = line?;
+        let row = match edge_list_row(&line, line_counter, edges.is_empty()) {
+            Ok(Some(row)) => row,
+            Ok(None) => continue,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        let (u, v) = match (
+            &mut lookup,
+            row.src.parse::<usize>(),
+            row.dst.parse::<usize>(),
+        ) {
+            (None, Ok(u), Ok(v)) => (u, v),
+            (lookup, _, _) => {
+                let lookup =
+                    lookup.get_or_insert_with(|| relabel(&mut edges, &mut names, &mut doors));
+                (label_index(lookup, row.src), label_index(lookup, row.dst))
+            }
+        };
+        edges.push((u, v, row.weight));
+        if let Some(name) = row.name {
+            names.insert((u.min(v), u.max(v)), name.to_string());
+        }
+        if let Some(count) = row.doors {
+            *doors.entry((u.min(v), u.max(v))).or_insert(0) += count;
+        }
+    }
+    errors.into_result()?;
+    Ok(EdgeListMap {
+        graph: GraphBuilder::new().edges_with_values(edges).build(),
+        labels: lookup
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(label, node)| (node, label))
+            .collect(),
+        names,
+        doors,
+    })
+}
+
+fn label_index(lookup: &mut BTreeMap<String, usize>, label: &str) -> usize {
+    // the index of label, giving it the next one the first time it shows up
+    if let Some(node) = lookup.get(label) {
+        return *node;
+    }
+    let next = lookup.len();
+    lookup.insert(label.to_string(), next);
+    next
+}
+
+fn relabel(
+    edges: &mut [(usize, usize, usize)],
+    names: &mut BTreeMap<(usize, usize), String>,
+    doors: &mut BTreeMap<(usize, usize), usize>,
+) -> BTreeMap<String, usize> {
+    // the endpoints read so far were all numbers, taken for node indices, until a label turned up. they're labels
+    // after all, numbered in the order they first showed up like any other, and this is the lookup for them
+    let mut lookup = BTreeMap::new();
+    for (u, v, _) in edges.iter_mut() {
+        *u = label_index(&mut lookup, &u.to_string());
+        *v = label_index(&mut lookup, &v.to_string());
+    }
+    let index = |node: usize| lookup[&node.to_string()];
+    let rekey = |(u, v): (usize, usize)| (index(u).min(index(v)), index(u).max(index(v)));
+    *names = std::mem::take(names)
+        .into_iter()
+        .map(|(street, name)| (rekey(street), name))
+        .collect();
+    *doors = std::mem::take(doors)
+        .into_iter()
+        .map(|(street, count)| (rekey(street), count))
+        .collect();
+    lookup
+}
+
+struct EdgeListRow<'a> {
+    src: &'a str,
+    dst: &'a str,
+    weight: usize,
+    name: Option<&'a str>,
+    doors: Option<usize>,
+}
+
+fn edge_list_row(
+    line: &str,
+    line_counter: usize,
+    first: bool,
+) -> Result<Option<EdgeListRow<'_>>, PacsamError> {
+    // the street on one line of an edge list, or None for a blank line or a comment. the first street's line is a
+    // header instead when its length starts with a letter, like from,to,length_ft,name
+    if line.trim_start().starts_with('#') {
+        return Ok(None);
+    }
+    let fields: Vec<&str> = if line.contains(',') {
+        line.split(',').map(str::trim).collect()
+    } else {
+        line.split_whitespace()
+            .take_while(|word| !word.starts_with('#'))
+            .collect()
+    };
+    if fields.iter().all(|field| field.is_empty()) {
+        return Ok(None);
+    }
+    let (src, dst, weight, name, doors) = match fields[..] {
+        [src, dst, weight] => (src, dst, weight, None, None),
+        [src, dst, weight, name] if line.contains(',') => (src, dst, weight, Some(name), None),
+        [src, dst, weight, name, doors] if line.contains(',') => {
+            (src, dst, weight, Some(name), Some(doors))
+        }
+        _ => {
+            return Err(PacsamError::Parse {
+                line: line_counter + 1,
+                column: 1,
+                token: line.trim().to_string(),
+                expected:
+                    "a street as from,to,length_ft[,name[,doors]] or source destination weight",
+            })
+        }
+    };
+    if first && weight.starts_with(|c: char| c.is_alphabetic()) {
+        return Ok(None);
+    }
+    let weight = parse_weight(weight).ok_or_else(|| PacsamError::Parse {
+        line: line_counter + 1,
+        column: column_of(line, weight),
+        token: weight.to_string(),
+        expected: "a length in feet",
+    })?;
+    let doors = match doors.filter(|doors| !doors.is_empty()) {
+        Some(doors) => Some(doors.parse::<usize>().map_err(|_| PacsamError::Parse {
+            line: line_counter + 1,
+            column: column_of(line, doors),
+            token: doors.to_string(),
+            expected: "a number of doors",
+        })?),
+        None => None,
+    };
+    Ok(Some(EdgeListRow {
+        src,
+        dst,
+        weight,
+        name: name.filter(|name| !name.is_empty()),
+        doors,
+    }))
+}
+
+pub fn edge_list_doors(input: &str) -> BTreeMap<(usize, usize), usize> {
+    // the door counts from the fifth column of a comma separated edge list, keyed like street_names. a file that
+    // doesn't parse has none, build_graph_edge_list reports why
+    read_edge_list(input.as_bytes())
+        .map(|map| map.doors)
+        .unwrap_or_default()
+}
+
+pub fn edge_list_names(input: &str) -> BTreeMap<(usize, usize), String> {
+    // the street names from the fourth column of a comma separated edge list, keyed like street_names
+    read_edge_list(input.as_bytes())
+        .map(|map| map.names)
+        .unwrap_or_default()
+}
+
+pub struct OsmMap {
+    pub graph: RoadGraph,
+    // (latitude, longitude) by node
+    pub coords: Vec<(f64, f64)>,
+    // street names from the ways' name tags, keyed the same way as street_names
+    pub names: BTreeMap<(usize, usize), String>,
+}
+
+pub fn build_graph_osm(input: &str) -> Result<OsmMap, PacsamError> {
+    // reads an .osm xml extract, e.g. one exported from openstreetmap.org. every way with a highway tag that can
+    // be walked becomes streets: it's cut at each node it shares with another such way and at its two ends, and
+    // each piece's length is the haversine distance along all the points in between. graph nodes are numbered in
+    // the order the ways reach them
+    let mut locations: BTreeMap<u64, (f64, f64)> = BTreeMap::new();
+    let mut ways: Vec<Way<u64>> = vec![];
+    let mut open_way: Option<Way<u64>> = None;
+    let mut line = 1;
+    for element in input.split('<') {
+        let tag = element.split('>').next().unwrap_or_default();
+        // errors point at the element's opening <
+        let before = &input[..column_of(input, element) - 1];
+        let column = before.len() - before.rfind('\n').map_or(0, |newline| newline + 1);
+        let parse_error = |token: &str| PacsamError::Parse {
+            line,
+            column,
+            token: token.to_string(),
+            expected: "a number",
+        };
+        if let Some(attributes) = tag.strip_prefix("node ") {
+            let id = xml_attr(attributes, "id").ok_or_else(|| parse_error(tag))?;
+            let id = id.parse::<u64>().map_err(|_| parse_error(&id))?;
+            let coordinate = |name: &str| -> Result<f64, PacsamError> {
+                let value = xml_attr(attributes, name).ok_or_else(|| parse_error(tag))?;
+                value.parse::<f64>().map_err(|_| parse_error(&value))
+            };
+            locations.insert(id, (coordinate("lat")?, coordinate("lon")?));
+        } else if let Some(attributes) = tag.strip_prefix("way ") {
+            open_way = Some((vec![], BTreeMap::new(), None));
+            if attributes.trim_end().ends_with('/') {
+                ways.extend(open_way.take());
+            }
+        } else if tag.starts_with("/way") {
+            ways.extend(open_way.take());
+        } else if let (Some(attributes), Some((refs, ..))) =
+            (tag.strip_prefix("nd "), &mut open_way)
+        {
+            let node = xml_attr(attributes, "ref").ok_or_else(|| parse_error(tag))?;
+            refs.push(node.parse::<u64>().map_err(|_| parse_error(&node))?);
+        } else if let (Some(attributes), Some((_, tags, _))) =
+            (tag.strip_prefix("tag "), &mut open_way)
+        {
+            if let (Some(key), Some(value)) = (xml_attr(attributes, "k"), xml_attr(attributes, "v"))
+            {
+                tags.insert(key, value);
+            }
+        }
+        line += element.matches('\n').count();
+    }
+    ways.retain(|(_, tags, _)| is_walkable(tags));
+    Ok(map_from_ways(&ways, &locations))
+}
+
+// the points along a street by key, its tags, and its length in feet when the input gives one
+type Way<K> = (Vec<K>, BTreeMap<String, String>, Option<f64>);
+
+fn map_from_ways<K: Ord + Copy>(ways: &[Way<K>], locations: &BTreeMap<K, (f64, f64)>) -> OsmMap {
+    // the streets along ways, cut into pieces the way build_graph_osm describes. a point with no location is left
+    // out and splits its way. a node on more than one way, or on the same way twice, is an intersection. a way with
+    // a length of its own shares it out between its pieces by their haversine lengths
+    let mut uses: BTreeMap<K, usize> = BTreeMap::new();
+    for (refs, ..) in ways {
+        for node in refs {
+            *uses.entry(*node).or_insert(0) += 1;
+        }
+    }
+    let mut index: BTreeMap<K, usize> = BTreeMap::new();
+    let mut coords: Vec<(f64, f64)> = vec![];
+    let mut node_index = |node: K, at: (f64, f64)| {
+        *index.entry(node).or_insert_with(|| {
+            coords.push(at);
+            coords.len() - 1
+        })
+    };
+    let mut edges: Vec<(usize, usize, usize)> = vec![];
+    let mut names: BTreeMap<(usize, usize), String> = BTreeMap::new();
+    for (refs, tags, length) in ways {
+        let mut pieces: Vec<(usize, usize, f64)> = vec![];
+        // a node the extract cuts off has no location, so the way is handled as separate runs either side of it
+        for run in refs.split(|node| !locations.contains_key(node)) {
+            let Some(first) = run.first() else {
+                continue;
+            };
+            let mut from = node_index(*first, locations[first]);
+            let mut feet = 0.0;
+            for (i, hop) in run.windows(2).enumerate() {
+                feet += haversine_feet(locations[&hop[0]], locations[&hop[1]]);
+                let last = i + 2 == run.len();
+                if last || uses[&hop[1]] > 1 {
+                    let to = node_index(hop[1], locations[&hop[1]]);
+                    pieces.push((from, to, feet));
+                    from = to;
+                    feet = 0.0;
+                }
+            }
+        }
+        let measured: f64 = pieces.iter().map(|(_, _, feet)| feet).sum();
+        let count = pieces.len() as f64;
+        for (from, to, feet) in pieces {
+            let feet = match length {
+                Some(length) if measured > 0.0 => feet * length / measured,
+                Some(length) => length / count,
+                None => feet,
+            };
+            edges.push((from, to, (feet * WEIGHT_SCALE as f64).round() as usize));
+            if let Some(name) = tags.get("name") {
+                names.insert((from.min(to), from.max(to)), name.clone());
+            }
+        }
+    }
+    OsmMap {
+        graph: GraphBuilder::new().edges_with_values(edges).build(),
+        coords,
+        names,
+    }
+}
+
+pub fn build_graph_geojson(input: &str, unit: &Unit) -> Result<OsmMap, PacsamError> {
+    // reads GeoJSON, the way web maps hand over drawn or exported streets: a FeatureCollection, a single Feature,
+    // or a bare geometry. every LineString (and each line of a MultiLineString) is a way, and lines meet wherever
+    // they share a point exactly, the same as ways sharing a node in build_graph_osm. a feature's string
+    // properties are its tags, so a name property names the street, and one with a highway property that isn't
+    // walkable is left out. a numeric length property, in unit, is the feature's length instead of the geodesic
+    // one worked out from its points
+    let json = parse_json(input)?;
+    let mut ways: Vec<Way<(u64, u64)>> = vec![];
+    let mut locations: BTreeMap<(u64, u64), (f64, f64)> = BTreeMap::new();
+    geojson_ways(&json, &GeoFeature::default(), &mut ways, &mut locations)?;
+    ways.retain(|(_, tags, _)| !tags.contains_key("highway") || is_walkable(tags));
+    for (_, _, length) in &mut ways {
+        *length = length.map(|length| in_feet_f64(length, unit));
+    }
+    Ok(map_from_ways(&ways, &locations))
+}
+
+#[derive(Default)]
+struct GeoFeature {
+    // what a feature's properties say about the lines in its geometry
+    tags: BTreeMap<String, String>,
+    length: Option<f64>,
+}
+
+fn geojson_ways(
+    json: &Json,
+    feature: &GeoFeature,
+    ways: &mut Vec<Way<(u64, u64)>>,
+    locations: &mut BTreeMap<(u64, u64), (f64, f64)>,
+) -> Result<(), PacsamError> {
+    // adds the lines in json to ways, with feature's tags and length. points are keyed by their exact coordinates,
+    // and geojson puts longitude first
+    let mut line = |points: &[Json]| -> Result<(), PacsamError> {
+        let mut refs = vec![];
+        for point in points {
+            let point = point.items("coordinates")?;
+            let (Some(lon), Some(lat)) = (point.first(), point.get(1)) else {
+                return Err(json.mismatch("coordinates", "a [longitude, latitude] point"));
+            };
+            let at = (lat.number("coordinates")?, lon.number("coordinates")?);
+            let key = (at.0.to_bits(), at.1.to_bits());
+            locations.insert(key, at);
+            refs.push(key);
+        }
+        ways.push((refs, feature.tags.clone(), feature.length));
+        Ok(())
+    };
+    match json.field("type")?.string("type")? {
+        "FeatureCollection" => {
+            for member in json.field("features")?.items("features")? {
+                geojson_ways(member, feature, ways, locations)?;
+            }
+        }
+        "Feature" => {
+            let mut properties = GeoFeature::default();
+            if let Ok(Json::Object(fields)) = json.field("properties") {
+                for (key, value) in fields {
+                    match value {
+                        Json::Text(value) => {
+                            properties.tags.insert(key.clone(), value.clone());
+                        }
+                        Json::Number(_) if key == "length" => {
+                            properties.length = Some(value.number("length")?);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            // a feature can have a null geometry, which has nothing to walk
+            match json.field("geometry")? {
+                Json::Null => {}
+                geometry => geojson_ways(geometry, &properties, ways, locations)?,
+            }
+        }
+        "LineString" => line(json.field("coordinates")?.items("coordinates")?)?,
+        "MultiLineString" => {
+            for points in json.field("coordinates")?.items("coordinates")? {
+                line(points.items("coordinates")?)?;
+            }
+        }
+        "GeometryCollection" => {
+            for geometry in json.field("geometries")?.items("geometries")? {
+                geojson_ways(geometry, feature, ways, locations)?;
+            }
+        }
+        // points and polygons aren't streets
+        _ => {}
+    }
+    Ok(())
+}
+
+pub fn solve_from_geojson(input: &str) -> Result<String, PacsamError> {
+    // the whole solve from a GeoJSON string to a json string, touching nothing outside memory, for running
+    // somewhere with no filesystem like a browser. the result is Route::to_json under "route", and the route's
+    // points as [longitude, latitude] under "coordinates" to draw it with
+    let map = build_graph_geojson(input, &Unit::Feet)?;
+    let route = Solver::from_graph(map.graph).route()?;
+    let points: Vec<String> = route
+        .path
+        .iter()
+        .map(|node| {
+            let (lat, lon) = map.coords[*node];
+            format!("[{lon},{lat}]")
+        })
+        .collect();
+    Ok(format!(
+        "{{\"route\":{},\"coordinates\":[{}]}}",
+        route.to_json(),
+        points.join(",")
+    ))
+}
+
+fn is_walkable(tags: &BTreeMap<String, String>) -> bool {
+    // any highway a person can walk along. motorways and trunk roads are off limits, and so is anything tagged
+    // foot=no or access=private unless it explicitly allows walkers
+    let Some(highway) = tags.get("highway") else {
+        return false;
+    };
+    let foot = tags.get("foot").map(String::as_str);
+    let closed = matches!(
+        highway.as_str(),
+        "motorway"
+            | "motorway_link"
+            | "trunk"
+            | "trunk_link"
+            | "construction"
+            | "proposed"
+            | "raceway"
+    );
+    let private = matches!(
+        tags.get("access").map(String::as_str),
+        Some("private" | "no")
+    );
+    !closed && foot != Some("no") && (!private || matches!(foot, Some("yes" | "designated")))
+}
+
+fn xml_attr(attributes: &str, name: &str) -> Option<String> {
+    // the value of name="..." (or name='...') in an element's attributes, with the xml escapes undone
+    for quote in ['"', '\''] {
+        let opening = format!("{name}={quote}");
+        let Some(at) = attributes
+            .match_indices(&opening)
+            .map(|(at, _)| at)
+            .find(|at| *at == 0 || attributes[..*at].ends_with(char::is_whitespace))
+        else {
+            continue;
+        };
+        let value = &attributes[at + opening.len()..];
+        let value = &value[..value.find(quote)?];
+        return Some(
+            value
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&amp;", "&"),
+        );
+    }
+    None
+}
+
+pub fn haversine_feet(from: (f64, f64), to: (f64, f64)) -> f64 {
+    // great circle distance in feet between two (latitude, longitude) points in degrees
+    const EARTH_RADIUS_FEET: f64 = 20_902_231.0;
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (to.1 - from.1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_FEET * a.sqrt().asin()
+}
+
+pub struct LabeledGraph {
+    pub graph: RoadGraph,
+    pub labels: Vec<String>,
+}
+
+pub struct IncidentEdge {
+    pub other: usize,
+    pub other_label: String,
+    pub weight: usize,
+}
+
+impl LabeledGraph {
+    pub fn edges_incident_to(&self, node: usize) -> Vec<IncidentEdge> {
+        // every street touching the node, with the label of the intersection at its other end
+        neighbors_vec(&self.graph, node)
+            .into_iter()
+            .map(|(other, weight)| IncidentEdge {
+                other,
+                other_label: self.labels[other].clone(),
+                weight,
+            })
+            .collect()
+    }
+    pub fn relabel_with(&mut self, f: impl Fn(usize) -> String) {
+        // replaces every label at once, e.g. with street codes for an export
+        self.labels = (0..self.graph.node_count()).map(f).collect();
+    }
+    pub fn path_text(&self, path: &[usize]) -> String {
+        // same " -- " layout as alphabetize, using this graph's own labels
+        path.iter()
+            .map(|node| self.labels[*node].as_str())
+            .collect::<Vec<&str>>()
+            .join(" -- ")
+    }
+}
+
+pub fn with_label_map(
+    edges: &[(usize, usize, usize)],
+    labels: Vec<String>,
+) -> Result<LabeledGraph, Box<dyn Error>> {
+    // for callers that already have their own index -> label mapping from another tool. the labels are checked
+    // against the graph so every node ends up with exactly one
+    let graph: RoadGraph = GraphBuilder::new()
+        .edges_with_values(edges.to_vec())
+        .build();
+    if labels.len() != graph.node_count() {
+        return Err(format!(
+            "{} labels given for a graph with {} nodes",
+            labels.len(),
+            graph.node_count()
+        )
+        .into());
+    }
+    Ok(LabeledGraph { graph, labels })
+}
+
+pub fn to_colon_format(graph: &RoadGraph) -> String {
+    // writes the graph back out in the format build_graph reads, where line i lists `target:weight` for the edges
+    // of node i. each edge goes on the line of its lower index only, and parallel edges are written once per copy
+    let mut lines: Vec<Vec<String>> = vec![vec![]; graph.node_count()];
+    for (u, v, weight) in edge_list(graph) {
+        lines[u].push(format!("{v}:{}", format_weight(weight)));
+    }
+    lines.iter().map(|tokens| tokens.join(",") + "\n").collect()
+}
+
+pub struct DotMap {
+    pub graph: RoadGraph,
+    // the label attribute of each node, or its id when that isn't a number
+    pub labels: BTreeMap<usize, String>,
+    // street names, keyed the same way as street_names
+    pub names: BTreeMap<(usize, usize), String>,
+    // door counts from the edges' doors attribute, keyed the same way
+    pub doors: BTreeMap<(usize, usize), usize>,
+}
+
+struct DotToken {
+    text: String,
+    line: usize,
+    column: usize,
+}
+
+fn is_dot(input: &str) -> bool {
+    // a graphviz file opens with its graph keyword, after any comments
+    let first = input
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with('#'))
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    ["graph", "strict", "digraph"]
+        .iter()
+        .any(|keyword| first.starts_with(keyword))
+}
+
+fn dot_tokens(input: &str) -> Vec<DotToken> {
+    // ids, quoted strings (without their quotes), and the punctuation dot uses, with where each one starts.
+    // comments are dropped
+    let mut tokens = vec![];
+    for (line_counter, line) in input.lines().enumerate() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let start = i;
+            let text = match chars[i] {
+                c if c.is_whitespace() => {
+                    i += 1;
+                    continue;
+                }
+                '/' if chars.get(i + 1) == Some(&'/') => break,
+                '"' => {
+                    let mut text = String::new();
+                    i += 1;
+                    while i < chars.len() && chars[i] != '"' {
+                        if chars[i] == '\\' && i + 1 < chars.len() {
+                            i += 1;
+                        }
+                        text.push(chars[i]);
+                        i += 1;
+                    }
+                    i += 1;
+                    text
+                }
+                '-' if matches!(chars.get(i + 1), Some('-' | '>')) => {
+                    i += 2;
+                    chars[start..i].iter().collect()
+                }
+                '{' | '}' | '[' | ']' | ';' | ',' | '=' => {
+                    i += 1;
+                    chars[start].to_string()
+                }
+                _ => {
+                    while i < chars.len()
+                        && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.' | '-' | '&'))
+                        && !(chars[i] == '-' && matches!(chars.get(i + 1), Some('-' | '>')))
+                    {
+                        i += 1;
+                    }
+                    if i == start {
+                        i += 1;
+                    }
+                    chars[start..i].iter().collect()
+                }
+            };
+            tokens.push(DotToken {
+                text,
+                line: line_counter + 1,
+                column: start + 1,
+            });
+        }
+    }
+    tokens
+}
+
+pub fn build_graph_dot(input: &str) -> Result<DotMap, PacsamError> {
+    // reads the part of graphviz's language a street map needs: an undirected graph whose edges are `a -- b` (or
+    // chains like `a -- b -- c`) with their length in a weight attribute, or in the label if that's a number. a
+    // label that isn't the length, or a name attribute, is the street's name. node statements can give a label,
+    // and default attribute statements like `node [shape=point]` are skipped. node ids that are all numbers are
+    // the node indices, otherwise each id gets the next index the first time it shows up
+    let tokens = dot_tokens(input);
+    let error = |token: Option<&DotToken>, expected: &'static str| match token {
+        Some(token) => PacsamError::Parse {
+            line: token.line,
+            column: token.column,
+            token: token.text.clone(),
+            expected,
+        },
+        None => PacsamError::Parse {
+            line: input.lines().count().max(1),
+            column: 1,
+            token: String::new(),
+            expected,
+        },
+    };
+    let mut at = 0;
+    if tokens
+        .get(at)
+        .is_some_and(|token| token.text.eq_ignore_ascii_case("strict"))
+    {
+        at += 1;
+    }
+    match tokens.get(at) {
+        Some(token) if token.text.eq_ignore_ascii_case("graph") => at += 1,
+        other => return Err(error(other, "an undirected graph, opening with graph")),
+    }
+    if tokens.get(at).is_some_and(|token| token.text != "{") {
+        at += 1;
+    }
+    match tokens.get(at) {
+        Some(token) if token.text == "{" => at += 1,
+        other => return Err(error(other, "the { that opens the graph")),
+    }
+    // statements as the ids they name (one for a node, two or more for a chain of edges) and their attributes
+    let mut statements: Vec<(Vec<&DotToken>, BTreeMap<String, String>)> = vec![];
+    loop {
+        let Some(token) = tokens.get(at) else {
+            return Err(error(None, "the } that closes the graph"));
+        };
+        match token.text.as_str() {
+            "}" => break,
+            ";" | "," => {
+                at += 1;
+                continue;
+            }
+            "{" | "[" | "]" | "=" | "--" | "->" => {
+                return Err(error(Some(token), "a node or edge statement"))
+            }
+            _ => {}
+        }
+        let keyword = ["graph", "node", "edge"]
+            .iter()
+            .any(|keyword| token.text.eq_ignore_ascii_case(keyword));
+        if token.text.eq_ignore_ascii_case("subgraph") {
+            return Err(error(
+                Some(token),
+                "a node or edge statement, subgraphs aren't supported",
+            ));
+        }
+        let mut ids = vec![token];
+        at += 1;
+        // a graph attribute like rankdir=LR
+        if tokens.get(at).is_some_and(|token| token.text == "=") {
+            at += 2;
+            continue;
+        }
+        while let Some(token) = tokens
+            .get(at)
+            .filter(|token| token.text == "--" || token.text == "->")
+        {
+            if token.text == "->" {
+                return Err(error(Some(token), "an undirected edge, written --"));
+            }
+            match tokens.get(at + 1) {
+                Some(id)
+                    if !["{", "}", "[", "]", ";", ",", "=", "--", "->"]
+                        .contains(&id.text.as_str()) =>
+                {
+                    ids.push(id)
+                }
+                other => return Err(error(other, "a node id after --")),
+            }
+            at += 2;
+        }
+        let mut attributes = BTreeMap::new();
+        while tokens.get(at).is_some_and(|token| token.text == "[") {
+            at += 1;
+            loop {
+                match tokens.get(at) {
+                    Some(token) if token.text == "]" => {
+                        at += 1;
+                        break;
+                    }
+                    Some(token) if token.text == "," || token.text == ";" => at += 1,
+                    Some(key) if tokens.get(at + 1).is_some_and(|token| token.text == "=") => {
+                        let Some(value) = tokens.get(at + 2) else {
+                            return Err(error(None, "an attribute value"));
+                        };
+                        attributes.insert(key.text.to_ascii_lowercase(), value.text.clone());
+                        at += 3;
+                    }
+                    other => return Err(error(other, "an attribute as key=value")),
+                }
+            }
+        }
+        if !(keyword && ids.len() == 1) {
+            statements.push((ids, attributes));
+        }
+    }
+    let numeric = statements
+        .iter()
+        .flat_map(|(ids, _)| ids)
+        .all(|id| id.text.parse::<usize>().is_ok());
+    let mut lookup: BTreeMap<String, usize> = BTreeMap::new();
+    let mut labels: BTreeMap<usize, String> = BTreeMap::new();
+    let mut index = |id: &str| {
+        if numeric {
+            return id.parse::<usize>().expect("checked above");
+        }
+        let next = lookup.len();
+        *lookup.entry(id.to_string()).or_insert_with(|| {
+            labels.insert(next, id.to_string());
+            next
+        })
+    };
+    let mut edges: Vec<(usize, usize, usize)> = vec![];
+    let mut names: BTreeMap<(usize, usize), String> = BTreeMap::new();
+    let mut doors: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    let mut node_labels: Vec<(usize, String)> = vec![];
+    let mut node_count = 0;
+    for (ids, attributes) in &statements {
+        let nodes: Vec<usize> = ids.iter().map(|id| index(&id.text)).collect();
+        node_count = nodes
+            .iter()
+            .fold(node_count, |count, node| count.max(node + 1));
+        if let [node] = nodes[..] {
+            if let Some(label) = attributes.get("label") {
+                node_labels.push((node, label.clone()));
+            }
+            continue;
+        }
+        let label = attributes.get("label");
+        let weight = match attributes.get("weight") {
+            Some(weight) => parse_weight(weight),
+            None => label.and_then(|label| parse_weight(label)),
+        }
+        .ok_or_else(|| {
+            error(
+                Some(ids[0]),
+                "a length in feet as the edge's weight or label",
+            )
+        })?;
+        let name = attributes
+            .get("name")
+            .or(label.filter(|label| parse_weight(label).is_none()));
+        let door_count =
+            match attributes.get("doors") {
+                Some(count) => Some(count.parse::<usize>().map_err(|_| {
+                    error(Some(ids[0]), "a number of doors in the doors attribute")
+                })?),
+                None => None,
+            };
+        for pair in nodes.windows(2) {
+            edges.push((pair[0], pair[1], weight));
+            let key = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+            if let Some(name) = name {
+                names.insert(key, name.clone());
+            }
+            if let Some(count) = door_count {
+                *doors.entry(key).or_insert(0) += count;
+            }
+        }
+    }
+    labels.extend(node_labels);
+    Ok(DotMap {
+        graph: graph_with_nodes(edges, node_count),
+        labels,
+        names,
+        doors,
+    })
+}
+
+pub fn to_dot(graph: &RoadGraph, labels: &BTreeMap<usize, String>) -> String {
+    // the map as a graphviz graph that build_graph_dot reads back, each street labelled with its length
+    let mut dot = String::from("graph streets {\n");
+    for node in 0..graph.node_count() {
+        dot.push_str(&format!(
+            "  {node} [label=\"{}\"];\n",
+            escape_json(&node_label(node, labels))
+        ));
+    }
+    for (u, v, weight) in edge_list(graph) {
+        let length = format_weight(weight);
+        dot.push_str(&format!(
+            "  {u} -- {v} [weight={length}, label=\"{length}\"];\n"
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+pub fn route_to_dot(path: &[usize], graph: &RoadGraph, labels: &BTreeMap<usize, String>) -> String {
+    // the route over the map it was planned on as a graphviz graph, one edge per hop labelled with its place in
+    // the walking order, with the re-walked hops drawn dashed
+    let mut dot = String::from("graph route {\n");
+    let visited: BTreeSet<usize> = path.iter().copied().collect();
+    for node in visited {
+        dot.push_str(&format!(
+            "  {node} [label=\"{}\"];\n",
+            escape_json(&node_label(node, labels))
+        ));
+    }
+    let hops = path
+        .windows(2)
+        .zip(hop_weights(path, graph))
+        .zip(rewalked_hops(path, graph));
+    for (step, ((hop, weight), rewalked)) in hops.enumerate() {
+        let style = if rewalked {
+            ", style=dashed, color=red"
+        } else {
+            ""
+        };
+        dot.push_str(&format!(
+            "  {} -- {} [label=\"{}\", weight={}{style}];\n",
+            hop[0],
+            hop[1],
+            step + 1,
+            format_weight(weight)
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn rewalked_hops(path: &[usize], graph: &RoadGraph) -> Vec<bool> {
+    // whether each hop of path re-walks a street of graph, the map the route was planned on. a pair of
+    // intersections crossed more times than the map has streets between them is being re-walked on the extra
+    // crossings
+    let mut streets: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (u, v, _) in edge_list(graph) {
+        *streets.entry((u, v)).or_insert(0) += 1;
+    }
+    let mut crossings: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    path.windows(2)
+        .map(|hop| {
+            let pair = (hop[0].min(hop[1]), hop[0].max(hop[1]));
+            let crossed = crossings.entry(pair).or_insert(0);
+            *crossed += 1;
+            *crossed > streets.get(&pair).copied().unwrap_or(0)
+        })
+        .collect()
+}
+
+pub fn route_svg(
+    path: &[usize],
+    graph: &RoadGraph,
+    coords: &[Option<(f64, f64)>],
+    labels: &BTreeMap<usize, String>,
+) -> String {
+    // a printable map: every street of graph (the map as read) in grey, the route over it as numbered arrows in
+    // walking order, and the re-walked hops dashed. coords are (latitude, longitude) by node, and streets or hops
+    // with an end that isn't located are left off. longitude is scaled by the cosine of the middle latitude so
+    // the map isn't stretched sideways, and north is at the top
+    let size = 1000.0;
+    let margin = 40.0;
+    let located: Vec<(f64, f64)> = coords.iter().flatten().copied().collect();
+    let max_lat = located.iter().map(|at| at.0).fold(f64::MIN, f64::max);
+    let min_lat = located.iter().map(|at| at.0).fold(f64::MAX, f64::min);
+    let squash = ((max_lat + min_lat) / 2.0).to_radians().cos();
+    let max_lon = located.iter().map(|at| at.1).fold(f64::MIN, f64::max);
+    let min_lon = located.iter().map(|at| at.1).fold(f64::MAX, f64::min);
+    let span = f64::max(max_lat - min_lat, (max_lon - min_lon) * squash);
+    let scale = if span > 0.0 { size / span } else { 0.0 };
+    let project = |node: usize| {
+        coords.get(node).copied().flatten().map(|(lat, lon)| {
+            (
+                margin + (lon - min_lon) * squash * scale,
+                margin + (max_lat - lat) * scale,
+            )
+        })
+    };
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\">\n\
+         <defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"6\" \
+         markerHeight=\"6\" orient=\"auto\"><path d=\"M0,0 L10,5 L0,10 z\" fill=\"red\"/></marker></defs>\n",
+        size + 2.0 * margin
+    );
+    for (u, v, _) in edge_list(graph) {
+        if let (Some((x1, y1)), Some((x2, y2))) = (project(u), project(v)) {
+            svg.push_str(&format!(
+                "  <line x1=\"{x1:.1}\" y1=\"{y1:.1}\" x2=\"{x2:.1}\" y2=\"{y2:.1}\" stroke=\"#bbb\" stroke-width=\"6\"/>\n"
+            ));
+        }
+    }
+    // each crossing of the same street is nudged sideways a little further so they don't hide one another
+    let mut crossings: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (step, (hop, rewalked)) in path.windows(2).zip(rewalked_hops(path, graph)).enumerate() {
+        let (Some((x1, y1)), Some((x2, y2))) = (project(hop[0]), project(hop[1])) else {
+            continue;
+        };
+        let crossed = crossings
+            .entry((hop[0].min(hop[1]), hop[0].max(hop[1])))
+            .or_insert(0);
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt().max(1.0);
+        let offset = 4.0 * *crossed as f64;
+        *crossed += 1;
+        let (dx, dy) = (-(y2 - y1) / length * offset, (x2 - x1) / length * offset);
+        let dash = if rewalked {
+            " stroke-dasharray=\"8,5\""
+        } else {
+            ""
+        };
+        svg.push_str(&format!(
+            "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"red\" stroke-width=\"2\"{dash} \
+             marker-end=\"url(#arrow)\"/>\n  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"11\" fill=\"red\">{}</text>\n",
+            x1 + dx,
+            y1 + dy,
+            x2 + dx,
+            y2 + dy,
+            (x1 + x2) / 2.0 + dx + 3.0,
+            (y1 + y2) / 2.0 + dy - 3.0,
+            step + 1
+        ));
+    }
+    for node in 0..graph.node_count() {
+        if let Some((x, y)) = project(node) {
+            svg.push_str(&format!(
+                "  <circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"4\"/>\n  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"13\">{}</text>\n",
+                x + 6.0,
+                y - 6.0,
+                escape_xml(&node_label(node, labels))
+            ));
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn to_svg_frames(path: &[usize], coords: &[(f64, f64)], labels: &[String]) -> Vec<String> {
+    // one svg per step of the route, each drawing the route walked so far as a polyline over the visited
+    // intersections, so the frames can be played back to show the route being traced. coords are
+    // (latitude, longitude) indexed by node, and longitude runs left to right with north at the top
+    let size = 800.0;
+    let margin = 20.0;
+    let lats = path.iter().map(|node| coords[*node].0);
+    let lons = path.iter().map(|node| coords[*node].1);
+    let max_lat = lats.clone().fold(f64::MIN, f64::max);
+    let min_lat = lats.fold(f64::MAX, f64::min);
+    let max_lon = lons.clone().fold(f64::MIN, f64::max);
+    let min_lon = lons.fold(f64::MAX, f64::min);
+    let span = f64::max(max_lat - min_lat, max_lon - min_lon);
+    let scale = if span > 0.0 { size / span } else { 0.0 };
+    let project = |node: usize| {
+        let (lat, lon) = coords[node];
+        (
+            margin + (lon - min_lon) * scale,
+            margin + (max_lat - lat) * scale,
+        )
+    };
+    let mut frames: Vec<String> = vec![];
+    for step in 0..path.len() {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\">\n",
+            size + 2.0 * margin
+        );
+        let points: Vec<String> = path[..=step]
+            .iter()
+            .map(|node| {
+                let (x, y) = project(*node);
+                format!("{x:.1},{y:.1}")
+            })
+            .collect();
+        svg.push_str(&format!(
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"red\" stroke-width=\"3\"/>\n",
+            points.join(" ")
+        ));
+        let mut drawn: Vec<usize> = vec![];
+        for node in &path[..=step] {
+            if drawn.contains(node) {
+                continue;
+            }
+            drawn.push(*node);
+            let (x, y) = project(*node);
+            svg.push_str(&format!(
+                "  <circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"4\"/>\n  <text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n",
+                x + 6.0,
+                y - 6.0,
+                escape_xml(&labels[*node])
+            ));
+        }
+        svg.push_str("</svg>\n");
+        frames.push(svg);
+    }
+    frames
+}
+
+pub fn to_kml(path: &[usize], coords: &[(f64, f64)]) -> String {
+    // the route as one line through the intersections in the order they're visited, for opening in Google
+    // Earth. coords are (latitude, longitude) indexed by node, but kml wants longitude first
+    let coordinates: Vec<String> = path
+        .iter()
+        .map(|node| {
+            let (lat, lon) = coords[*node];
+            format!("{lon},{lat},0")
+        })
+        .collect();
+    let mut kml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    kml.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n");
+    kml.push_str("  <Placemark>\n");
+    kml.push_str(&format!(
+        "    <name>{}</name>\n",
+        escape_xml(&alphabetize(path))
+    ));
+    kml.push_str("    <LineString>\n      <tessellate>1</tessellate>\n");
+    kml.push_str(&format!(
+        "      <coordinates>{}</coordinates>\n",
+        coordinates.join(" ")
+    ));
+    kml.push_str("    </LineString>\n  </Placemark>\n</kml>\n");
+    kml
+}
+
+pub fn to_gpx(
+    path: &[usize],
+    coords: &[Option<(f64, f64)>],
+    labels: &BTreeMap<usize, String>,
+) -> String {
+    // the route as a gpx 1.1 track for a handheld gps or phone, one track point per intersection in the order
+    // they're visited. coords are (latitude, longitude) by node. a node with no coordinates is left out of the
+    // track, so the line just cuts straight across to the next one that has them
+    let mut gpx = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    gpx.push_str(
+        "<gpx version=\"1.1\" creator=\"pacsam_optimization\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+    );
+    gpx.push_str("  <trk>\n");
+    gpx.push_str(&format!(
+        "    <name>{}</name>\n",
+        escape_xml(&label_nodes(path, labels))
+    ));
+    gpx.push_str("    <trkseg>\n");
+    for node in path {
+        if let Some((lat, lon)) = coords.get(*node).copied().flatten() {
+            gpx.push_str(&format!(
+                "      <trkpt lat=\"{lat}\" lon=\"{lon}\"><name>{}</name></trkpt>\n",
+                escape_xml(&node_label(*node, labels))
+            ));
+        }
+    }
+    gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+    gpx
+}
+
+pub fn parse_coordinates(input: &str) -> Result<Vec<Option<(f64, f64)>>, PacsamError> {
+    // a coordinate table laid out like the map file: line n is node n, written as `latitude,longitude`, with an
+    // optional elevation after them (see coordinate_elevations). a blank line is a node without coordinates
+    input
+        .lines()
+        .enumerate()
+        .map(|(line_counter, line)| {
+            if line.trim().is_empty() {
+                return Ok(None);
+            }
+            let parse_error = || PacsamError::Parse {
+                line: line_counter + 1,
+                column: 1,
+                token: line.to_string(),
+                expected: "latitude,longitude[,elevation]",
+            };
+            let mut values = line.split(',').map(|value| value.trim().parse::<f64>());
+            match (values.next(), values.next(), values.next(), values.next()) {
+                (Some(Ok(lat)), Some(Ok(lon)), None | Some(Ok(_)), None) => Ok(Some((lat, lon))),
+                _ => Err(parse_error()),
+            }
+        })
+        .collect()
+}
+
+pub fn coordinate_elevations(input: &str, unit: &Unit) -> Vec<Option<f64>> {
+    // the elevations in a coordinate table, like `40.0012,-75.1934,212`, in feet. they're in the map file's unit.
+    // parse_coordinates has already reported any line that doesn't parse
+    input
+        .lines()
+        .map(|line| {
+            let elevation = line.split(',').nth(2)?.trim().parse::<f64>().ok()?;
+            Some(in_feet_f64(elevation, unit))
+        })
+        .collect()
+}
+
+pub fn map_links(
+    path: &[usize],
+    coords: &[Option<(f64, f64)>],
+    service: MapService,
+) -> Vec<String> {
+    // the route as walking directions links, each taking up where the last left off. google takes an origin, a
+    // destination and up to 8 stops between them in one link, which keeps under the 10 points its apps reliably
+    // follow. apple only takes the two ends, so it's a link per street. coords are (latitude, longitude) by node,
+    // and a node without them is skipped, so the leg cuts across to the next located one
+    let mut points: Vec<(f64, f64)> = vec![];
+    for at in path
+        .iter()
+        .filter_map(|node| coords.get(*node).copied().flatten())
+    {
+        if points.last() != Some(&at) {
+            points.push(at);
+        }
+    }
+    let per_link = match service {
+        MapService::Google => 10,
+        MapService::Apple => 2,
+    };
+    let point = |(lat, lon): &(f64, f64)| format!("{lat:.6},{lon:.6}");
+    // the last point of each link is the first of the next
+    let mut links = vec![];
+    let mut first = 0;
+    while first + 1 < points.len() {
+        let leg = &points[first..(first + per_link).min(points.len())];
+        let (origin, destination) = (point(&leg[0]), point(&leg[leg.len() - 1]));
+        links.push(match service {
+            MapService::Google => {
+                let stops: Vec<String> = leg[1..leg.len() - 1].iter().map(point).collect();
+                let waypoints = match stops.is_empty() {
+                    true => String::new(),
+                    false => format!("&waypoints={}", stops.join("%7C")),
+                };
+                format!(
+                    "https://www.google.com/maps/dir/?api=1&origin={origin}&destination={destination}\
+                     {waypoints}&travelmode=walking"
+                )
+            }
+            MapService::Apple => {
+                format!("https://maps.apple.com/?saddr={origin}&daddr={destination}&dirflg=w")
+            }
+        });
+        first += leg.len() - 1;
+    }
+    links
+}
+
+pub fn route_geojson(
+    path: &[usize],
+    coords: &[Option<(f64, f64)>],
+    names: &BTreeMap<(usize, usize), String>,
+    repeats: &[bool],
+    labels: &BTreeMap<usize, String>,
+    visits: Option<&RouteVisits>,
+) -> String {
+    // the route as a GeoJSON FeatureCollection with a LineString feature per street walked, in walking order.
+    // each one's properties give its place in the route (from 1), its ends, its street name when there is one, and
+    // whether it's a repeat (see repeat_hops). coords are (latitude, longitude) by node, and a street with an end
+    // that isn't located is left out. with visits each street also says how many times it's walked, and a Point
+    // feature per intersection says how many times it's passed and whether it's a hotspot
+    let located = |node: usize| coords.get(node).copied().flatten();
+    let mut features: Vec<String> = path
+        .windows(2)
+        .zip(repeats.iter().chain(std::iter::repeat(&false)))
+        .enumerate()
+        .filter_map(|(order, (hop, repeat))| {
+            let ((from_lat, from_lon), (to_lat, to_lon)) = (located(hop[0])?, located(hop[1])?);
+            let street = (hop[0].min(hop[1]), hop[0].max(hop[1]));
+            let name = match names.get(&street) {
+                Some(name) => format!(",\"name\":\"{}\"", escape_json(name)),
+                None => String::new(),
+            };
+            let walked = match visits {
+                Some(visits) => format!(",\"visits\":{}", visits.streets[&street]),
+                None => String::new(),
+            };
+            Some(format!(
+                "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"LineString\",\"coordinates\":\
+                 [[{from_lon},{from_lat}],[{to_lon},{to_lat}]]}},\"properties\":{{\"order\":{},\"from\":\"{}\",\
+                 \"to\":\"{}\"{name},\"repeat\":{repeat}{walked}}}}}",
+                order + 1,
+                escape_json(&node_label(hop[0], labels)),
+                escape_json(&node_label(hop[1], labels))
+            ))
+        })
+        .collect();
+    for (node, passes) in visits.iter().flat_map(|visits| &visits.nodes) {
+        let (Some((lat, lon)), Some(visits)) = (located(*node), visits) else {
+            continue;
+        };
+        features.push(format!(
+            "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"Point\",\"coordinates\":[{lon},{lat}]}},\
+             \"properties\":{{\"node\":\"{}\",\"visits\":{passes},\"extra\":{},\"hotspot\":{}}}}}",
+            escape_json(&node_label(*node, labels)),
+            visits.extra(*node),
+            visits.is_hotspot(*node)
+        ));
+    }
+    format!(
+        "{{\"type\":\"FeatureCollection\",\"features\":[\n{}\n]}}\n",
+        features.join(",\n")
+    )
+}
+
+pub fn route_kml(
+    path: &[usize],
+    coords: &[Option<(f64, f64)>],
+    labels: &BTreeMap<usize, String>,
+    stops: bool,
+    visits: Option<&RouteVisits>,
+) -> String {
+    // like to_kml, for coordinates read by parse_coordinates. nodes without coordinates are left out of the
+    // line. with stops, every visit also gets a pin named with its place in the route, like `3. F`, so the order
+    // can be checked in Google Earth. a node visited twice gets two pins. with visits, each hotspot gets a pin
+    // saying how many times the route passes it
+    let located: Vec<(usize, usize, (f64, f64))> = path
+        .iter()
+        .enumerate()
+        .filter_map(|(order, node)| Some((order, *node, coords.get(*node).copied().flatten()?)))
+        .collect();
+    let coordinates: Vec<String> = located
+        .iter()
+        .map(|(_, _, (lat, lon))| format!("{lon},{lat},0"))
+        .collect();
+    let mut kml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    kml.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+    kml.push_str("  <Placemark>\n");
+    kml.push_str(&format!(
+        "    <name>{}</name>\n",
+        escape_xml(&label_nodes(path, labels))
+    ));
+    kml.push_str("    <LineString>\n      <tessellate>1</tessellate>\n");
+    kml.push_str(&format!(
+        "      <coordinates>{}</coordinates>\n",
+        coordinates.join(" ")
+    ));
+    kml.push_str("    </LineString>\n  </Placemark>\n");
+    if stops {
+        for (order, node, (lat, lon)) in &located {
+            kml.push_str(&format!(
+                "  <Placemark>\n    <name>{}. {}</name>\n    <Point><coordinates>{lon},{lat},0</coordinates></Point>\n  </Placemark>\n",
+                order + 1,
+                escape_xml(&node_label(*node, labels))
+            ));
+        }
+    }
+    if let Some(visits) = visits {
+        for node in visits.hotspots() {
+            let Some((lat, lon)) = coords.get(node).copied().flatten() else {
+                continue;
+            };
+            kml.push_str(&format!(
+                "  <Placemark>\n    <name>{}: {} passes</name>\n    <Point><coordinates>{lon},{lat},0</coordinates></Point>\n  </Placemark>\n",
+                escape_xml(&node_label(node, labels)),
+                visits.nodes[&node]
+            ));
+        }
+    }
+    kml.push_str("</Document>\n</kml>\n");
+    kml
+}
+
+// an intersection passed this many more times than its streets need is a hotspot, where the walker keeps crossing
+// their own path
+const HOTSPOT_EXTRA: usize = 2;
+
+pub struct RouteVisits {
+    // how many times the route passes through each intersection it reaches, a circuit's start and end counting
+    // as one pass, and how many times it walks each street, keyed u < v
+    pub nodes: BTreeMap<usize, usize>,
+    pub streets: BTreeMap<(usize, usize), usize>,
+    // the fewest passes each intersection of the map could do with: half its streets, rounded up, since a pass
+    // goes down two of them
+    pub needed: Vec<usize>,
+}
+
+pub fn route_visits(path: &[usize], graph: &RoadGraph) -> RouteVisits {
+    // the counts behind a heatmap of path over graph, the map as it was read before routing added its repeats
+    let mut nodes: BTreeMap<usize, usize> = BTreeMap::new();
+    let closed = path.len() > 1 && path.first() == path.last();
+    for node in &path[..path.len() - usize::from(closed)] {
+        *nodes.entry(*node).or_insert(0) += 1;
+    }
+    let mut streets: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for hop in path.windows(2) {
+        *streets
+            .entry((hop[0].min(hop[1]), hop[0].max(hop[1])))
+            .or_insert(0) += 1;
+    }
+    RouteVisits {
+        nodes,
+        streets,
+        needed: (0..graph.node_count())
+            .map(|node| graph.degree(node).div_ceil(2))
+            .collect(),
+    }
+}
+
+impl RouteVisits {
+    pub fn extra(&self, node: usize) -> usize {
+        // the passes through node past the ones its streets need
+        let needed = self.needed.get(node).copied().unwrap_or(0);
+        self.nodes
+            .get(&node)
+            .map_or(0, |passes| passes.saturating_sub(needed))
+    }
+    pub fn is_hotspot(&self, node: usize) -> bool {
+        self.extra(node) >= HOTSPOT_EXTRA
+    }
+    pub fn hotspots(&self) -> Vec<usize> {
+        // the hotspots, the most extra passes first
+        let mut hotspots: Vec<usize> = self
+            .nodes
+            .keys()
+            .copied()
+            .filter(|node| self.is_hotspot(*node))
+            .collect();
+        hotspots.sort_by_key(|node| (Reverse(self.extra(*node)), *node));
+        hotspots
+    }
+    pub fn describe(
+        &self,
+        names: &BTreeMap<(usize, usize), String>,
+        labels: &BTreeMap<usize, String>,
+    ) -> String {
+        // only what's passed more than it needs to be, the most first, since everything else is walked just once
+        let mut crossed: Vec<usize> = self
+            .nodes
+            .keys()
+            .copied()
+            .filter(|node| self.extra(*node) > 0)
+            .collect();
+        crossed.sort_by_key(|node| (Reverse(self.extra(*node)), *node));
+        let mut lines = vec![format!(
+            "intersections passed more than their streets need: {} ({} hotspots)",
+            crossed.len(),
+            self.hotspots().len()
+        )];
+        for node in crossed {
+            lines.push(format!(
+                "  {}: {} passes, {} extra{}",
+                node_label(node, labels),
+                self.nodes[&node],
+                self.extra(node),
+                if self.is_hotspot(node) {
+                    ", hotspot"
+                } else {
+                    ""
+                }
+            ));
+        }
+        let mut rewalked: Vec<(&(usize, usize), &usize)> = self
+            .streets
+            .iter()
+            .filter(|(_, times)| **times > 1)
+            .collect();
+        rewalked.sort_by_key(|(street, times)| (Reverse(**times), **street));
+        lines.push(format!("streets walked more than once: {}", rewalked.len()));
+        for ((u, v), times) in rewalked {
+            let ends = format!("{} -- {}", node_label(*u, labels), node_label(*v, labels));
+            lines.push(match names.get(&(*u, *v)) {
+                Some(name) => format!("  {name} ({ends}): {times} times"),
+                None => format!("  {ends}: {times} times"),
+            });
+        }
+        lines.join("\n")
+    }
+}
+
+pub fn utilization_csv(path: &[usize], coords: &[Option<(f64, f64)>]) -> String {
+    // how many times the route walks each street, one row per street with both ends' coordinates so it can be
+    // dropped straight onto a heatmap. coords are (latitude, longitude) by node, and a street whose ends aren't
+    // both located can't be placed on a map, so it's left out with a warning
+    let mut counts: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for hop in path.windows(2) {
+        let street = (hop[0].min(hop[1]), hop[0].max(hop[1]));
+        *counts.entry(street).or_insert(0) += 1;
+    }
+    let mut csv = String::from("from_lon,from_lat,to_lon,to_lat,count\n");
+    for ((u, v), count) in counts {
+        match (
+            coords.get(u).copied().flatten(),
+            coords.get(v).copied().flatten(),
+        ) {
+            (Some((u_lat, u_lon)), Some((v_lat, v_lon))) => {
+                csv.push_str(&format!("{u_lon},{u_lat},{v_lon},{v_lat},{count}\n"));
+            }
+            _ => eprintln!("warning: street {u}-{v} has no coordinates, leaving it out"),
+        }
+    }
+    csv
+}
+
+fn escape_json(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+enum Json<'a> {
+    // a json value, as much of json as reading back the to_json output and GeoJSON needs. numbers keep their text
+    // so a length can go through parse_weight without picking up float error
+    Number(&'a str),
+    Bool(bool),
+    Text(String),
+    Null,
+    Array(Vec<Json<'a>>),
+    Object(Vec<(String, Json<'a>)>),
+}
+
+impl<'a> Json<'a> {
+    fn mismatch(&self, key: &str, expected: &'static str) -> PacsamError {
+        // a value of the wrong kind, reported by the key it's under since the parse has already finished
+        PacsamError::Parse {
+            line: 1,
+            column: 1,
+            token: key.to_string(),
+            expected,
+        }
+    }
+    fn field(&self, key: &str) -> Result<&Json<'a>, PacsamError> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value)
+                .ok_or_else(|| self.mismatch(key, "a field with this name")),
+            _ => Err(self.mismatch(key, "an object")),
+        }
+    }
+    fn items(&self, key: &str) -> Result<&[Json<'a>], PacsamError> {
+        match self {
+            Json::Array(items) => Ok(items),
+            _ => Err(self.mismatch(key, "an array")),
+        }
+    }
+    fn index(&self, key: &str) -> Result<usize, PacsamError> {
+        match self {
+            Json::Number(text) => text
+                .parse()
+                .map_err(|_| self.mismatch(key, "a whole number")),
+            _ => Err(self.mismatch(key, "a whole number")),
+        }
+    }
+    fn weight(&self, key: &str) -> Result<usize, PacsamError> {
+        match self {
+            Json::Number(text) => parse_weight(text).ok_or_else(|| self.mismatch(key, "a length")),
+            _ => Err(self.mismatch(key, "a length")),
+        }
+    }
+    fn number(&self, key: &str) -> Result<f64, PacsamError> {
+        match self {
+            Json::Number(text) => text.parse().map_err(|_| self.mismatch(key, "a number")),
+            _ => Err(self.mismatch(key, "a number")),
+        }
+    }
+    fn string(&self, key: &str) -> Result<&str, PacsamError> {
+        match self {
+            Json::Text(text) => Ok(text),
+            _ => Err(self.mismatch(key, "a string")),
+        }
+    }
+    fn flag(&self, key: &str) -> Result<bool, PacsamError> {
+        match self {
+            Json::Bool(flag) => Ok(*flag),
+            _ => Err(self.mismatch(key, "true or false")),
+        }
+    }
+}
+
+fn parse_json(input: &str) -> Result<Json<'_>, PacsamError> {
+    let mut parser = JsonParser { input, at: 0 };
+    let value = parser.value()?;
+    parser.skip_space();
+    if parser.at < input.len() {
+        return Err(parser.error("the end of the json"));
+    }
+    Ok(value)
+}
+
+struct JsonParser<'a> {
+    input: &'a str,
+    // the byte offset reading has got to
+    at: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn error(&self, expected: &'static str) -> PacsamError {
+        let before = &self.input[..self.at];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        PacsamError::Parse {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            token: self.input[self.at..].chars().take(20).collect(),
+            expected,
+        }
+    }
+    fn skip_space(&mut self) {
+        let rest = &self.input[self.at..];
+        self.at += rest.len() - rest.trim_start().len();
+    }
+    fn eat(&mut self, text: &str) -> bool {
+        self.skip_space();
+        let found = self.input[self.at..].starts_with(text);
+        if found {
+            self.at += text.len();
+        }
+        found
+    }
+    fn value(&mut self) -> Result<Json<'a>, PacsamError> {
+        self.skip_space();
+        let rest = &self.input[self.at..];
+        if self.eat("[") {
+            let mut items = vec![];
+            if !self.eat("]") {
+                loop {
+                    items.push(self.value()?);
+                    if self.eat("]") {
+                        break;
+                    }
+                    if !self.eat(",") {
+                        return Err(self.error("a , or ]"));
+                    }
+                }
+            }
+            Ok(Json::Array(items))
+        } else if self.eat("{") {
+            let mut fields = vec![];
+            if !self.eat("}") {
+                loop {
+                    self.skip_space();
+                    let key = self.text()?;
+                    if !self.eat(":") {
+                        return Err(self.error("a :"));
+                    }
+                    fields.push((key, self.value()?));
+                    if self.eat("}") {
+                        break;
+                    }
+                    if !self.eat(",") {
+                        return Err(self.error("a , or }"));
+                    }
+                }
+            }
+            Ok(Json::Object(fields))
+        } else if rest.starts_with('"') {
+            Ok(Json::Text(self.text()?))
+        } else if self.eat("null") {
+            Ok(Json::Null)
+        } else if self.eat("true") {
+            Ok(Json::Bool(true))
+        } else if self.eat("false") {
+            Ok(Json::Bool(false))
+        } else {
+            let length = rest
+                .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+                .unwrap_or(rest.len());
+            if length == 0 {
+                return Err(self.error("a json value"));
+            }
+            self.at += length;
+            Ok(Json::Number(&rest[..length]))
+        }
+    }
+    fn text(&mut self) -> Result<String, PacsamError> {
+        // a quoted string, undoing escape_json and the other single character escapes, and \u ones
+        if !self.eat("\"") {
+            return Err(self.error("a quoted string"));
+        }
+        let mut text = String::new();
+        let mut chars = self.input[self.at..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.at += offset + 1;
+                    return Ok(text);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => text.push('\n'),
+                    Some((_, 't')) => text.push('\t'),
+                    Some((_, 'r')) => text.push('\r'),
+                    Some((_, escaped @ ('"' | '\\' | '/'))) => text.push(escaped),
+                    Some((_, 'b')) => text.push('\u{8}'),
+                    Some((_, 'f')) => text.push('\u{c}'),
+                    // a utf-16 code unit, and a character past the first 65536 is two of them in a row
+                    Some((_, 'u')) => {
+                        let unit = |chars: &mut std::str::CharIndices| -> Option<u32> {
+                            let hex: String = (0..4)
+                                .filter_map(|_| chars.next())
+                                .map(|(_, c)| c)
+                                .collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .filter(|_| hex.len() == 4)
+                        };
+                        let decoded = match unit(&mut chars) {
+                            Some(high @ 0xd800..=0xdbff) => {
+                                let low = match (chars.next(), chars.next()) {
+                                    (Some((_, '\\')), Some((_, 'u'))) => unit(&mut chars),
+                                    _ => None,
+                                };
+                                low.filter(|low| (0xdc00..=0xdfff).contains(low))
+                                    .and_then(|low| {
+                                        char::from_u32(
+                                            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00),
+                                        )
+                                    })
+                            }
+                            Some(unit) => char::from_u32(unit),
+                            None => None,
+                        };
+                        match decoded {
+                            Some(c) => text.push(c),
+                            None => {
+                                self.at += offset;
+                                return Err(self.error("four hex digits after \\u"));
+                            }
+                        }
+                    }
+                    _ => {
+                        self.at += offset;
+                        return Err(self.error("an escape like \\\" or \\n"));
+                    }
+                },
+                c => text.push(c),
+            }
+        }
+        Err(self.error("a closing quote"))
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn compact(graph: &mut RoadGraph) -> Vec<usize> {
+    // the builder sizes the graph by the largest index it sees, so an input that skips indices ends up with
+    // isolated phantom nodes. this renumbers the nodes that actually have streets to a contiguous range and
+    // returns the old index of each new one, so results can be mapped back onto the original labels
+    let mut new_to_old: Vec<usize> = vec![];
+    let mut old_to_new: Vec<usize> = vec![usize::MAX; graph.node_count()];
+    for (i, new_index) in old_to_new.iter_mut().enumerate() {
+        if graph.degree(i) > 0 {
+            *new_index = new_to_old.len();
+            new_to_old.push(i);
+        }
+    }
+    let edges: Vec<(usize, usize, usize)> = edge_list(graph)
+        .into_iter()
+        .map(|(u, v, weight)| (old_to_new[u], old_to_new[v], weight))
+        .collect();
+    *graph = GraphBuilder::new().edges_with_values(edges).build();
+    new_to_old
+}
+
+pub fn connected_components(graph: &RoadGraph) -> Vec<Vec<usize>> {
+    // isolated nodes have no streets to cover, so they don't count as components of their own
+    let mut seen = vec![false; graph.node_count()];
+    let mut components: Vec<Vec<usize>> = vec![];
+    for start in 0..graph.node_count() {
+        if seen[start] || graph.degree(start) == 0 {
+            continue;
+        }
+        components.push(component_from(graph, start, &mut seen));
+    }
+    components
+}
+
+pub fn iter_components(graph: &RoadGraph) -> impl Iterator<Item = RoadGraph> + '_ {
+    // same pieces as connected_components, each renumbered from 0, but only searched for and built when asked for
+    // so a big multi-zone file never has all of them in memory at once
+    let mut seen = vec![false; graph.node_count()];
+    let mut next = 0;
+    std::iter::from_fn(move || {
+        while next < graph.node_count() {
+            let start = next;
+            next += 1;
+            if !seen[start] && graph.degree(start) > 0 {
+                let component = component_from(graph, start, &mut seen);
+                return Some(subgraph(graph, &component));
+            }
+        }
+        None
+    })
+}
+
+fn component_from(graph: &RoadGraph, start: usize, seen: &mut [bool]) -> Vec<usize> {
+    // breadth first search out from start, marking everything reached. the result is sorted
+    seen[start] = true;
+    let mut component: Vec<usize> = vec![];
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        component.push(node);
+        for neighbor in graph.neighbors_with_values(node) {
+            if !seen[neighbor.target] {
+                seen[neighbor.target] = true;
+                queue.push_back(neighbor.target);
+            }
+        }
+    }
+    component.sort();
+    component
+}
+
+pub fn trim_to_largest_component(graph: &mut RoadGraph) -> usize {
+    // keeps only the biggest connected piece of a messy import, renumbered from 0, and returns how many nodes were
+    // thrown away. on a tie the piece containing the lowest index wins
+    let node_count = graph.node_count();
+    let mut largest: Vec<usize> = vec![];
+    for component in connected_components(graph) {
+        if component.len() > largest.len() {
+            largest = component;
+        }
+    }
+    *graph = subgraph(graph, &largest);
+    node_count - graph.node_count()
+}
+
+pub fn routes_per_component(graph: &RoadGraph) -> Result<Vec<Route>, PacsamError> {
+    // some input files hold several separate delivery zones. each one is cut out into its own graph, run through
+    // the usual cul de sac / eulerize / cycle steps, and its route is mapped back onto the original node indices
+    // the zones are independent of each other, so with the parallel feature they're routed on rayon's thread pool.
+    // either way the routes come back in component order, and the first zone that fails is the error
+    let components = connected_components(graph);
+    #[cfg(feature = "parallel")]
+    let components_iter = components.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let components_iter = components.iter();
+    components_iter
+        .map(|component| route_component(graph, component))
+        .collect()
+}
+
+fn route_component(graph: &RoadGraph, component: &[usize]) -> Result<Route, PacsamError> {
+    let zone = subgraph(graph, component);
+    let copies = street_copies(&zone);
+    let street_count = zone.edge_count();
+    let path = if is_tree(&zone) {
+        tree_route(&zone, 0)
+    } else {
+        fix_culdesacs(&zone, &BoundaryPolicy::TreatAsCuldesac);
+        eulerize(&zone);
+        find_cycle(&zone)?
+    };
+    // measured on the zone's own numbers, then every node is put back to the one it is in graph
+    let weights = hop_weights(&path, &zone);
+    let feet =
+        weights.iter().map(|weight| *weight as u128).sum::<u128>() as f64 / WEIGHT_SCALE as f64;
+    Ok(Route {
+        edges: path
+            .windows(2)
+            .zip(weights)
+            .map(|(hop, weight)| (component[hop[0]], component[hop[1]], weight))
+            .collect(),
+        total_feet: feet,
+        total_miles: feet_to_miles(feet),
+        duplicated_edge_count: path.len().saturating_sub(1) - street_count,
+        repeats: repeat_hops(&path, &copies, false),
+        path: path.into_iter().map(|node| component[node]).collect(),
+    })
+}
+
+pub fn same_network(a: &RoadGraph, b: &RoadGraph) -> bool {
+    // the graph type has no PartialEq of its own and its neighbor lists keep insertion order, so two networks read
+    // from reordered but equivalent inputs are compared by their sorted edge lists
+    let mut a_edges = edge_list(a);
+    let mut b_edges = edge_list(b);
+    a_edges.sort();
+    b_edges.sort();
+    a_edges == b_edges
+}
+
+pub fn map_weights(graph: &mut RoadGraph, f: impl Fn(usize) -> usize) {
+    // rebuilds the graph with every edge weight passed through f, e.g. to convert meters to feet or to add a fixed
+    // service time per street. f sees the stored hundredths (see WEIGHT_SCALE). the streets themselves stay the same
+    let edges: Vec<(usize, usize, usize)> = edge_list(graph)
+        .into_iter()
+        .map(|(u, v, weight)| (u, v, f(weight)))
+        .collect();
+    *graph = GraphBuilder::new().edges_with_values(edges).build();
+}
+
+pub fn swap_nodes(graph: &mut RoadGraph, labels: &mut BTreeMap<usize, String>, a: usize, b: usize) {
+    // exchanges two node indices, carrying all of their streets and names along with them, e.g. to make the depot
+    // node 0 without re-authoring the input file
+    if a >= graph.node_count() || b >= graph.node_count() {
+        return;
+    }
+    let swap = |node: usize| {
+        if node == a {
+            b
+        } else if node == b {
+            a
+        } else {
+            node
+        }
+    };
+    let edges: Vec<(usize, usize, usize)> = edge_list(graph)
+        .into_iter()
+        .map(|(u, v, weight)| (swap(u), swap(v), weight))
+        .collect();
+    *graph = GraphBuilder::new().edges_with_values(edges).build();
+    let (label_a, label_b) = (labels.remove(&a), labels.remove(&b));
+    labels.extend(label_a.map(|label| (b, label)));
+    labels.extend(label_b.map(|label| (a, label)));
+}
+
+fn copy_graph(graph: &RoadGraph) -> RoadGraph {
+    // the graph type isn't Clone, and eulerizing mutates in place, so this rebuilds an independent copy
+    GraphBuilder::new()
+        .edges_with_values(edge_list(graph))
+        .build()
+}
+
+fn graph_with_nodes(edges: Vec<(usize, usize, usize)>, node_count: usize) -> RoadGraph {
+    // GraphBuilder sizes the graph by the largest node in the edges, so this is for when it needs to be bigger
+    UndirectedALGraph::from((
+        EdgeList::with_max_node_id(edges, node_count.saturating_sub(1)),
+        CsrLayout::default(),
+    ))
+}
+
+fn directed_with_nodes(arcs: Vec<(usize, usize, usize)>, node_count: usize) -> DirectedRoadGraph {
+    // graph_with_nodes for one-way maps
+    DirectedALGraph::from((
+        EdgeList::with_max_node_id(arcs, node_count.saturating_sub(1)),
+        CsrLayout::default(),
+    ))
+}
+
+fn subgraph(graph: &RoadGraph, nodes: &[usize]) -> RoadGraph {
+    // nodes must be sorted. node i of the new graph is nodes[i] of the old one
+    let edges: Vec<(usize, usize, usize)> = edge_list(graph)
+        .into_iter()
+        .filter_map(|(u, v, weight)| {
+            let new_u = nodes.binary_search(&u).ok()?;
+            let new_v = nodes.binary_search(&v).ok()?;
+            Some((new_u, new_v, weight))
+        })
+        .collect();
+    GraphBuilder::new().edges_with_values(edges).build()
+}
+
+fn edge_list(graph: &RoadGraph) -> Vec<(usize, usize, usize)> {
+    // every undirected edge shows up in the neighbor lists of both of its ends, so only keep the copy seen from
+    // the lower index. parallel edges are kept, and self loops (listed twice on the same node) are counted once.
+    // they come out sorted, whatever order the graph happens to hold them in
+    let mut edges: Vec<(usize, usize, usize)> = vec![];
+    for i in 0..graph.node_count() {
+        let mut self_loops = 0;
+        for (target, weight) in neighbors_vec(graph, i) {
+            if target > i {
+                edges.push((i, target, weight));
+            } else if target == i {
+                self_loops += 1;
+                if self_loops % 2 == 0 {
+                    edges.push((i, i, weight));
+                }
+            }
+        }
+    }
+    edges
+}
+
+fn check_indices(input: &str) -> Result<(), PacsamError> {
+    // the graph gets a node for every index up to the largest one mentioned, so one typo like 0:9999:100 would
+    // create thousands of phantom nodes. a file with n lines and m streets can only name n + m different nodes,
+    // so an index past that can't be part of a gap-free numbering
+    let line_count = input.lines().count();
+    let tokens: Vec<(usize, &str)> = input
+        .lines()
+        .enumerate()
+        .flat_map(|(line_counter, line)| line.split(',').map(move |edge| (line_counter, edge)))
+        .filter(|(_, edge)| edge.contains(':'))
+        .collect();
+    let bound = line_count + tokens.len();
+    for (line_counter, edge) in tokens {
+        let vertex = edge.split(':').next().unwrap_or_default().trim();
+        if let Ok(index) = vertex.parse::<usize>() {
+            if index >= bound {
+                return Err(PacsamError::IndexOutOfRange {
+                    line: line_counter + 1,
+                    index,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn build_graph_durations(input: &str) -> Result<RoadGraph, PacsamError> {
+    // same layout as build_graph, but each weight is a travel time like 0:01:30. the time has colons of its own, so
+    // only the first colon separates the vertex from the weight
+    let mut edges: Vec<(usize, usize, usize)> = vec![];
+    for (line_counter, line) in input.lines().enumerate() {
+        for edge in line.split(',') {
+            let Some((vertex, duration)) = edge.split_once(':') else {
+                continue;
+            };
+            let vertex = vertex
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| PacsamError::Parse {
+                    line: line_counter + 1,
+                    column: column_of(line, vertex.trim_start()),
+                    token: vertex.trim().to_string(),
+                    expected: "a node index",
+                })?;
+            let seconds = parse_duration(duration).ok_or_else(|| PacsamError::Parse {
+                line: line_counter + 1,
+                column: column_of(line, duration.trim_start()),
+                token: duration.to_string(),
+                expected: "a travel time as H:MM:SS",
+            })?;
+            edges.push((line_counter, vertex, seconds * WEIGHT_SCALE));
+        }
+    }
+    Ok(GraphBuilder::new().edges_with_values(edges).build())
+}
+
+pub fn parse_duration(token: &str) -> Option<usize> {
+    // accepts H:MM:SS, M:SS or plain seconds. everything after the leading field has to be under 60
+    let parts: Vec<&str> = token.trim().split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    let mut seconds = 0;
+    for (i, part) in parts.iter().enumerate() {
+        let value = part.parse::<usize>().ok()?;
+        if i > 0 && value >= 60 {
+            return None;
+        }
+        seconds = seconds * 60 + value;
+    }
+    Some(seconds)
+}
+
+pub fn format_duration(seconds: usize) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floyd_warshall_matches_hand_computed_distances() {
+        // a square 0-1-2-3 with a 2.5 foot diagonal 0-2 that's shorter than going round, and 4-5 off on their own
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 200),
+                (2, 3, 100),
+                (3, 0, 300),
+                (0, 2, 250),
+                (4, 5, 700),
+            ],
+            6,
+        );
+        let distances = all_pairs_shortest_paths(&graph);
+        assert_eq!(distances[0][0], 0);
+        assert_eq!(distances[0][1], 100);
+        // by the diagonal rather than 0-1-2 at 300
+        assert_eq!(distances[0][2], 250);
+        // the 300 foot street straight there beats 0-2-3 at 350
+        assert_eq!(distances[0][3], 300);
+        // 1-2-3, where 1-0-3 is 400 and 1-0-2-3 is 450
+        assert_eq!(distances[1][3], 300);
+        assert_eq!(distances[4][5], 700);
+        assert_eq!(distances[0][4], usize::MAX);
+        assert_eq!(distances[5][3], usize::MAX);
+        for (u, row) in distances.iter().enumerate() {
+            for (v, distance) in row.iter().enumerate() {
+                assert_eq!(*distance, distances[v][u]);
+            }
+        }
+        // the dijkstra rows come to the same thing
+        assert_eq!(all_pairs_distances(&graph), distances);
+    }
+
+    #[test]
+    fn solver_starts_at_the_first_node_with_streets() {
+        // node 0 is isolated and the triangle is on 1, 2 and 3
+        let graph = graph_with_nodes(vec![(1, 2, 100), (2, 3, 100), (3, 1, 100)], 4);
+        let path = Solver::from_graph(graph).solve().unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(path.first(), Some(&1));
+        assert_eq!(path.last(), Some(&1));
+    }
+
+    #[test]
+    fn calling_solve_twice_yields_identical_paths() {
+        // eulerize works on a copy of the solver's graph, so the second solve mustn't see the first one's duplicates
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 200),
+                (2, 3, 300),
+                (3, 0, 400),
+                (0, 2, 500),
+            ],
+            4,
+        );
+        let solver = Solver::from_graph(graph);
+        let first = solver.route().unwrap();
+        let second = solver.route().unwrap();
+        assert_eq!(first.path, second.path);
+        assert_eq!(first.duplicated_edge_count, second.duplicated_edge_count);
+        // 0 and 2 are odd, and 0-1-2 at 3 feet is the cheaper way between them than the 5 foot diagonal
+        assert_eq!(first.total_feet, 18.0);
+        assert_eq!(first.total_feet, second.total_feet);
+    }
+
+    #[test]
+    fn json_routes_carry_their_walkers_and_shifts() {
+        // a square of 1000 foot streets, so the 4000 foot circuit makes two shifts of 2000 feet at 0.5 miles each,
+        // 0.37 miles after lengths are cut to hundredths
+        let args = [
+            "pacsam",
+            "square.csv",
+            "--format",
+            "json",
+            "--walkers",
+            "2",
+            "--shift-length",
+            "0.5",
+        ];
+        let config = Config::build(args.iter().map(|arg| arg.to_string())).unwrap();
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100_000),
+                (1, 2, 100_000),
+                (2, 3, 100_000),
+                (3, 0, 100_000),
+            ],
+            4,
+        );
+        let path = vec![0, 1, 2, 3, 0];
+        let doors = BTreeMap::new();
+        let labels = BTreeMap::new();
+        let shares = knocked_shares(split_route(&graph, &path, 2, 0), &doors);
+        let splits = format!(
+            "{}{}",
+            walkers_json(&shares, &doors, &config, &labels),
+            shifts_json(&path, &graph, &doors, &config, &labels)
+        );
+        let summary = RouteSummary {
+            base_feet: 4000.0,
+            total_feet: 4000.0,
+            distinct_edges: 4,
+            total_traversals: 4,
+            repeats: 0,
+            doors: 0,
+            approach_feet: None,
+        };
+        let weights = hop_weights(&path, &graph);
+        let text = summary_json(
+            &summary,
+            &path,
+            &weights,
+            &[false; 4],
+            &config,
+            &labels,
+            &splits,
+        );
+        let json = parse_json(&text).unwrap();
+        let walkers = json.field("walkers").unwrap().items("walkers").unwrap();
+        assert_eq!(walkers.len(), 2);
+        let shifts = json.field("shifts").unwrap().items("shifts").unwrap();
+        assert_eq!(shifts.len(), 2);
+        assert_eq!(
+            shifts[0].field("length").unwrap().number("length").unwrap(),
+            0.37
+        );
+        assert_eq!(
+            shifts[0]
+                .field("path")
+                .unwrap()
+                .items("path")
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn walkers_are_described_in_the_chosen_units() {
+        let share = WalkerRoute {
+            path: vec![0, 1, 0],
+            feet: 5280.0,
+            deadhead_feet: 1000.0,
+        };
+        assert_eq!(
+            share.describe(&BTreeMap::new(), &Distance::Miles),
+            "1 mi (0.18 mi to and from the depot): A -- B -- A"
+        );
+        assert_eq!(
+            share.describe(&BTreeMap::new(), &Distance::Kilometers),
+            "1.6 km (0.3 km to and from the depot): A -- B -- A"
+        );
+    }
+
+    #[test]
+    fn walkers_split_the_circuit_they_are_given() {
+        // two triangles meeting at 0. find_cycle_from would go round 0-1-2 first, but the route chose 0-3-4
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (0, 3, 100),
+                (3, 4, 100),
+                (4, 0, 100),
+            ],
+            5,
+        );
+        let shares = split_route(&graph, &[0, 3, 4, 0, 1, 2, 0], 2, 0);
+        let paths: Vec<Vec<usize>> = shares.iter().map(|share| share.path.clone()).collect();
+        assert_eq!(paths, vec![vec![0, 3, 4, 0], vec![0, 1, 2, 0]]);
+        assert!(shares
+            .iter()
+            .all(|share| share.feet == 3.0 && share.deadhead_feet == 0.0));
+    }
+
+    #[test]
+    fn scripted_reviews_pin_fix_unpin_and_write() {
+        // two triangles meeting at A. the route goes round A-B-C first, and the pin asks for A-D-E before it
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (0, 3, 100),
+                (3, 4, 100),
+                (4, 0, 100),
+            ],
+            5,
+        );
+        let route = [0, 1, 2, 0, 3, 4, 0];
+        let out = std::env::temp_dir().join(format!("pacsam-review-{}", std::process::id()));
+        let written = |name: &str| {
+            let route = fs::read_to_string(out.join(name)).unwrap();
+            route.lines().next().unwrap_or_default().to_string()
+        };
+        fs::create_dir_all(&out).unwrap();
+        let script = format!(
+            "pin E D\nwrite {pinned}\nfix 1 3\nnot a command\nunpin 1\nwrite {unpinned}\nunpin 5\ndone\nwrite {late}\n",
+            pinned = out.join("pinned").display(),
+            unpinned = out.join("unpinned").display(),
+            late = out.join("late").display()
+        );
+        let labels = BTreeMap::new();
+        let mut answers = vec![];
+        review(
+            &route,
+            &graph,
+            &BTreeSet::new(),
+            &labels,
+            &Distance::Kilometers,
+            (std::io::Cursor::new(script), &mut answers),
+            false,
+        )
+        .unwrap();
+        // a line per change, can't for the ones that don't work, and the route as it ended up
+        let answers = String::from_utf8(answers).unwrap();
+        let answers: Vec<&str> = answers.lines().collect();
+        assert_eq!(answers.len(), 6, "{answers:?}");
+        assert!(answers[0].starts_with("ok: 1 pins"), "{answers:?}");
+        assert!(answers[1].starts_with("ok: 2 pins"), "{answers:?}");
+        assert!(
+            answers[2].starts_with("can't: 'not a command'"),
+            "{answers:?}"
+        );
+        assert!(answers[3].starts_with("ok: 1 pins"), "{answers:?}");
+        assert!(
+            answers[4].starts_with("can't: there's no pin 5"),
+            "{answers:?}"
+        );
+        assert_eq!(answers[5], "A -- B -- C -- A -- D -- E -- A");
+        assert_eq!(written("pinned"), "A -- B -- C -- A -- E -- D -- A");
+        // unpinning E D leaves only the fixed stretch A-B-C. every change reworks the route it started with, which
+        // already walked that, so the route goes back to how it was
+        assert_eq!(written("unpinned"), "A -- B -- C -- A -- D -- E -- A");
+        // nothing after done is read
+        assert!(!out.join("late").exists());
+        fs::remove_dir_all(&out).unwrap();
+    }
+
+    #[test]
+    fn streamed_adjacency_lists_read_like_whole_ones() {
+        let map = "=Oak & 1st, 1:100:Oak St#4, 2:200\n0:100:Oak St#4, 2:150\n0:200, 1:150:Elm St\n";
+        let streamed = read_adjacency(map.as_bytes(), &[], &Unit::Meters)
+            .unwrap()
+            .unwrap();
+        let whole = build_graph_in(without_doors(map), &Unit::Meters).unwrap();
+        assert_eq!(edge_list(&streamed.graph), edge_list(&whole));
+        assert_eq!(streamed.labels, node_labels(map));
+        assert_eq!(streamed.names, street_names(map));
+        assert_eq!(streamed.doors, street_doors(map));
+        assert_eq!(streamed.doors[&(0, 1)], 8);
+        assert_eq!(streamed.names[&(1, 2)], "Elm St");
+        // an optional street is only followed by the whole file readers
+        let marked = "1:100*\n0:100*\n";
+        assert!(read_adjacency(marked.as_bytes(), &[], &Unit::Feet)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn parse_errors_add_up_with_their_line_numbers() {
+        // line 2 has a node that isn't a number, line 3 a weight that isn't one and line 5 a token with no weight
+        let map = "1:100\nx:100, 2:50\n1:50, 0:abc\n\n2\n";
+        let lines = |result: Result<(), PacsamError>| -> Vec<(usize, String)> {
+            match result {
+                Err(PacsamError::Many {
+                    errors,
+                    unlisted: 0,
+                }) => errors
+                    .into_iter()
+                    .map(|err| match err {
+                        PacsamError::Parse { line, token, .. } => (line, token),
+                        other => panic!("{other}"),
+                    })
+                    .collect(),
+                other => panic!("{:?}", other.err().map(|err| err.to_string())),
+            }
+        };
+        let expected = vec![
+            (2, "x".to_string()),
+            (3, "abc".to_string()),
+            (5, "2".to_string()),
+        ];
+        assert_eq!(lines(build_graph(map.to_string()).map(|_| ())), expected);
+        assert_eq!(
+            lines(read_adjacency(map.as_bytes(), &[], &Unit::Feet).map(|_| ())),
+            expected
+        );
+    }
+
+    #[test]
+    fn counting_streets_takes_one_long_street_over_two_short_ones() {
+        // 0 and 2 are odd. by distance they pair over 0-1-2, 2 feet, and by streets over the 10 foot 0-2
+        let network = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (0, 2, 1000),
+                (2, 3, 100),
+                (3, 0, 100),
+            ],
+            4,
+        );
+        let tree = fewest_streets_tree(&network, 0);
+        assert_eq!(tree[2], (STREET_COST + 1000, Some(0)));
+        assert_eq!(tree[1], (STREET_COST + 100, Some(0)));
+        let graph = copy_graph(&network);
+        let report = duplicate_paths_for(
+            &graph,
+            &network,
+            &[0, 2],
+            ObjectiveKind::MinRepeatedEdges,
+            &[],
+        );
+        assert_eq!(report.duplicated_edge_count, 1);
+        assert_eq!(report.duplicated_feet, 10.0);
+        let graph = copy_graph(&network);
+        let report =
+            duplicate_paths_for(&graph, &network, &[0, 2], ObjectiveKind::MinDistance, &[]);
+        assert_eq!(report.duplicated_edge_count, 2);
+        assert_eq!(report.duplicated_feet, 2.0);
+    }
+
+    #[test]
+    fn compacting_closes_index_gaps() {
+        // streets between 0, 1 and 5, with 2, 3 and 4 never mentioned
+        let mut graph = graph_with_nodes(vec![(0, 1, 100), (1, 5, 200), (5, 0, 300)], 6);
+        let new_to_old = compact(&mut graph);
+        assert_eq!(new_to_old, vec![0, 1, 5]);
+        assert_eq!(graph.node_count(), 3);
+        assert!((0..3).all(|node| graph.degree(node) == 2));
+        let restored: Vec<(usize, usize, usize)> = edge_list(&graph)
+            .into_iter()
+            .map(|(u, v, weight)| (new_to_old[u], new_to_old[v], weight))
+            .collect();
+        assert_eq!(restored, vec![(0, 1, 100), (0, 5, 300), (1, 5, 200)]);
+    }
+
+    #[test]
+    fn two_triangles_route_as_two_circuits() {
+        let graph = build_graph("1:100,2:100\n2:100\n\n4:100,5:100\n5:100\n".to_string()).unwrap();
+        let routes = routes_per_component(&graph).unwrap();
+        // each zone starts and ends at its lowest node, on its own node numbers mapped back to the file's
+        let paths: Vec<&[usize]> = routes.iter().map(|route| route.path.as_slice()).collect();
+        assert_eq!(paths, vec![[0, 2, 1, 0], [3, 5, 4, 3]]);
+        assert_eq!(
+            routes[1].edges,
+            vec![(3, 5, 10_000), (5, 4, 10_000), (4, 3, 10_000)]
+        );
+        assert!(routes
+            .iter()
+            .all(|route| route.total_feet == 300.0 && route.duplicated_edge_count == 0));
+    }
+
+    #[test]
+    fn eulerized_graphs_round_trip_through_the_colon_format() {
+        // 0 and 2 are odd, and 0-1-2 is the cheaper way between them than the diagonal, so both of its streets
+        // are written twice
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 200),
+                (2, 3, 300),
+                (3, 0, 400),
+                (0, 2, 500),
+            ],
+            4,
+        );
+        eulerize(&graph);
+        let dumped = to_colon_format(&graph);
+        assert_eq!(dumped, "1:1,1:1,2:5,3:4\n2:2,2:2\n3:3\n\n");
+        let reloaded = build_graph(dumped).unwrap();
+        assert!(odd_degree_nodes(&reloaded).is_empty());
+        assert!(same_network(&reloaded, &graph));
+    }
+
+    #[test]
+    fn neighbors_come_back_owned_and_sorted() {
+        // node 1 has three streets, two of them to 2, and 3 has none
+        let graph = graph_with_nodes(vec![(1, 2, 300), (0, 1, 100), (1, 2, 200)], 4);
+        assert_eq!(neighbors_vec(&graph, 1), vec![(0, 100), (2, 200), (2, 300)]);
+        assert_eq!(neighbors_vec(&graph, 3), vec![]);
+        assert_eq!(neighbors_vec(&graph, 9), vec![]);
+    }
+
+    fn route_summary(graph: &RoadGraph) -> RouteSummary {
+        let route = Solver::from_graph(copy_graph(graph)).route().unwrap();
+        RouteSummary {
+            base_feet: total_feet(graph),
+            total_feet: route.total_feet,
+            distinct_edges: graph.edge_count(),
+            total_traversals: route.path.len() - 1,
+            repeats: route.duplicated_edge_count,
+            doors: 0,
+            approach_feet: None,
+        }
+    }
+
+    #[test]
+    fn efficiency_is_100_only_without_backtracking() {
+        let triangle = graph_with_nodes(vec![(0, 1, 100), (1, 2, 100), (2, 0, 100)], 3);
+        assert_eq!(efficiency_score(&route_summary(&triangle)), 100.0);
+        // a single dead end street is walked out and back
+        let dead_end = graph_with_nodes(vec![(0, 1, 100)], 2);
+        assert_eq!(efficiency_score(&route_summary(&dead_end)), 50.0);
+    }
+
+    #[test]
+    fn svg_frames_trace_one_more_step_each() {
+        let path = vec![0, 1, 2, 0];
+        let coords = vec![(40.0, -75.0), (40.001, -75.0), (40.001, -74.999)];
+        let labels: Vec<String> = ["A", "B", "C"].iter().map(|l| l.to_string()).collect();
+        let frames = to_svg_frames(&path, &coords, &labels);
+        assert_eq!(frames.len(), path.len());
+        for frame in &frames {
+            assert!(frame.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+            assert!(frame.trim_end().ends_with("</svg>"));
+            assert_eq!(
+                frame.matches("<svg").count(),
+                frame.matches("</svg>").count()
+            );
+        }
+    }
+
+    #[test]
+    fn hybrid_labels_switch_past_the_threshold() {
+        let path = vec![0, 1, 27];
+        assert_eq!(
+            label_path(&path, 28, &LabelStyle::Hybrid(28)),
+            "A -- B -- AB"
+        );
+        assert_eq!(
+            label_path(&path, 29, &LabelStyle::Hybrid(28)),
+            "N0 -- N1 -- N27"
+        );
+    }
+
+    #[test]
+    fn doubled_weights_double_the_route() {
+        // a triangle with a dead end, so the route has deadheading to double as well
+        let mut graph =
+            graph_with_nodes(vec![(0, 1, 150), (1, 2, 275), (2, 0, 300), (0, 3, 425)], 4);
+        let before = Solver::from_graph(copy_graph(&graph)).route().unwrap();
+        map_weights(&mut graph, |weight| weight * 2);
+        let after = Solver::from_graph(copy_graph(&graph)).route().unwrap();
+        assert_eq!(before.total_feet, 15.75);
+        assert_eq!(after.total_feet, 2.0 * before.total_feet);
+        assert_eq!(after.path, before.path);
+    }
+
+    #[test]
+    fn reordered_inputs_are_the_same_network() {
+        // the same triangle, once declared from its lower ends and once from its upper ends in another order
+        let forward = build_graph("1:100,2:250\n2:300\n".to_string()).unwrap();
+        let backward = build_graph("\n0:100\n1:300,0:250\n".to_string()).unwrap();
+        assert!(same_network(&forward, &backward));
+        let heavier = build_graph("1:100,2:250\n2:301\n".to_string()).unwrap();
+        assert!(!same_network(&forward, &heavier));
+    }
+
+    #[test]
+    fn a_third_trip_down_a_street_breaks_the_traversal_limit() {
+        // the short street 0-1 is listed twice, and the cheapest way to pair up the odd nodes 0 and 1 is a third
+        // copy of it rather than going round by node 2
+        let map =
+            std::env::temp_dir().join(format!("pacsam-max-traversal-{}.csv", std::process::id()));
+        fs::write(&map, "1:1,1:1,2:100\n2:100\n").unwrap();
+        let args = [
+            "pacsam".to_string(),
+            map.display().to_string(),
+            "--max-traversal".to_string(),
+            "2".to_string(),
+        ];
+        let result = run(Config::build(args.into_iter()).unwrap());
+        fs::remove_file(&map).unwrap();
+        assert!(matches!(
+            result,
+            Err(PacsamError::TraversalLimitExceeded {
+                edge: (0, 1),
+                count: 3,
+                limit: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn swapped_nodes_take_their_streets_and_names() {
+        // a depot called Office at node 2 with a dead end off it, moved to node 0
+        let mut graph =
+            graph_with_nodes(vec![(0, 1, 100), (1, 2, 200), (2, 0, 300), (2, 3, 400)], 4);
+        let mut labels = BTreeMap::from([(2, "Office".to_string()), (3, "Cul".to_string())]);
+        swap_nodes(&mut graph, &mut labels, 0, 2);
+        let expected =
+            graph_with_nodes(vec![(2, 1, 100), (1, 0, 200), (0, 2, 300), (0, 3, 400)], 4);
+        assert!(same_network(&graph, &expected));
+        assert_eq!(
+            labels,
+            BTreeMap::from([(0, "Office".to_string()), (3, "Cul".to_string())])
+        );
+    }
+
+    #[test]
+    fn component_routes_match_routing_each_in_turn() {
+        // two triangles, a square with a dead end and a lone street. with the parallel feature the zones are
+        // routed on the thread pool, and have to come back the same as routing them one after another
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (3, 4, 200),
+                (4, 5, 200),
+                (5, 3, 200),
+                (6, 7, 100),
+                (7, 8, 100),
+                (8, 9, 100),
+                (9, 6, 100),
+                (9, 10, 50),
+                (11, 12, 75),
+            ],
+            13,
+        );
+        let serial: Vec<Vec<usize>> = connected_components(&graph)
+            .iter()
+            .map(|component| route_component(&graph, component).unwrap().path)
+            .collect();
+        let paths: Vec<Vec<usize>> = routes_per_component(&graph)
+            .unwrap()
+            .into_iter()
+            .map(|route| route.path)
+            .collect();
+        assert_eq!(paths, serial);
+        assert_eq!(serial.len(), 4);
+    }
+
+    #[test]
+    fn every_subcommand_runs_on_the_neighborhood() {
+        let converted =
+            std::env::temp_dir().join(format!("pacsam-convert-{}.csv", std::process::id()));
+        let run_with = |args: &[&str]| {
+            let args = ["pacsam"].iter().chain(args).map(|arg| arg.to_string());
+            run(Config::build(args).unwrap())
+        };
+        let routed = run_with(&["neighborhood.csv"]).unwrap();
+        assert_eq!(
+            run_with(&["route", "neighborhood.csv"]).unwrap().route.path,
+            routed.route.path
+        );
+        assert_eq!(routed.route.path.first(), routed.route.path.last());
+        assert_eq!(routed.route.path.len(), 41);
+        // the summary and then the route, with nothing to warn about
+        assert_eq!(routed.lines.len(), 2);
+        assert!(routed.lines[1].starts_with("A -- "));
+        assert!(routed.warnings.is_empty());
+        let analyzed = run_with(&["analyze", "neighborhood.csv"]).unwrap();
+        assert!(analyzed.route.path.is_empty());
+        assert_eq!(analyzed.lines[0], "intersections: 23");
+        assert_eq!(
+            run_with(&["validate", "neighborhood.csv"]).unwrap().lines,
+            vec!["ok"]
+        );
+        let output = converted.display().to_string();
+        let converting = run_with(&["convert", "neighborhood.csv", &output]).unwrap();
+        assert!(converting.route.path.is_empty() && converting.lines.is_empty());
+        let original = build_graph(fs::read_to_string("neighborhood.csv").unwrap()).unwrap();
+        let reread = build_graph(fs::read_to_string(&converted).unwrap()).unwrap();
+        fs::remove_file(&converted).unwrap();
+        assert!(same_network(&original, &reread));
+    }
+
+    #[test]
+    fn travel_times_are_read_as_seconds_and_summed() {
+        assert_eq!(parse_duration("0:01:30"), Some(90));
+        assert_eq!(parse_duration("1:30"), Some(90));
+        assert_eq!(parse_duration("0:61:00"), None);
+        let graph = build_graph_durations("1:0:01:30,2:0:02:00\n2:0:00:45\n").unwrap();
+        let route = Solver::from_graph(graph).route().unwrap();
+        assert_eq!(route.total_feet, 255.0);
+        assert_eq!(format_duration(route.total_feet as usize), "0:04:15");
+        assert!(matches!(
+            build_graph_durations("1:0:1x:30\n"),
+            Err(PacsamError::Parse { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn degree_parity_marks_the_odd_nodes() {
+        // a triangle with a dead end off 0 and a lone street 4-5: 0, 3, 4 and 5 have odd degree
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (0, 3, 100),
+                (4, 5, 100),
+            ],
+            7,
+        );
+        let parity = degree_parity(&graph);
+        assert_eq!(parity, vec![true, false, false, true, true, true, false]);
+        let marked: Vec<usize> = (0..parity.len()).filter(|node| parity[*node]).collect();
+        assert_eq!(marked, odd_degree_nodes(&graph));
+    }
+
+    #[test]
+    fn distance_matrix_agrees_with_shortest_paths() {
+        // a sparse ladder, which gets a dijkstra per row, plus a lone street that can't be reached from it
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 250),
+                (0, 3, 300),
+                (1, 4, 125),
+                (2, 5, 100),
+                (3, 4, 100),
+                (4, 5, 400),
+                (6, 7, 50),
+            ],
+            8,
+        );
+        let matrix = all_pairs_distances(&graph);
+        for (u, v) in [(0, 5), (3, 2), (5, 3), (0, 4)] {
+            let path = shortest_path(&dijkstra(&graph, u), v);
+            let walked: usize = hop_edges(&graph, &path)
+                .iter()
+                .map(|(_, _, weight)| weight)
+                .sum();
+            assert_eq!(matrix[u][v], walked);
+        }
+        assert_eq!(matrix[0][5], 450);
+        assert_eq!(matrix[0][7], usize::MAX);
+        for (u, row) in matrix.iter().enumerate() {
+            for (v, distance) in row.iter().enumerate() {
+                assert_eq!(*distance, matrix[v][u]);
+            }
+        }
+    }
+
+    #[test]
+    fn boundary_stubs_can_be_route_ends() {
+        // a square of 1 foot streets with a 5 foot stub off 0 that runs off the edge of the map, and a walker
+        // dropped off at the stub's far end who finishes at 0. as a cul de sac the stub is doubled up front, and the
+        // walk out of it needs a third copy. as an endpoint it's walked once
+        let map = std::env::temp_dir().join(format!("pacsam-boundary-{}.csv", std::process::id()));
+        fs::write(&map, "1:1,3:1,4:5\n2:1\n3:1\n").unwrap();
+        let graph = build_graph(fs::read_to_string(&map).unwrap()).unwrap();
+        let feet = |extra: &[&str]| {
+            let args = ["pacsam", map.to_str().unwrap(), "--open", "4", "0"]
+                .into_iter()
+                .chain(extra.iter().copied())
+                .map(|arg| arg.to_string());
+            let path = run(Config::build(args).unwrap()).unwrap().route.path;
+            hop_edges(&graph, &path)
+                .iter()
+                .map(|(_, _, weight)| weight)
+                .sum::<usize>()
+        };
+        let culdesac = feet(&[]);
+        let endpoint = feet(&["--no-culdesac"]);
+        fs::remove_file(&map).unwrap();
+        assert_eq!(culdesac, 1900);
+        assert_eq!(endpoint, 900);
+    }
+
+    #[test]
+    fn matchings_know_when_they_pair_every_odd_node() {
+        // a path 0-1-2-3 with a dead end 1-4: the odd nodes are 0, 1, 3 and 4
+        let graph = graph_with_nodes(vec![(0, 1, 100), (1, 2, 100), (2, 3, 100), (1, 4, 300)], 5);
+        let odd = odd_degree_nodes(&graph);
+        assert_eq!(odd, vec![0, 1, 3, 4]);
+        let distances = all_pairs_distances(&graph);
+        let pairs = matched_pairs(&graph);
+        let cost = pairs.iter().map(|(u, v)| distances[*u][*v]).sum();
+        let matching = Matching::new(pairs, cost);
+        assert!(matching.is_perfect(&odd));
+        // 0 with 4 through 1 and 1 with 3, or 0 with 1 and 3 with 4 through 2 and 1, both come to 600
+        assert_eq!(matching.cost(), 600);
+        let missing_one = Matching::new(vec![(0, 1)], 100);
+        assert!(!missing_one.is_perfect(&odd));
+        let twice_over = Matching::new(vec![(0, 1), (1, 3)], 300);
+        assert!(!twice_over.is_perfect(&odd));
+    }
+
+    #[test]
+    fn one_way_streets_force_the_long_way_back() {
+        // 0-1 is a 1 foot one-way street, so the walk back from 1 to 0 has to go round by 2. the two-way streets
+        // cost 5 feet walked away from 0 or 1 and 3 feet walked back, so the detour is 5 + 3 rather than 1
+        let graph = build_directed_graph_in("1:1:>,2:5/3\n2:5/3\n", &Unit::Feet).unwrap();
+        let report = balance_directed(&graph);
+        assert_eq!(report.duplicated_feet, 8.0);
+        assert_eq!(unbalanced_nodes(&graph), vec![]);
+        let back = find_directed_cycle(&graph, 0).unwrap();
+        assert_eq!(back.len(), 8);
+    }
+
+    #[test]
+    fn label_maps_have_to_name_every_node() {
+        let edges = [(0, 1, 100), (1, 2, 200), (2, 0, 300)];
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let labeled = with_label_map(&edges, names(&["Depot", "Oak", "Elm"])).unwrap();
+        assert_eq!(labeled.labels, vec!["Depot", "Oak", "Elm"]);
+        assert_eq!(labeled.graph.edge_count(), 3);
+        let Err(err) = with_label_map(&edges, names(&["Depot", "Oak"])) else {
+            panic!("two labels for three nodes were accepted");
+        };
+        assert_eq!(err.to_string(), "2 labels given for a graph with 3 nodes");
+        assert!(with_label_map(&edges, names(&["Depot", "Oak", "Elm", "Ash"])).is_err());
+    }
+
+    #[test]
+    fn adjacent_odd_nodes_repeat_only_the_street_between_them() {
+        // a square with a diagonal 1-3: 1 and 3 are the only odd nodes, and the diagonal is shorter than either
+        // way round the square
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 3, 100),
+                (3, 0, 100),
+                (1, 3, 150),
+            ],
+            4,
+        );
+        let report = eulerize(&graph);
+        assert_eq!(report.odd_node_count, 2);
+        assert_eq!(report.duplicated_edge_count, 1);
+        assert_eq!(report.duplicated_feet, 1.5);
+        let repeated: Vec<(usize, usize)> = traversal_counts(&graph)
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(street, _)| street)
+            .collect();
+        assert_eq!(repeated, vec![(1, 3)]);
+    }
+
+    #[test]
+    fn descriptions_count_intersections_and_streets() {
+        // a triangle of quarter mile streets with a quarter mile dead end off 0
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 132_000),
+                (1, 2, 132_000),
+                (2, 0, 132_000),
+                (0, 3, 132_000),
+            ],
+            4,
+        );
+        assert_eq!(
+            describe(&graph),
+            "4 intersections, 4 streets totaling 1 miles, 1 dead ends, 2 odd-degree junctions; \
+             an Euler circuit requires 0.25 extra miles of backtracking."
+        );
+    }
+
+    #[test]
+    fn checkpoints_are_rotated_to_the_middle() {
+        let circuit = vec![0, 1, 2, 3, 4, 5, 0];
+        let rotated = rotate_to_midpoint(&circuit, 0);
+        assert_eq!(rotated, vec![3, 4, 5, 0, 1, 2, 3]);
+        assert_eq!(rotated[rotated.len() / 2], 0);
+        // a checkpoint the route never passes leaves it alone
+        assert_eq!(rotate_to_midpoint(&circuit, 9), circuit);
+    }
+
+    #[test]
+    fn utilization_counts_the_doubled_street_twice() {
+        // a triangle with a dead end 0-3 that's walked out and back. node 2 has no coordinates, so its two streets
+        // are left out
+        let path = vec![0, 1, 2, 0, 3, 0];
+        let coords = vec![
+            Some((40.0, -75.0)),
+            Some((40.5, -75.0)),
+            None,
+            Some((40.0, -75.5)),
+        ];
+        assert_eq!(
+            utilization_csv(&path, &coords),
+            "from_lon,from_lat,to_lon,to_lat,count\n-75,40,-75,40.5,1\n-75,40,-75.5,40,2\n"
+        );
+    }
+
+    #[test]
+    fn trees_walk_every_street_out_and_back() {
+        let star = graph_with_nodes(vec![(0, 1, 100), (0, 2, 200), (0, 3, 300)], 4);
+        assert_eq!(tree_route(&star, 0), vec![0, 1, 0, 2, 0, 3, 0]);
+        let line = graph_with_nodes(vec![(0, 1, 100), (1, 2, 200), (2, 3, 300)], 4);
+        assert_eq!(tree_route(&line, 0), vec![0, 1, 2, 3, 2, 1, 0]);
+        for tree in [star, line] {
+            let route = Solver::from_graph(copy_graph(&tree)).route().unwrap();
+            assert_eq!(route.total_feet, 2.0 * total_feet(&tree));
+            let mut walks: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+            for hop in route.path.windows(2) {
+                *walks
+                    .entry((hop[0].min(hop[1]), hop[0].max(hop[1])))
+                    .or_insert(0) += 1;
+            }
+            assert_eq!(walks.len(), 3);
+            assert!(walks.values().all(|count| *count == 2));
+        }
+    }
+
+    #[test]
+    fn relabeled_graphs_print_their_new_labels() {
+        let mut labeled = with_label_map(
+            &[(0, 1, 100), (1, 2, 100), (2, 0, 100)],
+            vec!["A".to_string(), "B".to_string(), "C".to_string()],
+        )
+        .unwrap();
+        labeled.relabel_with(|node| format!("STOP-{node}"));
+        let route = find_cycle(&labeled.graph).unwrap();
+        assert_eq!(
+            labeled.path_text(&route),
+            "STOP-0 -- STOP-2 -- STOP-1 -- STOP-0"
+        );
+    }
+
+    #[test]
+    fn cycles_are_refused_on_graphs_that_arent_eulerized() {
+        // a triangle with a dead end off 0 leaves 0 and 3 odd
+        let odd = graph_with_nodes(vec![(0, 1, 100), (1, 2, 100), (2, 0, 100), (0, 3, 100)], 4);
+        let err = find_cycle(&odd).unwrap_err();
+        assert!(matches!(
+            &err,
+            PacsamError::NotEulerizable { odd_nodes, unreached: 0 } if *odd_nodes == vec![0, 3]
+        ));
+        assert_eq!(
+            err.to_string(),
+            "no euler circuit: nodes [0, 3] have odd degree, eulerize the graph first"
+        );
+        // every degree is even, but the second triangle can't be reached from the first
+        let apart = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (3, 4, 100),
+                (4, 5, 100),
+                (5, 3, 100),
+            ],
+            6,
+        );
+        let err = find_cycle(&apart).unwrap_err();
+        assert!(matches!(
+            &err,
+            PacsamError::NotEulerizable { odd_nodes, unreached: 3 } if odd_nodes.is_empty()
+        ));
+        assert_eq!(
+            err.to_string(),
+            "no euler circuit: 3 streets are unreachable from the start"
+        );
+        // and a start with no streets is a node the route can't be on
+        assert!(matches!(
+            find_cycle_from(&apart, 9),
+            Err(PacsamError::NoSuchNode(9))
+        ));
+    }
+
+    #[test]
+    fn directions_follow_a_translated_template() {
+        let graph = graph_with_nodes(vec![(0, 1, 25_000), (1, 2, 30_000), (2, 0, 45_000)], 3);
+        let labels: Vec<String> = ["A", "B", "C"].iter().map(|l| l.to_string()).collect();
+        let spanish = DirectionTemplate {
+            step: "Desde {from}, tome la calle hasta {to} ({feet} pies)".to_string(),
+            heading: ", rumbo {bearing}".to_string(),
+        };
+        assert_eq!(
+            directions(&[0, 1, 2, 0], &graph, &labels, &spanish),
+            vec![
+                "Desde A, tome la calle hasta B (250 pies)",
+                "Desde B, tome la calle hasta C (300 pies)",
+                "Desde C, tome la calle hasta A (450 pies)",
+            ]
+        );
+    }
+
+    #[test]
+    fn unlocated_nodes_only_lose_their_own_headings() {
+        // A is due south of B and D is due east of A. C has no coordinates, so only the hops touching it go without
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (0, 3, 100),
+                (3, 1, 100),
+            ],
+            4,
+        );
+        let labels: Vec<String> = ["A", "B", "C", "D"].iter().map(|l| l.to_string()).collect();
+        let coords = vec![
+            Some((40.0, -75.0)),
+            Some((40.01, -75.0)),
+            None,
+            Some((40.0, -74.99)),
+        ];
+        let steps = directions_with_bearings(
+            &[0, 1, 2, 0, 3],
+            &graph,
+            &labels,
+            &coords,
+            &DirectionTemplate::default(),
+        );
+        assert_eq!(
+            steps,
+            vec![
+                "From A, take the street to B (1 ft), heading N",
+                "From B, take the street to C (1 ft)",
+                "From C, take the street to A (1 ft)",
+                "From A, take the street to D (1 ft), heading E",
+            ]
+        );
+    }
+
+    #[test]
+    fn incident_edges_name_the_far_ends() {
+        // B is a degree 3 node, with streets to A, C and D
+        let labeled = with_label_map(
+            &[(0, 1, 100), (1, 2, 250), (3, 1, 75), (2, 3, 100)],
+            ["A", "B", "C", "D"].iter().map(|l| l.to_string()).collect(),
+        )
+        .unwrap();
+        let incident: Vec<(usize, String, usize)> = labeled
+            .edges_incident_to(1)
+            .into_iter()
+            .map(|edge| (edge.other, edge.other_label, edge.weight))
+            .collect();
+        assert_eq!(
+            incident,
+            vec![
+                (0, "A".to_string(), 100),
+                (2, "C".to_string(), 250),
+                (3, "D".to_string(), 75)
+            ]
+        );
+    }
+
+    #[test]
+    fn node_tours_go_round_the_ring() {
+        // a ring of six 1 foot streets. the best tour of 0, 3, 1 and 4 is once round it
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 3, 100),
+                (3, 4, 100),
+                (4, 5, 100),
+                (5, 0, 100),
+            ],
+            6,
+        );
+        let (tour, length) = node_tour(&graph, &[0, 3, 1, 4]);
+        assert_eq!(tour, vec![0, 1, 3, 4, 0]);
+        assert_eq!(length, 600);
+        assert_eq!(node_tour(&graph, &[]), (vec![], 0));
+    }
+
+    #[test]
+    fn json_warnings_parse_with_a_kind() {
+        let graph = graph_with_nodes(vec![(0, 0, 100), (0, 1, 0), (1, 2, 100), (2, 0, 100)], 4);
+        let warnings = input_warnings(&graph);
+        assert_eq!(warnings.len(), 3);
+        for warning in warnings {
+            let text = warning.to_json();
+            let json = parse_json(&text).unwrap();
+            assert!(json.field("kind").is_ok(), "{text}");
+        }
+    }
+
+    #[test]
+    fn trimming_keeps_the_biggest_piece() {
+        // a square on 0-3 and a lone street 4-5
+        let mut graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 3, 100),
+                (3, 0, 100),
+                (4, 5, 300),
+            ],
+            6,
+        );
+        assert_eq!(trim_to_largest_component(&mut graph), 2);
+        let square = graph_with_nodes(vec![(0, 1, 100), (1, 2, 100), (2, 3, 100), (3, 0, 100)], 4);
+        assert!(same_network(&graph, &square));
+        assert_eq!(graph.node_count(), 4);
+    }
+
+    #[test]
+    fn exact_totals_hold_past_u32() {
+        // 1000 laps of a triangle of 3,000,000 hundredths (30,000 ft) streets is 9 * 10^9 hundredths, past u32::MAX
+        let graph = graph_with_nodes(
+            vec![(0, 1, 3_000_000), (1, 2, 3_000_000), (2, 0, 3_000_000)],
+            3,
+        );
+        let mut path = vec![0];
+        for _ in 0..1000 {
+            path.extend([1, 2, 0]);
+        }
+        let total = total_feet_u128(&path, &graph);
+        assert_eq!(total, 9_000_000_000);
+        assert!(total > u32::MAX as u128);
+    }
+
+    #[test]
+    fn scores_choose_the_street_taken() {
+        // two triangles meeting at 0. scoring earlier candidates higher takes them in the default order, and
+        // scoring later ones higher walks the other triangle first
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (0, 3, 100),
+                (3, 4, 100),
+                (4, 0, 100),
+            ],
+            5,
+        );
+        let default = find_cycle_from(&graph, 0).unwrap();
+        let first = find_cycle_with(&graph, 0, |hop| -(hop.candidate as i64)).unwrap();
+        assert_eq!(first, default);
+        let last = find_cycle_with(&graph, 0, |hop| hop.candidate as i64).unwrap();
+        assert!(is_euler_circuit(&last, &graph));
+        assert_eq!(default, vec![0, 3, 4, 0, 2, 1, 0]);
+        assert_eq!(last, vec![0, 1, 2, 0, 3, 4, 0]);
+    }
+
+    #[test]
+    fn one_repeated_street_is_one_more_traversal() {
+        // a square with a short diagonal 1-3, which is walked twice
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 3, 100),
+                (3, 0, 100),
+                (1, 3, 150),
+            ],
+            4,
+        );
+        let summary = route_summary(&graph);
+        assert_eq!(summary.distinct_edges, 5);
+        assert_eq!(summary.total_traversals, 6);
+        assert_eq!(summary.repeats, 1);
+        assert!(summary
+            .describe_in(&Distance::Miles)
+            .ends_with("streets: 5 distinct, 6 traversals, 1 repeats"));
+    }
+
+    #[test]
+    fn stray_huge_indices_are_rejected() {
+        // two lines and three streets can name at most five nodes, so 9999 has to be a typo
+        assert!(check_indices("1:100,2:100\n2:100\n").is_ok());
+        assert!(matches!(
+            check_indices("1:100,2:100\n9999:100\n"),
+            Err(PacsamError::IndexOutOfRange {
+                line: 2,
+                index: 9999
+            })
+        ));
+    }
+
+    #[test]
+    fn components_are_yielded_one_at_a_time() {
+        // a triangle and a lone street, with an unused index between them
+        let graph = graph_with_nodes(vec![(0, 1, 100), (1, 2, 100), (2, 0, 100), (4, 5, 300)], 6);
+        let mut components = iter_components(&graph);
+        let triangle = components.next().unwrap();
+        assert!(same_network(
+            &triangle,
+            &graph_with_nodes(vec![(0, 1, 100), (1, 2, 100), (2, 0, 100)], 3)
+        ));
+        let street = components.next().unwrap();
+        assert!(same_network(
+            &street,
+            &graph_with_nodes(vec![(0, 1, 300)], 2)
+        ));
+        assert!(components.next().is_none());
+        assert_eq!(iter_components(&graph).count(), 2);
+    }
+
+    #[test]
+    fn deadhead_percentages_round_to_the_chosen_decimals() {
+        let summary = |base_feet: f64, total_feet: f64| RouteSummary {
+            base_feet,
+            total_feet,
+            distinct_edges: 0,
+            total_traversals: 0,
+            repeats: 0,
+            doors: 0,
+            approach_feet: None,
+        };
+        assert_eq!(
+            format_percent(deadhead_ratio(&summary(300.0, 300.0)), 1),
+            "0.0%"
+        );
+        // an empty graph has nothing to divide by
+        assert_eq!(
+            format_percent(deadhead_ratio(&summary(0.0, 0.0)), 1),
+            "0.0%"
+        );
+        // 100 of the 300 feet re-walked
+        let third = deadhead_ratio(&summary(200.0, 300.0));
+        assert_eq!(format_percent(third, 1), "33.3%");
+        assert_eq!(format_percent(third, 3), "33.333%");
+        assert_eq!(format_percent(third, 0), "33%");
+        assert_eq!(format_percent(f64::NAN, 1), "0.0%");
+    }
+
+    #[test]
+    fn paths_between_terminals_end_where_asked() {
+        // a square with a dead end off 0. dropped off at the dead end and picked up at 2, the only repeat is the
+        // shorter way from 0 to 2
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 3, 200),
+                (3, 0, 200),
+                (0, 4, 500),
+            ],
+            5,
+        );
+        let path = euler_path_between(&graph, 4, 2).unwrap();
+        assert_eq!(path.first(), Some(&4));
+        assert_eq!(path.last(), Some(&2));
+        assert_eq!(total_feet_u128(&path, &graph), 1300);
+        let mut walked: Vec<(usize, usize)> = path
+            .windows(2)
+            .map(|hop| (hop[0].min(hop[1]), hop[0].max(hop[1])))
+            .collect();
+        walked.sort();
+        assert_eq!(
+            walked,
+            vec![(0, 1), (0, 1), (0, 3), (0, 4), (1, 2), (1, 2), (2, 3)]
+        );
+        assert_eq!(graph.edge_count(), 5);
+        assert!(matches!(
+            euler_path_between(&graph, 4, 7),
+            Err(PacsamError::NoSuchNode(7))
+        ));
+    }
+
+    #[test]
+    fn four_odd_nodes_pair_up_across_the_short_streets() {
+        // every pair of 0-3 is joined, with two 1 foot streets 0-1 and 2-3 and the rest 10 feet. all four nodes
+        // are odd, and pairing across the short streets costs 2 feet against 20 for either other pairing
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (2, 3, 100),
+                (0, 2, 1000),
+                (1, 3, 1000),
+                (0, 3, 1000),
+                (1, 2, 1000),
+            ],
+            4,
+        );
+        let mut pairs: Vec<(usize, usize)> = matched_pairs(&graph)
+            .into_iter()
+            .map(|(u, v)| (u.min(v), u.max(v)))
+            .collect();
+        pairs.sort();
+        assert_eq!(pairs, vec![(0, 1), (2, 3)]);
+        let report = eulerize(&graph);
+        assert_eq!(report.odd_node_count, 4);
+        assert_eq!(report.duplicated_edge_count, 2);
+        assert_eq!(report.duplicated_feet, 2.0);
+        assert!(odd_degree_nodes(&graph).is_empty());
+    }
+
+    #[test]
+    fn shortest_paths_follow_the_predecessors() {
+        // A-B-C, with a direct A-C street that's longer than going through B
+        let graph = graph_with_nodes(vec![(0, 1, 100), (1, 2, 100), (0, 2, 500)], 3);
+        let path = shortest_path(&dijkstra(&graph, 0), 2);
+        assert_eq!(path, vec![0, 1, 2]);
+        assert_eq!(alphabetize(&path), "A -- B -- C");
+        // and nothing back for a node dijkstra never reached
+        let apart = graph_with_nodes(vec![(0, 1, 100), (2, 3, 100)], 4);
+        assert!(shortest_path(&dijkstra(&apart, 0), 3).is_empty());
+    }
+
+    #[test]
+    fn labels_run_on_past_z() {
+        assert_eq!(alphabetize(&[25]), "Z");
+        assert_eq!(alphabetize(&[26]), "AA");
+        assert_eq!(alphabetize(&[51]), "AZ");
+        assert_eq!(alphabetize(&[52]), "BA");
+        assert_eq!(alphabetize(&[0, 25, 26, 51]), "A -- Z -- AA -- AZ");
+    }
+
+    #[test]
+    fn heap_dijkstra_agrees_with_floyd_warshall() {
+        // the ladder from the distance matrix test, with its lone street. floyd-warshall doesn't share any code
+        // with the heap, so the two agreeing row by row means the heap settles every node at its true distance
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 250),
+                (0, 3, 300),
+                (1, 4, 125),
+                (2, 5, 100),
+                (3, 4, 100),
+                (4, 5, 400),
+                (6, 7, 50),
+            ],
+            8,
+        );
+        let floyd = all_pairs_shortest_paths(&graph);
+        for (u, row) in floyd.iter().enumerate() {
+            let mut heap = vec![usize::MAX; graph.node_count()];
+            for vertex in dijkstra(&graph, u) {
+                heap[vertex.idx] = vertex.distance_from_u;
+            }
+            assert_eq!(&heap, row);
+        }
+        let from_zero: Vec<usize> = dijkstra(&graph, 0)
+            .into_iter()
+            .filter(|vertex| vertex.distance_from_u != usize::MAX)
+            .map(|vertex| vertex.distance_from_u)
+            .collect();
+        assert_eq!(from_zero, vec![0, 100, 225, 300, 350, 450]);
+    }
+
+    #[test]
+    fn malformed_tokens_name_their_line() {
+        let weight = build_graph("1:100\n2:abc\n".to_string()).err().unwrap();
+        assert!(matches!(&weight, PacsamError::Parse { line: 2, token, .. } if token == "abc"));
+        assert!(weight
+            .to_string()
+            .starts_with("line 2, column 3: could not parse 'abc'"));
+        let colon = build_graph("1:100,2:100\n2100\n".to_string())
+            .err()
+            .unwrap();
+        assert!(matches!(&colon, PacsamError::Parse { line: 2, token, .. } if token == "2100"));
+        assert_eq!(
+            colon.to_string(),
+            "line 2, column 1: could not parse '2100', expected an edge as target:weight"
+        );
+        // a trailing comma and a blank line leave empty tokens, which are fine
+        let graph = build_graph("1:100,2:100,\n\n".to_string()).unwrap();
+        assert_eq!(edge_list(&graph), vec![(0, 1, 10_000), (0, 2, 10_000)]);
+    }
+
+    #[test]
+    fn corrupted_circuits_are_caught() {
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (0, 3, 100),
+                (3, 0, 100),
+            ],
+            4,
+        );
+        assert!(is_euler_circuit(&[0, 1, 2, 0, 3, 0], &graph));
+        // one of the two 0-3 streets left out
+        assert!(!is_euler_circuit(&[0, 1, 2, 0, 3], &graph));
+        // 1 and 3 aren't joined
+        assert!(!is_euler_circuit(&[0, 1, 3, 0, 2, 0], &graph));
+        // the right number of real streets, but 0-1 three times and 0-3 never
+        assert!(!is_euler_circuit(&[0, 1, 2, 0, 1, 0], &graph));
+    }
+
+    #[test]
+    fn circuits_from_another_start_are_rotations() {
+        // a ring round 0-4. from 0 and from 4 the lowest street leads the same way round, so starting at 4 gives the
+        // circuit from 0 rotated
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 3, 100),
+                (3, 4, 100),
+                (4, 0, 100),
+            ],
+            5,
+        );
+        let from_zero = find_cycle(&graph).unwrap();
+        let from_four = find_cycle_from(&graph, 4).unwrap();
+        assert_eq!(from_zero, vec![0, 4, 3, 2, 1, 0]);
+        assert_eq!(from_four, rotate_circuit(&from_zero, 1));
+        // a node with no streets has no circuit
+        let isolated = graph_with_nodes(vec![(0, 1, 100), (1, 2, 100), (2, 0, 100)], 4);
+        assert!(find_cycle_from(&isolated, 3).is_err());
+    }
+
+    #[test]
+    fn disjoint_triangles_are_refused_before_routing() {
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (3, 4, 100),
+                (4, 5, 100),
+                (5, 3, 100),
+            ],
+            6,
+        );
+        assert!(!is_connected(&graph));
+        assert_eq!(
+            connected_components(&graph),
+            vec![vec![0, 1, 2], vec![3, 4, 5]]
+        );
+        let map = std::env::temp_dir().join(format!("pacsam-disjoint-{}.csv", std::process::id()));
+        fs::write(&map, to_colon_format(&graph)).unwrap();
+        let args = ["pacsam".to_string(), map.display().to_string()];
+        let result = run(Config::build(args.into_iter()).unwrap());
+        fs::remove_file(&map).unwrap();
+        let err = result.unwrap_err();
+        assert!(matches!(&err, PacsamError::Disconnected(pieces) if pieces.len() == 2));
+        assert!(err
+            .to_string()
+            .starts_with("graph is not connected, it splits into 2 pieces: {0, 1, 2}, {3, 4, 5}"));
+    }
+
+    #[test]
+    fn the_added_mileage_is_the_one_repeated_street() {
+        // a square of quarter mile streets with a 2640 foot diagonal 1-3, the only street eulerize has to repeat
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 132_000),
+                (1, 2, 132_000),
+                (2, 3, 132_000),
+                (3, 0, 132_000),
+                (1, 3, 264_000),
+            ],
+            4,
+        );
+        let report = eulerize(&graph);
+        assert_eq!(report.duplicated_edge_count, 1);
+        assert_eq!(report.duplicated_feet, 2640.0);
+        assert_eq!(deadhead_miles(report.duplicated_feet), 0.5);
+    }
+
+    #[test]
+    fn kml_puts_longitude_first_inside_one_placemark() {
+        let coords = vec![(40.1, -75.2), (40.3, -75.4)];
+        let kml = to_kml(&[0, 1, 0], &coords);
+        assert!(kml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<kml xmlns=\"http://www.opengis.net/kml/2.2\">"));
+        assert!(kml.contains("<coordinates>-75.2,40.1,0 -75.4,40.3,0 -75.2,40.1,0</coordinates>"));
+        assert_eq!(kml.matches("<Placemark>").count(), 1);
+        // each element opens inside the one before it and closes in the reverse order
+        let order = [
+            "<kml",
+            "<Placemark>",
+            "<LineString>",
+            "<coordinates>",
+            "</coordinates>",
+            "</LineString>",
+            "</Placemark>",
+            "</kml>",
+        ];
+        let positions: Vec<usize> = order.iter().map(|tag| kml.find(tag).unwrap()).collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(kml.contains("<name>A -- B -- A</name>"));
+    }
+
+    #[test]
+    fn lengths_convert_and_truncate() {
+        // a triangle of 5280, 2640 and 2640, which is 2 miles round in feet and 10.56 km round in meters
+        let graph = graph_with_nodes(vec![(0, 1, 528_000), (1, 2, 264_000), (2, 0, 264_000)], 3);
+        let path = vec![0, 1, 2, 0];
+        assert_eq!(length(&path, &graph, &Unit::Feet, &Distance::Miles), 2.0);
+        assert_eq!(length(&[0, 1], &graph, &Unit::Feet, &Distance::Miles), 1.0);
+        assert_eq!(
+            length(&path, &graph, &Unit::Meters, &Distance::Kilometers),
+            10.56
+        );
+        // 2640 feet is 0.804672 km, cut to 0.8 rather than rounded
+        assert_eq!(
+            length(&[1, 2], &graph, &Unit::Feet, &Distance::Kilometers),
+            0.8
+        );
+        // 7920 feet is exactly 1.5 miles, and 2640 meters 1.6404 miles
+        assert_eq!(
+            length(&[0, 1, 2], &graph, &Unit::Feet, &Distance::Miles),
+            1.5
+        );
+        assert_eq!(
+            length(&[1, 2], &graph, &Unit::Meters, &Distance::Miles),
+            1.64
+        );
+    }
+
+    #[test]
+    fn fractional_feet_add_up_exactly() {
+        // 0.1 and 0.2 famously don't add to 0.3 in floating point, but they do in stored hundredths
+        let graph = build_graph("1:10.25,2:0.1\n2:0.2\n".to_string()).unwrap();
+        assert_eq!(
+            edge_list(&graph),
+            vec![(0, 1, 1025), (0, 2, 10), (1, 2, 20)]
+        );
+        let path = vec![0, 1, 2, 0];
+        assert_eq!(total_feet_u128(&path, &graph), 1055);
+        assert_eq!(total_feet_u128(&[1, 2, 0], &graph), 30);
+        assert_eq!(format_weight(30), "0.3");
+        let route = Solver::from_graph(graph).route().unwrap();
+        assert_eq!(route.total_feet, 10.55);
+    }
+
+    #[test]
+    fn odd_node_rows_line_up_with_their_nodes() {
+        // with the parallel feature the rows are built on the thread pool, and each has to stay with its own node.
+        // the nodes are given out of order so a row that slipped would show
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 250),
+                (0, 3, 300),
+                (1, 4, 125),
+                (2, 5, 100),
+                (3, 4, 100),
+                (4, 5, 400),
+                (6, 7, 50),
+            ],
+            8,
+        );
+        let nodes = [5, 0, 7, 3, 1];
+        let distance = odd_node_distances(&graph, &nodes);
+        for u in nodes {
+            let tree = dijkstra(&graph, u);
+            for v in nodes {
+                let sequential = tree
+                    .iter()
+                    .find(|vertex| vertex.idx == v)
+                    .unwrap()
+                    .distance_from_u;
+                assert_eq!(distance(u, v), wide_distance(sequential), "{u} to {v}");
+            }
+        }
+        assert_eq!(distance(5, 0), 450);
+        assert_eq!(distance(0, 7), u64::MAX);
+    }
+
+    #[test]
+    fn unnamed_streets_fall_back_to_their_intersections() {
+        // 0-1-2 is all Oak St, and 2-0 has no name
+        let graph = graph_with_nodes(vec![(0, 1, 10_000), (1, 2, 15_000), (2, 0, 20_000)], 3);
+        let names = BTreeMap::from([
+            ((0, 1), "Oak St".to_string()),
+            ((1, 2), "Oak St".to_string()),
+        ]);
+        let steps = turn_by_turn(
+            &[0, 1, 2, 0],
+            &graph,
+            &names,
+            &BTreeMap::new(),
+            &[],
+            &[],
+            false,
+        );
+        assert_eq!(
+            steps,
+            vec![
+                "Walk Oak St from A to C (0.04 mi, 0.04 mi so far)",
+                "Walk from C to A (0.03 mi, 0.08 mi so far)",
+            ]
+        );
+    }
+
+    #[test]
+    fn dead_ends_get_one_return_street_each() {
+        // a lone street A-B is both ends' only street, and one copy makes both ends even
+        let lone = graph_with_nodes(vec![(0, 1, 100)], 2);
+        fix_culdesacs(&lone, &BoundaryPolicy::TreatAsCuldesac);
+        assert_eq!(lone.edge_count(), 2);
+        assert_eq!(edge_list(&lone), vec![(0, 1, 100), (0, 1, 100)]);
+        assert!(odd_degree_nodes(&lone).is_empty());
+        // two dead ends off the same corner of a triangle each get their own
+        let shared = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (0, 3, 50),
+                (0, 4, 75),
+            ],
+            5,
+        );
+        fix_culdesacs(&shared, &BoundaryPolicy::TreatAsCuldesac);
+        assert_eq!(shared.edge_count(), 7);
+        assert_eq!(
+            traversal_counts(&shared)
+                .iter()
+                .filter(|(_, count)| *count == 2)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn reports_count_the_odd_nodes_and_the_repeat() {
+        // a house: the square 0-1-2-3 with a roof 1-4-2. the roof's eaves 1 and 2 are odd, and the ceiling 1-2 is
+        // the one street walked again
+        let edges = vec![
+            (0, 1, 300),
+            (1, 2, 200),
+            (2, 3, 300),
+            (3, 0, 200),
+            (1, 4, 150),
+            (4, 2, 150),
+        ];
+        let graph = graph_with_nodes(edges.clone(), 5);
+        let report = eulerize(&graph);
+        assert_eq!(report.odd_node_count, 2);
+        assert_eq!(report.duplicated_edge_count, 1);
+        assert_eq!(report.duplicated_feet, 2.0);
+        let route = Solver::from_graph(graph_with_nodes(edges, 5))
+            .route()
+            .unwrap();
+        assert_eq!(route.duplicated_edge_count, 1);
+        assert_eq!(route.repeats.iter().filter(|repeat| **repeat).count(), 1);
+    }
+
+    #[test]
+    fn both_formats_read_the_same_graph() {
+        let adjacency = build_graph("1:100,2:250\n2:300\n".to_string()).unwrap();
+        let numbered = build_graph_edge_list("0 1 100\n0 2 250\n1 2 300\n".to_string()).unwrap();
+        assert!(same_network(&adjacency, &numbered));
+        // named intersections are numbered in the order they first show up
+        let named =
+            read_edge_list("Oak Elm 100\nOak Ash 250\n# a comment\nElm Ash 300\n".as_bytes())
+                .unwrap();
+        assert!(same_network(&adjacency, &named.graph));
+        assert_eq!(named.labels[&0], "Oak");
+        assert_eq!(named.labels[&2], "Ash");
+    }
+
+    #[test]
+    fn empty_and_single_node_maps_route_to_nothing() {
+        // a blank file and a lone blank line both build a graph of just node 0, which the route leaves out
+        for contents in ["", "\n"] {
+            let route = Solver::from_contents(contents).unwrap().route().unwrap();
+            assert!(route.path.is_empty());
+            assert_eq!((route.total_feet, route.total_miles), (0.0, 0.0));
+        }
+        // a circuit asked for on a single node with no streets is just standing there
+        let single = graph_with_nodes(vec![], 1);
+        assert_eq!(find_cycle(&single).unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn ties_settle_the_same_way_every_time() {
+        // round the square from 0 to 2 is 2 feet either way. the tree goes by way of 1, the lower index, however
+        // the graph's neighbor lists came out when it was built
+        let trees: Vec<Vec<usize>> = (0..20)
+            .map(|_| {
+                let graph =
+                    graph_with_nodes(vec![(2, 3, 100), (3, 0, 100), (0, 1, 100), (1, 2, 100)], 4);
+                shortest_path(&dijkstra(&graph, 0), 2)
+            })
+            .collect();
+        assert!(trees.iter().all(|path| *path == vec![0, 1, 2]));
+        // and eulerize repeats the same streets. 0 and 2 are the odd nodes once 0-4-2 joins them too, and all three
+        // ways between them are 2 feet
+        let repeated: Vec<Vec<(usize, usize)>> = (0..20)
+            .map(|_| {
+                let graph = graph_with_nodes(
+                    vec![
+                        (0, 1, 100),
+                        (1, 2, 100),
+                        (2, 3, 100),
+                        (3, 0, 100),
+                        (0, 4, 100),
+                        (4, 2, 100),
+                    ],
+                    5,
+                );
+                eulerize(&graph);
+                traversal_counts(&graph)
+                    .into_iter()
+                    .filter(|(_, count)| *count > 1)
+                    .map(|(street, _)| street)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(repeated[0], vec![(0, 1), (1, 2)]);
+        assert!(repeated.iter().all(|edges| *edges == repeated[0]));
+    }
+
+    #[test]
+    fn matched_pairs_cover_each_odd_node_once() {
+        let graph = build_graph(fs::read_to_string("neighborhood.csv").unwrap()).unwrap();
+        fix_culdesacs(&graph, &BoundaryPolicy::TreatAsCuldesac);
+        let odd = odd_degree_nodes(&graph);
+        assert!(odd.len() >= 4);
+        let pairs = matched_pairs(&graph);
+        assert_eq!(pairs.len() * 2, odd.len());
+        let mut paired: Vec<usize> = pairs.iter().flat_map(|(u, v)| [*u, *v]).collect();
+        paired.sort();
+        assert_eq!(paired, odd);
+    }
+
+    #[test]
+    fn results_round_trip_through_a_file() {
+        let graph = graph_with_nodes(vec![(0, 1, 264_000), (1, 2, 264_000), (2, 0, 528_000)], 3);
+        let out = std::env::temp_dir().join(format!("pacsam-result-{}.txt", std::process::id()));
+        let out_path = out.to_str().unwrap();
+        // something longer already there has to be replaced, not written over the start of
+        fs::write(
+            &out,
+            "an older and much longer route\n9.99 mi\nand a third line\n",
+        )
+        .unwrap();
+        write_result(
+            &[0, 1, 2, 0],
+            &graph,
+            &BTreeMap::new(),
+            out_path,
+            &Distance::Miles,
+        )
+        .unwrap();
+        let written = fs::read_to_string(&out).unwrap();
+        fs::remove_file(&out).unwrap();
+        assert_eq!(written, "A -- B -- C -- A\n2 mi\n");
+    }
+
+    #[test]
+    fn a_cap_splits_the_circuit_in_two() {
+        // two triangles of 100 foot streets meeting at the depot 0. a 300 foot cap fits exactly one triangle, and
+        // each shift ends back at the depot, so nothing is added to get home
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 10_000),
+                (1, 2, 10_000),
+                (2, 0, 10_000),
+                (0, 3, 10_000),
+                (3, 4, 10_000),
+                (4, 0, 10_000),
+            ],
+            5,
+        );
+        let (routes, deadhead) = split_routes(&graph, 300);
+        assert_eq!(routes.len(), 2);
+        assert!(routes
+            .iter()
+            .all(|route| route.first() == Some(&0) && route.last() == Some(&0)));
+        assert!(routes
+            .iter()
+            .all(|route| length(route, &graph, &Unit::Feet, &Distance::Miles) <= 0.06));
+        assert_eq!(deadhead, 0.0);
+        // a cap that's a street short of a triangle has to send shifts back from partway round
+        let (routes, deadhead) = split_routes(&graph, 200);
+        assert!(routes.len() > 2);
+        assert!(deadhead > 0.0);
+    }
+
+    #[test]
+    fn cycles_on_a_fixed_graph_stay_the_same() {
+        // two triangles at 0, a doubled street 3-5 and a loop at 4: the circuit pinned here is the one the
+        // edge-scanning version found, so a change to the bookkeeping that alters the walk shows up
+        let graph = graph_with_nodes(
+            vec![
+                (0, 1, 100),
+                (1, 2, 100),
+                (2, 0, 100),
+                (0, 3, 100),
+                (3, 4, 100),
+                (4, 0, 100),
+                (3, 5, 100),
+                (5, 3, 100),
+                (4, 4, 100),
+            ],
+            6,
+        );
+        let circuit = find_cycle(&graph).unwrap();
+        assert_eq!(circuit, vec![0, 4, 4, 3, 5, 3, 0, 2, 1, 0]);
+        assert!(is_euler_circuit(&circuit, &graph));
+    }
+
+    #[test]
+    fn one_way_loops_balance_and_shortcuts_dont() {
+        // 0 -> 1 -> 2 -> 0, every street one way
+        let loop_map = "1:100:>\n2:100:>\n0:100:>\n";
+        let graph = build_directed_graph_in(loop_map, &Unit::Feet).unwrap();
+        assert_eq!(graph.edge_count(), 3);
+        assert!(unbalanced_nodes(&graph).is_empty());
+        assert_eq!(find_directed_cycle(&graph, 0).unwrap(), vec![0, 1, 2, 0]);
+        // a one-way shortcut 0 -> 2 leaves 0 with a spare way out and 2 with a spare way in
+        let shortcut =
+            build_directed_graph_in("1:100:>,2:100:>\n2:100:>\n0:100:>\n", &Unit::Feet).unwrap();
+        assert_eq!(unbalanced_nodes(&shortcut), vec![(0, 1), (2, -1)]);
+    }
+
+    #[test]
+    fn optional_streets_are_only_walked_when_they_help() {
+        // a square of 1 foot streets with a 1.5 foot diagonal 0-2. required, the diagonal makes 0 and 2 odd and
+        // gets walked twice. marked optional, the square is a circuit on its own and the diagonal is left alone
+        let feet = |map: &str| {
+            let file = std::env::temp_dir().join(format!(
+                "pacsam-optional-{}-{}.csv",
+                std::process::id(),
+                map.len()
+            ));
+            fs::write(&file, map).unwrap();
+            let args = ["pacsam".to_string(), file.display().to_string()];
+            let path = run(Config::build(args.into_iter()).unwrap())
+                .unwrap()
+                .route
+                .path;
+            fs::remove_file(&file).unwrap();
+            let graph = build_graph(map.replace('*', "")).unwrap();
+            hop_edges(&graph, &path)
+                .iter()
+                .map(|(_, _, weight)| weight)
+                .sum::<usize>()
+        };
+        let required = feet("1:1,3:1,2:1.5\n2:1\n3:1\n");
+        let optional = feet("1:1,3:1,2:1.5*\n2:1\n3:1\n");
+        assert_eq!(required, 700);
+        assert_eq!(optional, 400);
+    }
+
+    #[test]
+    fn file_paths_from_the_command_line_are_trimmed() {
+        let args = ["pacsam", " neighborhood.csv\r"]
+            .iter()
+            .map(|arg| arg.to_string());
+        assert_eq!(Config::build(args).unwrap().file_path, "neighborhood.csv");
+        // with no path main asks for one
+        let args = ["pacsam"].iter().map(|arg| arg.to_string());
+        assert_eq!(Config::build(args).unwrap().file_path, "");
+    }
+
+    #[test]
+    fn errors_can_be_matched_on() {
+        // a missing file comes back as the io error it was, through the From impl
+        let missing = solve("no such map.csv").err().unwrap();
+        assert!(
+            matches!(&missing, PacsamError::Io(err) if err.kind() == std::io::ErrorKind::NotFound)
+        );
+        let boxed: Box<dyn Error> = Box::new(PacsamError::NoSuchNode(7));
+        assert!(boxed.downcast_ref::<PacsamError>().is_some());
+    }
+
+    #[test]
+    fn reported_lengths_follow_the_units() {
+        // the same two mile triangle, written and described in kilometers
+        let graph = graph_with_nodes(vec![(0, 1, 264_000), (1, 2, 264_000), (2, 0, 528_000)], 3);
+        let out = std::env::temp_dir().join(format!("pacsam-result-km-{}.txt", std::process::id()));
+        let out_path = out.to_str().unwrap();
+        write_result(
+            &[0, 1, 2, 0],
+            &graph,
+            &BTreeMap::new(),
+            out_path,
+            &Distance::Kilometers,
+        )
+        .unwrap();
+        let written = fs::read_to_string(&out).unwrap();
+        fs::remove_file(&out).unwrap();
+        assert_eq!(written, "A -- B -- C -- A\n3.21 km\n");
+        // and the length line is skipped when the file is read back as a walk
+        assert_eq!(
+            parse_walk(&written, &BTreeMap::new()).unwrap(),
+            vec![0, 1, 2, 0]
+        );
+        let stats = RouteStats {
+            street_feet: 5280.0,
+            route_feet: 10560.0,
+            deadhead_feet: 5280.0,
+            duplicated: vec![(0, 1, 528_000)],
+            odd_nodes_before: 2,
+            odd_nodes_after: 0,
+            lower_bound_feet: 10560.0,
+            bound_is_exact: true,
+        };
+        let described = stats.describe(&BTreeMap::new(), &Distance::Kilometers);
+        let lines: Vec<&str> = described.lines().collect();
+        assert_eq!(lines[0], "street length: 1.6 km");
+        assert_eq!(lines[1], "route length: 3.21 km");
+        assert!(lines[2].starts_with("deadheading: 1.6 km ("), "{described}");
+        assert!(
+            lines[4].starts_with("lower bound: 3.21 km ("),
+            "{described}"
+        );
+        assert_eq!(lines[6], "  A -- B (5280 ft)");
+    }
+
+    #[test]
+    fn open_ends_are_named_like_the_start() {
+        // the square with a stub off 0 again, dropped off at the stub's end E and picked up at A
+        let map = std::env::temp_dir().join(format!("pacsam-open-ends-{}.csv", std::process::id()));
+        fs::write(&map, "1:1,3:1,4:5\n2:1\n3:1\n").unwrap();
+        let route = |start: &str, end: &str| {
+            let args = [
+                "pacsam",
+                map.to_str().unwrap(),
+                "--open",
+                start,
+                end,
+                "--no-culdesac",
+            ]
+            .into_iter()
+            .map(|arg| arg.to_string());
+            run(Config::build(args).unwrap())
+        };
+        let lettered = route("E", "A").unwrap().route.path;
+        let numbered = route("4", "0").unwrap().route.path;
+        let unknown = route("E", "Main St");
+        fs::remove_file(&map).unwrap();
+        assert_eq!(lettered, numbered);
+        assert_eq!((lettered[0], lettered[lettered.len() - 1]), (4, 0));
+        assert!(matches!(unknown, Err(PacsamError::UnknownNode(name)) if name == "Main St"));
+    }
+
+    #[test]
+    fn settings_files_read_as_options() {
+        let settings = "# the office's defaults\n\
+                        units = \"km\"   # trailing comments too\n\
+                        walkers = 2\n\
+                        door-minutes = 1_000\n\
+                        both_sides = true\n\
+                        heatmap = false\n\
+                        start = 'A \\ B'\n\
+                        avoid_node = [\"Q\", 17,\n  \"R\", # spread over lines\n]\n\
+                        avoid_street = [[\"A\", \"B\"], [\"C\", \"D\"]]\n";
+        let args: Vec<&str> = [
+            "--units",
+            "km",
+            "--walkers",
+            "2",
+            "--door-minutes",
+            "1000",
+            "--both-sides",
+            "--start",
+            "A \\ B",
+            "--avoid-node",
+            "Q",
+            "--avoid-node",
+            "17",
+            "--avoid-node",
+            "R",
+            "--avoid-street",
+            "A",
+            "B",
+            "--avoid-street",
+            "C",
+            "D",
+        ]
+        .into();
+        assert_eq!(settings_args(settings).unwrap(), args);
+        assert_eq!(
+            settings_args("# nothing but a comment\n\n").unwrap(),
+            Vec::<String>::new()
+        );
+        // problems name the line they're on
+        for (settings, error) in [
+            (
+                "[route]\nunits = \"km\"\n",
+                "line 1: sections aren't supported, every setting goes at the top level",
+            ),
+            (
+                "units = \"km\"\nstart \"A\"\n",
+                "line 2: expected = after start",
+            ),
+            ("start = \"A\n", "line 1: a string isn't closed"),
+            ("start = \"\\q\"\n", "line 1: unknown escape in a string"),
+            (
+                "start = A\n",
+                "line 1: expected a string, a number, true, false or a list",
+            ),
+            (
+                "avoid_node = [\"Q\" \"R\"]\n",
+                "line 1: expected , or ] in a list",
+            ),
+            (
+                "avoid_node = [true]\n",
+                "line 1: avoid_node can't list true or false",
+            ),
+            (
+                "avoid_street = [[[\"A\"]]]\n",
+                "line 1: avoid_street can only list strings and numbers",
+            ),
+            (
+                "walkers = 2 3\n",
+                "line 1: expected the end of the line after walkers",
+            ),
+            (
+                "config = \"other.toml\"\n",
+                "line 1: config can't be set from a settings file",
+            ),
+            ("= 2\n", "line 1: expected a setting name"),
+        ] {
+            assert_eq!(settings_args(settings).unwrap_err(), error, "{settings}");
+        }
+    }
+
+    #[test]
+    fn settings_switches_can_be_turned_off() {
+        // switches a settings file turns on, turned back off for one run by their opposites
+        let settings =
+            std::env::temp_dir().join(format!("pacsam-settings-{}.toml", std::process::id()));
+        fs::write(
+            &settings,
+            "both_sides = true\nheatmap = true\nno_culdesac = true\n",
+        )
+        .unwrap();
+        let config = |extra: &[&str]| {
+            let args = ["pacsam", "map.csv", "--config", settings.to_str().unwrap()]
+                .into_iter()
+                .chain(extra.iter().copied())
+                .map(|arg| arg.to_string())
+                .collect();
+            Config::build(with_settings(args).unwrap().into_iter()).unwrap()
+        };
+        let defaults = config(&[]);
+        let overridden = config(&["--no-both-sides", "--culdesac"]);
+        fs::remove_file(&settings).unwrap();
+        assert!(defaults.both_sides && defaults.heatmap);
+        assert!(matches!(
+            defaults.boundary_policy,
+            BoundaryPolicy::TreatAsEndpoint
+        ));
+        assert!(!overridden.both_sides && overridden.heatmap);
+        assert!(matches!(
+            overridden.boundary_policy,
+            BoundaryPolicy::TreatAsCuldesac
+        ));
+    }
+
+    #[test]
+    fn symmetrize_puts_back_the_missing_directions() {
+        // a triangle where 0-1 and 1-2 were marked one-way, two parallel 2-0 streets with one of them one-way, and
+        // a one-way loop at 1, which goes round the same whichever way it's marked
+        let graph =
+            build_directed_graph_in("1:100:>,2:300\n2:200:>,1:50:>\n0:400:>\n", &Unit::Feet)
+                .unwrap();
+        assert_eq!(graph.edge_count(), 6);
+        assert_eq!(symmetrize(&graph), 3);
+        assert_eq!(
+            arc_list(&graph),
+            vec![
+                (0, 1, 10_000),
+                (0, 2, 30_000),
+                (0, 2, 40_000),
+                (1, 0, 10_000),
+                (1, 1, 5_000),
+                (1, 2, 20_000),
+                (2, 0, 30_000),
+                (2, 0, 40_000),
+                (2, 1, 20_000),
+            ]
+        );
+        // everything comes back now, so there's nothing left to add
+        assert_eq!(unbalanced_nodes(&graph), vec![]);
+        assert_eq!(symmetrize(&graph), 0);
+    }
+
+    #[test]
+    fn per_component_routes_refuse_a_single_start_or_output() {
+        let build = |extra: &[&str]| {
+            let args = ["pacsam", "map.csv", "--per-component"]
+                .into_iter()
+                .chain(extra.iter().copied())
+                .map(|arg| arg.to_string());
+            Config::build(args).err()
+        };
+        assert_eq!(build(&[]), None);
+        assert_eq!(build(&["--format", "json"]), None);
+        assert_eq!(
+            build(&["--start", "B"]).as_deref(),
+            Some("--start can't be combined with --per-component")
+        );
+        assert_eq!(
+            build(&["--open", "A", "C"]).as_deref(),
+            Some("--open can't be combined with --per-component")
+        );
+        assert_eq!(
+            build(&["out.txt"]).as_deref(),
+            Some("an OUTPUT path can't be combined with --per-component")
+        );
+    }
+
+    #[test]
+    fn solve_all_routes_one_way_maps_like_the_route_command() {
+        // a one-way loop has only the one way round, where Solver would have walked it either way
+        let dir = std::env::temp_dir().join(format!("pacsam-solve-all-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("loop.csv"), "2:100:>\n0:100:>\n1:100:>\n").unwrap();
+        fs::write(dir.join("spur.csv"), "1:100,2:50\n").unwrap();
+        let args = ["pacsam", "solve-all", dir.to_str().unwrap()].map(String::from);
+        let report = run(Config::build(args.into_iter()).unwrap()).unwrap();
+        let written = fs::read_to_string(dir.join("loop.route.txt")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(written, "A -- C -- B -- A\n0.05 mi\n");
+        assert_eq!(report.lines.len(), 4);
+        assert!(
+            report.lines[1].starts_with("loop.csv"),
+            "{:?}",
+            report.lines
+        );
+        assert!(report.lines[1].ends_with(" 0"), "{:?}", report.lines);
+        let routes = solve_all([(
+            "loop.csv".to_string(),
+            "2:100:>\n0:100:>\n1:100:>\n".to_string(),
+        )]);
+        assert_eq!(routes[0].1.as_ref().unwrap().path, [0, 2, 1, 0]);
+    }
 }
@@ -1,13 +1,42 @@
-use std::{io, process};
+use pacsam_optimization::{with_settings, Config, Report, USAGE};
+use std::{env, io, process};
 
 fn main() {
-    println!("File Path >");
-    let mut file_path = String::new();
-    io::stdin()
-        .read_line(&mut file_path)
-        .expect("could not parse file path");
-    file_path = file_path.trim().into();
-    if let Err(e) = pacsam_optimization::run(file_path) {
+    if env::args().any(|arg| arg == "-h" || arg == "--help") {
+        print!("{USAGE}");
+        return;
+    }
+    let mut config = with_settings(env::args().collect())
+        .and_then(|args| Config::build(args.into_iter()))
+        .unwrap_or_else(|err| {
+            eprintln!("Problem parsing arguments: {err}");
+            process::exit(1);
+        });
+    pacsam_optimization::init_logging(config.verbosity);
+    if config.file_path.is_empty() {
+        println!("File Path >");
+        let mut file_path = String::new();
+        io::stdin()
+            .read_line(&mut file_path)
+            .expect("could not parse file path");
+        config.file_path = file_path.trim().into();
+        // stdin closed straight away (or just a blank line), so there's nothing to read
+        if config.file_path.is_empty() {
+            eprintln!("Problem: no input file provided");
+            process::exit(1);
+        }
+    }
+    // the library hands back what the command has to say, and whatever it had gathered still gets shown when it
+    // fails partway, ahead of the problem itself
+    let mut report = Report::default();
+    let result = pacsam_optimization::run_into(config, &mut report);
+    for warning in &report.warnings {
+        eprintln!("{warning}");
+    }
+    for line in &report.lines {
+        println!("{line}");
+    }
+    if let Err(e) = result {
         eprintln!("Problem: {e}");
         process::exit(1);
     }
@@ -0,0 +1,293 @@
+// random maps and the things every route over them has to get right, for checking solver changes against many
+// maps at once instead of the few in the repo. everything is seeded, so a failing map can be made again from its
+// seed alone
+pub use super::Rng;
+use super::{
+    copy_graph, directed_with_nodes, edge_list, graph_with_nodes, haversine_feet, neighbors_vec,
+    DirectedRoadGraph, RoadGraph, WEIGHT_SCALE,
+};
+use graph_builder::prelude::*;
+use std::{collections::BTreeMap, ops::Range};
+
+pub struct GraphSpec {
+    pub nodes: usize,
+    // the average number of streets at an intersection that isn't a dead end, before the dead ends are hung off
+    // them. a spanning tree alone averages just under 2, and the cross streets make up the rest
+    pub mean_degree: f64,
+    // no intersection gets cross streets past this many streets
+    pub max_degree: usize,
+    // the share of nodes that are cul de sacs, each a single street off the rest of the map
+    pub culdesac_fraction: f64,
+    // streets are a whole number of feet from 1 up to this
+    pub max_feet: usize,
+}
+
+impl Default for GraphSpec {
+    fn default() -> Self {
+        GraphSpec {
+            nodes: 30,
+            mean_degree: 3.0,
+            max_degree: 4,
+            culdesac_fraction: 0.2,
+            max_feet: 1000,
+        }
+    }
+}
+
+impl GraphSpec {
+    pub fn random(rng: &mut Rng) -> Self {
+        // a spec to go with the seed, so the maps checked vary in shape as well as in the details
+        GraphSpec {
+            nodes: 2 + rng.below(30),
+            mean_degree: 2.0 + rng.below(30) as f64 / 10.0,
+            max_degree: 3 + rng.below(4),
+            culdesac_fraction: rng.below(50) as f64 / 100.0,
+            max_feet: 50 + rng.below(2000),
+        }
+    }
+}
+
+pub fn random_graph(spec: &GraphSpec, rng: &mut Rng) -> RoadGraph {
+    // always connected: the core nodes get a random spanning tree, each joining one that came before it, then
+    // cross streets between random pairs up to mean_degree. the cul de sacs come last and each hangs off a random
+    // core node, so they stay dead ends. cross streets can double up an existing street, like two roads between
+    // the same corners, but never loop back on one intersection
+    let culdesacs =
+        ((spec.nodes as f64 * spec.culdesac_fraction) as usize).min(spec.nodes.saturating_sub(1));
+    let core = spec.nodes - culdesacs;
+    let mut degree = vec![0; spec.nodes];
+    let mut edges = vec![];
+    let mut street = |u: usize, v: usize, rng: &mut Rng, degree: &mut Vec<usize>| {
+        degree[u] += 1;
+        degree[v] += 1;
+        edges.push((u, v, (1 + rng.below(spec.max_feet)) * WEIGHT_SCALE));
+    };
+    for node in 1..core {
+        let other = rng.below(node);
+        street(other, node, rng, &mut degree);
+    }
+    let wanted = (spec.mean_degree * core as f64 / 2.0) as usize;
+    let mut tries = 0;
+    while core > 1 && degree[..core].iter().sum::<usize>() / 2 < wanted && tries < 20 * wanted {
+        tries += 1;
+        let (u, v) = (rng.below(core), rng.below(core));
+        if u != v && degree[u] < spec.max_degree && degree[v] < spec.max_degree {
+            street(u.min(v), u.max(v), rng, &mut degree);
+        }
+    }
+    for node in core..spec.nodes {
+        let other = rng.below(core);
+        street(other, node, rng, &mut degree);
+    }
+    graph_with_nodes(edges, spec.nodes)
+}
+
+pub fn random_located(spec: &GraphSpec, rng: &mut Rng) -> (RoadGraph, Vec<Option<(f64, f64)>>) {
+    // random_graph's streets between intersections scattered over a square mile or so, each street from half to
+    // twice the straight line between its ends, since streets wind and maps get lengths wrong
+    let graph = random_graph(spec, rng);
+    let coords: Vec<Option<(f64, f64)>> = (0..spec.nodes)
+        .map(|_| {
+            let (lat, lon) = (rng.below(10_000) as f64, rng.below(10_000) as f64);
+            Some((40.0 + lat / 1_000_000.0, -75.0 + lon / 1_000_000.0))
+        })
+        .collect();
+    let edges = edge_list(&graph)
+        .into_iter()
+        .map(|(u, v, _)| {
+            let straight = haversine_feet(coords[u].unwrap(), coords[v].unwrap());
+            let stretch = 0.5 + rng.below(150) as f64 / 100.0;
+            (
+                u,
+                v,
+                1 + (straight * stretch * WEIGHT_SCALE as f64) as usize,
+            )
+        })
+        .collect();
+    (graph_with_nodes(edges, spec.nodes), coords)
+}
+
+pub struct MixedMap {
+    // where the walk can go, with each two-way street an arc each way, like build_directed_graph reads a map
+    pub travel: DirectedRoadGraph,
+    // the two-way streets, like two_way_streets, and the one-way ones as arcs the way they go
+    pub two_way: BTreeMap<(usize, usize), usize>,
+    pub one_way: Vec<(usize, usize, usize)>,
+}
+
+pub fn random_mixed(spec: &GraphSpec, rng: &mut Rng) -> MixedMap {
+    // random_graph's streets, all two-way, with a few one-way loops laid over them like couplets around a block.
+    // a loop can always be walked right round, and the two-way streets reach everything, so every intersection
+    // can get to every other one legally
+    let graph = random_graph(spec, rng);
+    let mut two_way: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    let mut arcs = vec![];
+    for (u, v, weight) in edge_list(&graph) {
+        if u != v {
+            *two_way.entry((u, v)).or_insert(0) += 1;
+        }
+        arcs.push((u, v, weight));
+        arcs.push((v, u, weight));
+    }
+    let mut one_way = vec![];
+    for _ in 0..rng.below(4) {
+        let mut stops: Vec<usize> = (0..2 + rng.below(4))
+            .map(|_| rng.below(spec.nodes))
+            .collect();
+        stops.dedup();
+        if stops.len() < 2 || stops.first() == stops.last() {
+            continue;
+        }
+        for hop in 0..stops.len() {
+            let (u, v) = (stops[hop], stops[(hop + 1) % stops.len()]);
+            one_way.push((u, v, (1 + rng.below(spec.max_feet)) * WEIGHT_SCALE));
+        }
+    }
+    arcs.extend(one_way.iter().copied());
+    MixedMap {
+        travel: directed_with_nodes(arcs, spec.nodes),
+        two_way,
+        one_way,
+    }
+}
+
+pub fn check_mixed_route(map: &MixedMap, path: &[usize]) -> Result<(), String> {
+    // every hop of path has to go the right way along a street, and the walks between each two intersections have
+    // to add up to the streets there: as many the way each one-way street goes, and every two-way street either way
+    let mut walked: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (hop, pair) in path.windows(2).enumerate() {
+        let (u, v) = (pair[0], pair[1]);
+        let legal = map
+            .travel
+            .out_neighbors_with_values(u)
+            .any(|arc| arc.target == v);
+        if !legal {
+            return Err(format!(
+                "hop {hop} goes from {u} to {v}, and no street goes that way"
+            ));
+        }
+        *walked.entry((u, v)).or_insert(0) += 1;
+    }
+    let mut needed: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (u, v, _) in &map.one_way {
+        *needed.entry((*u, *v)).or_insert(0) += 1;
+    }
+    for ((u, v), one_way) in &needed {
+        let times = walked.get(&(*u, *v)).copied().unwrap_or(0);
+        if times < *one_way {
+            return Err(format!(
+                "the one-way street from {u} to {v} is walked {times} times, and the map has {one_way} of it"
+            ));
+        }
+    }
+    for ((u, v), count) in &map.two_way {
+        let times = |from: usize, to: usize| walked.get(&(from, to)).copied().unwrap_or(0);
+        let one_way = |from: usize, to: usize| needed.get(&(from, to)).copied().unwrap_or(0);
+        let spare = times(*u, *v) - one_way(*u, *v) + times(*v, *u) - one_way(*v, *u);
+        if spare < *count {
+            return Err(format!(
+                "the two-way street from {u} to {v} is walked {spare} times, and the map has {count} of it"
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn copy(graph: &RoadGraph) -> RoadGraph {
+    // routing adds its repeats to the graph it's handed, and the checks need the map as it was
+    copy_graph(graph)
+}
+
+pub fn with_streets(graph: &RoadGraph, streets: &[(usize, usize, usize)]) -> RoadGraph {
+    // the map with more streets added, whose ends can be new nodes past the end of it, like connectors out to a
+    // depot off the turf
+    let nodes = streets
+        .iter()
+        .map(|(u, v, _)| u.max(v) + 1)
+        .fold(graph.node_count(), usize::max);
+    let mut edges = edge_list(graph);
+    edges.extend(streets.iter().copied());
+    graph_with_nodes(edges, nodes)
+}
+
+pub fn reordered(graph: &RoadGraph, rng: &mut Rng) -> RoadGraph {
+    // the same map with its streets listed in another order, each one maybe from its other end, which shouldn't
+    // change the route at all
+    let mut edges: Vec<(usize, usize, usize)> = edge_list(graph)
+        .into_iter()
+        .map(|(u, v, weight)| match rng.below(2) {
+            0 => (u, v, weight),
+            _ => (v, u, weight),
+        })
+        .collect();
+    rng.shuffle(&mut edges);
+    graph_with_nodes(edges, graph.node_count())
+}
+
+pub fn check_walk(graph: &RoadGraph, path: &[usize]) -> Result<(), String> {
+    // every hop of path has to be along a street of graph
+    for (hop, pair) in path.windows(2).enumerate() {
+        let (u, v) = (pair[0], pair[1]);
+        if u >= graph.node_count() || !neighbors_vec(graph, u).iter().any(|(node, _)| *node == v) {
+            return Err(format!(
+                "hop {hop} goes from {u} to {v}, and there's no street there"
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn check_covers(graph: &RoadGraph, path: &[usize]) -> Result<(), String> {
+    // path has to walk every street of graph at least once, and a street graph has two of at least twice
+    let mut walked: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for pair in path.windows(2) {
+        *walked
+            .entry((pair[0].min(pair[1]), pair[0].max(pair[1])))
+            .or_insert(0) += 1;
+    }
+    let mut needed: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (u, v, _) in edge_list(graph) {
+        *needed.entry((u, v)).or_insert(0) += 1;
+    }
+    for ((u, v), count) in needed {
+        let times = walked.get(&(u, v)).copied().unwrap_or(0);
+        if times < count {
+            return Err(format!(
+                "the street from {u} to {v} is walked {times} times, and the map has {count} of it"
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn check_closed(path: &[usize]) -> Result<(), String> {
+    // a circuit ends back where it started
+    match (path.first(), path.last()) {
+        (Some(first), Some(last)) if first != last => {
+            Err(format!("the route starts at {first} and ends at {last}"))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn check_route(graph: &RoadGraph, path: &[usize], closed: bool) -> Result<(), String> {
+    // everything a route over graph has to get right. graph is the map as it was before routing added anything
+    check_walk(graph, path)?;
+    check_covers(graph, path)?;
+    if closed {
+        check_closed(path)?;
+    }
+    Ok(())
+}
+
+pub fn check_seeds(
+    seeds: Range<u64>,
+    property: impl Fn(&mut Rng) -> Result<(), String>,
+) -> Result<(), String> {
+    // runs property once per seed, each with its own Rng, and stops at the first seed it fails for. the error
+    // says which seed it was so the case can be run again on its own
+    for seed in seeds {
+        property(&mut Rng::new(seed)).map_err(|err| format!("seed {seed}: {err}"))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::process::{Command, Stdio};
+
+// runs the built binary the way a user would, for the behavior that lives in main rather than the library
+
+#[test]
+fn closed_stdin_without_a_file_is_reported() {
+    // no file on the command line, so main asks for one, and stdin is already at its end
+    let output = Command::new(env!("CARGO_BIN_EXE_pacsam_optimization"))
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no input file provided"), "{stderr}");
+}
+
+#[test]
+fn json_warnings_are_one_object_per_line() {
+    // a map with a street looping back on node 0 and a zero length street 0-1
+    let map = std::env::temp_dir().join(format!("pacsam-warnings-{}.csv", std::process::id()));
+    std::fs::write(&map, "0:1,1:0,2:1\n2:1\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_pacsam_optimization"))
+        .arg(&map)
+        .arg("--warnings-json")
+        .output()
+        .unwrap();
+    std::fs::remove_file(&map).unwrap();
+    assert!(output.status.success());
+    // the route stays on stdout, and every line on stderr is a warning object led by its kind
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("A -- "), "{stdout}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let kinds: Vec<&str> = stderr
+        .lines()
+        .map(|line| {
+            let fields = line
+                .strip_prefix("{\"kind\":\"")
+                .and_then(|line| line.strip_suffix('}'))
+                .unwrap_or_else(|| panic!("not a warning object: {line}"));
+            fields.split('"').next().unwrap_or_default()
+        })
+        .collect();
+    assert_eq!(kinds, vec!["SelfLoop", "ZeroWeightEdge"]);
+}
+
+#[test]
+fn per_component_json_has_a_route_for_each_piece() {
+    // two triangles with nothing between them
+    let map = std::env::temp_dir().join(format!("pacsam-components-{}.csv", std::process::id()));
+    std::fs::write(&map, "1:100,2:100\n2:100\n\n4:100,5:100\n5:100\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_pacsam_optimization"))
+        .arg(&map)
+        .args(["--per-component", "--format", "json"])
+        .output()
+        .unwrap();
+    std::fs::remove_file(&map).unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("{\"components\":[{\"path\":[0,2,1,0],"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("},{\"path\":[3,5,4,3],"), "{stdout}");
+    assert_eq!(stdout.matches("\"total_feet\":300,").count(), 2, "{stdout}");
+}
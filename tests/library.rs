@@ -0,0 +1,64 @@
+use pacsam_optimization::{
+    alphabetize, build_graph, eulerize, find_cycle, fix_culdesacs, is_euler_circuit, solve,
+    total_feet, BoundaryPolicy, PacsamError, RoadGraph, Solver,
+};
+
+// the crate as a downstream library sees it: a map as a &str in, a lettered circuit out, without naming
+// graph_builder's types
+
+#[test]
+fn the_pipeline_runs_through_the_public_api() {
+    // a triangle A-B-C with a dead end off A to D
+    let graph: RoadGraph = build_graph("1:100,2:100,3:50\n2:100\n".to_string()).unwrap();
+    fix_culdesacs(&graph, &BoundaryPolicy::TreatAsCuldesac);
+    let report = eulerize(&graph);
+    assert_eq!(report.duplicated_edge_count, 0);
+    let circuit = find_cycle(&graph).unwrap();
+    assert!(is_euler_circuit(&circuit, &graph));
+    assert_eq!(alphabetize(&circuit), "A -- D -- A -- C -- B -- A");
+}
+
+#[test]
+fn the_google_earth_workflow_runs_end_to_end() {
+    // the lettered neighborhood the crate ships with, as the edge list exported from Google Earth
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/neighborhood.csv");
+    let graph: RoadGraph = build_graph(std::fs::read_to_string(fixture).unwrap()).unwrap();
+    fix_culdesacs(&graph, &BoundaryPolicy::TreatAsCuldesac);
+    eulerize(&graph);
+    let circuit = find_cycle(&graph).unwrap();
+    assert!(is_euler_circuit(&circuit, &graph));
+    let lettered = alphabetize(&circuit);
+    assert!(
+        lettered.starts_with("A -- ") && lettered.ends_with(" -- A"),
+        "{lettered}"
+    );
+    // every street the eulerized graph holds is driven once, so the circuit is as long as the graph
+    let miles = (total_feet(&graph) / 5280.0 * 100.0).trunc() / 100.0;
+    assert_eq!(miles, 3.13);
+    assert_eq!(solve(fixture).unwrap().total_miles, miles);
+    // the route the command prints for the fixture, pinned so a change to any stage shows up here
+    assert_eq!(
+        lettered,
+        "A -- R -- S -- L -- T -- U -- V -- U -- W -- U -- T -- S -- R -- Q -- M -- N -- O -- N -- P -- B -- P -- Q -- \
+         M -- L -- J -- K -- J -- H -- I -- H -- F -- G -- E -- D -- F -- H -- C -- D -- C -- B -- A"
+    );
+}
+
+#[test]
+fn solve_routes_one_way_maps_like_the_route_command() {
+    // a one-way loop A->B->C->A with a street off A to D. one-way maps walk optional streets like any other, so
+    // D gets walked out and back after the loop
+    let map = "1:100:>,3:50*\n2:100:>\n0:100:>\n";
+    let file = std::env::temp_dir().join(format!("pacsam-one-way-{}.csv", std::process::id()));
+    std::fs::write(&file, map).unwrap();
+    let route = solve(file.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&file).unwrap();
+    assert_eq!(route.path, [0, 1, 2, 0, 3, 0]);
+    assert_eq!(route.duplicated_edge_count, 1);
+    assert_eq!(route.total_feet, 400.0);
+    // Solver only holds two-way streets, so it says so rather than routing the loop both ways
+    assert!(matches!(
+        Solver::from_contents(map),
+        Err(PacsamError::Unsupported("one-way streets"))
+    ));
+}
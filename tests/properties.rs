@@ -0,0 +1,548 @@
+use graph_builder::prelude::*;
+use pacsam_optimization::testgen::{
+    check_closed, check_covers, check_mixed_route, check_route, check_seeds, check_walk, copy,
+    random_graph, random_located, random_mixed, reordered, with_streets, GraphSpec,
+};
+use pacsam_optimization::{
+    approach_legs, audit_walk, balance_directed, balance_directed_over, connector_edges,
+    diff_routes, eulerize, eulerize_from_depot, eulerize_located, eulerize_open, find_cycle_from,
+    find_directed_cycle, find_trail, hop_doors, hop_weights, lower_bound, orient_mixed,
+    read_edge_list, reorder_route, route_visits, Connector, DirectedRoadGraph, EdgeCost,
+    LengthCost, ObjectiveKind, RoadGraph, Solver, Unit, WEIGHT_SCALE,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+// each property runs against a few hundred random maps of random shapes. a failure names the seed, and the same
+// seed makes the same map again
+
+#[test]
+fn circuits_cover_every_street() {
+    check_seeds(0..200, |rng| {
+        let spec = GraphSpec::random(rng);
+        let graph = random_graph(&spec, rng);
+        let route = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        check_route(&graph, &route.path, true)
+    })
+    .unwrap();
+}
+
+#[test]
+fn every_door_is_reached_once() {
+    check_seeds(0..200, |rng| {
+        let graph = random_graph(&GraphSpec::random(rng), rng);
+        let mut doors = BTreeMap::new();
+        for u in 0..graph.node_count() {
+            for v in graph.neighbors_with_values(u).map(|arc| arc.target) {
+                if u < v {
+                    *doors.entry((u, v)).or_insert(0) += rng.below(20);
+                }
+            }
+        }
+        let route = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        let reached: usize = hop_doors(&route.path, &doors).iter().sum();
+        let expected: usize = doors.values().sum();
+        match reached == expected {
+            true => Ok(()),
+            false => Err(format!("the route reaches {reached} doors of {expected}")),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn edge_lists_read_the_same_around_comments() {
+    check_seeds(0..200, |rng| {
+        let graph = random_graph(&GraphSpec::random(rng), rng);
+        let mut streets = vec![];
+        for u in 0..graph.node_count() {
+            for arc in graph.neighbors_with_values(u).filter(|arc| u < arc.target) {
+                streets.push((u, arc.target, arc.value));
+            }
+        }
+        let mut text = String::new();
+        for (u, v, weight) in &streets {
+            match rng.below(4) {
+                0 => text.push_str("# a comment\n\n"),
+                1 => text.push_str("   \n"),
+                _ => {}
+            }
+            let feet = *weight as f64 / WEIGHT_SCALE as f64;
+            match rng.below(3) {
+                0 => text.push_str(&format!("{u} {v} {feet}  # {u} to {v}\n")),
+                1 => text.push_str(&format!("{u},{v},{feet},street {u}\t\n")),
+                _ => text.push_str(&format!("{u}\t{v} {feet}   \n")),
+            }
+        }
+        let read = read_edge_list(text.as_bytes())
+            .map_err(|err| err.to_string())?
+            .graph;
+        let total = |graph: &RoadGraph| -> usize {
+            (0..graph.node_count())
+                .flat_map(|u| graph.neighbors_with_values(u).map(|arc| arc.value))
+                .sum()
+        };
+        match (read.edge_count(), total(&read)) == (streets.len(), total(&graph)) {
+            true => Ok(()),
+            false => Err(format!(
+                "read {} streets of {} back from\n{text}",
+                read.edge_count(),
+                streets.len()
+            )),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn audits_pass_the_routes_they_check() {
+    check_seeds(0..200, |rng| {
+        let graph = random_graph(&GraphSpec::random(rng), rng);
+        let route = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        let audit = audit_walk(&graph, &BTreeSet::new(), &route.path);
+        if !audit.missed.is_empty() || !audit.jumps.is_empty() {
+            return Err(format!(
+                "the route misses {} streets and jumps {} times",
+                audit.missed.len(),
+                audit.jumps.len()
+            ));
+        }
+        match audit.extra_feet() {
+            Some(extra) if extra.abs() < 1e-6 => Ok(()),
+            extra => Err(format!("the route is {extra:?} ft off the best one")),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn depot_routes_cover_the_turf_and_come_back() {
+    // a parking lot off the map with a connector or two out to it. the route leaves from the lot, walks every
+    // street of the turf, and the walk out is at least the shortest connector
+    check_seeds(0..200, |rng| {
+        let graph = random_graph(&GraphSpec::random(rng), rng);
+        let connectors: Vec<Connector> = (0..1 + rng.below(2))
+            .map(|_| Connector {
+                from: "Lot".to_string(),
+                to: rng.below(graph.node_count()).to_string(),
+                length: (1 + rng.below(2000)) * WEIGHT_SCALE,
+            })
+            .collect();
+        let added = connector_edges(
+            graph.node_count(),
+            &connectors,
+            &Unit::Feet,
+            &mut BTreeMap::new(),
+        );
+        let depot = graph.node_count();
+        let optional: BTreeSet<(usize, usize)> = added.iter().map(|(u, v, _)| (*u, *v)).collect();
+        let shortest = added.iter().map(|(_, _, weight)| *weight).min().unwrap();
+        let map = with_streets(&graph, &added);
+        let route = eulerize_from_depot(&map, &optional, depot).map_err(|err| err.to_string())?;
+        let path = find_cycle_from(&route, depot).map_err(|err| err.to_string())?;
+        check_walk(&map, &path)?;
+        check_covers(&graph, &path)?;
+        check_closed(&path)?;
+        if path[0] != depot {
+            return Err(format!("the route starts at {} rather than the depot", path[0]));
+        }
+        let (out, back) = approach_legs(&path, &hop_weights(&path, &route), &optional);
+        let shortest = shortest as f64 / WEIGHT_SCALE as f64;
+        match out >= shortest && back >= shortest {
+            true => Ok(()),
+            false => Err(format!(
+                "the walk out is {out} ft and back {back} ft, and the shortest connector is {shortest} ft"
+            )),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn open_routes_run_between_their_ends() {
+    check_seeds(0..200, |rng| {
+        let spec = GraphSpec::random(rng);
+        let graph = random_graph(&spec, rng);
+        let (start, end) = (rng.below(spec.nodes), rng.below(spec.nodes));
+        let route = copy(&graph);
+        eulerize_open(&route, start, end);
+        let path = find_trail(&route, start, end).map_err(|err| err.to_string())?;
+        check_route(&graph, &path, start == end)?;
+        match (path.first(), path.last()) {
+            (Some(first), Some(last)) if (*first, *last) != (start, end) => Err(format!(
+                "asked for {start} to {end}, and the route runs from {first} to {last}"
+            )),
+            _ => Ok(()),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn routes_are_no_shorter_than_the_lower_bound() {
+    check_seeds(0..200, |rng| {
+        let spec = GraphSpec::random(rng);
+        let graph = random_graph(&spec, rng);
+        let (bound, _) = lower_bound(&graph);
+        let route = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        match route.total_feet + 1e-6 < bound {
+            true => Err(format!(
+                "the route is {} ft, under the lower bound of {bound} ft",
+                route.total_feet
+            )),
+            false => Ok(()),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn cul_de_sac_heavy_maps_still_route() {
+    check_seeds(0..100, |rng| {
+        let spec = GraphSpec {
+            nodes: 10 + rng.below(30),
+            culdesac_fraction: 0.6,
+            ..GraphSpec::default()
+        };
+        let graph = random_graph(&spec, rng);
+        let route = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        check_route(&graph, &route.path, true)
+    })
+    .unwrap();
+}
+
+#[test]
+fn counting_repeats_trades_distance_for_fewer_streets() {
+    // the maps stay small enough that the matching is exact, since the greedy one makes no promises either way
+    check_seeds(0..200, |rng| {
+        let spec = GraphSpec {
+            nodes: 2 + rng.below(19),
+            ..GraphSpec::random(rng)
+        };
+        let graph = random_graph(&spec, rng);
+        let shortest = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        let fewest = Solver::from_graph(copy(&graph))
+            .with_objective(ObjectiveKind::MinRepeatedEdges)
+            .route()
+            .map_err(|err| err.to_string())?;
+        check_route(&graph, &fewest.path, true)?;
+        if fewest.duplicated_edge_count > shortest.duplicated_edge_count {
+            return Err(format!(
+                "counting repeats walks {} streets twice, and going by distance only {}",
+                fewest.duplicated_edge_count, shortest.duplicated_edge_count
+            ));
+        }
+        match fewest.total_feet + 1e-6 < shortest.total_feet {
+            true => Err(format!(
+                "counting repeats is {} ft, shorter than going by distance at {} ft",
+                fewest.total_feet, shortest.total_feet
+            )),
+            false => Ok(()),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn located_shortest_paths_add_as_little_as_all_pairs() {
+    check_seeds(0..200, |rng| {
+        let (graph, coords) = random_located(&GraphSpec::random(rng), rng);
+        let (all_pairs, located) = (copy(&graph), copy(&graph));
+        let expected = eulerize(&all_pairs).duplicated_feet;
+        let found = eulerize_located(&located, &coords).duplicated_feet;
+        match (found - expected).abs() > 1e-6 {
+            true => Err(format!(
+                "a* between the odd corners adds {found} ft, and all pairs shortest paths {expected} ft"
+            )),
+            false => Ok(()),
+        }
+    })
+    .unwrap();
+}
+
+// a cost of the caller's own, which only counts streets and so walks the fewest again, like --objective repeats
+struct EveryStreetAlike;
+
+impl EdgeCost for EveryStreetAlike {
+    fn cost(&self, _: usize, _: usize, _: usize) -> usize {
+        WEIGHT_SCALE
+    }
+}
+
+#[test]
+fn costed_routes_cover_every_street_and_measure_in_feet() {
+    // weighing streets by their length is what the solver does anyway, and any other cost still walks every
+    // street, with the route measured in feet either way
+    check_seeds(0..200, |rng| {
+        let graph = random_graph(&GraphSpec::random(rng), rng);
+        let by_length = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        let costed = Solver::from_graph(copy(&graph))
+            .with_cost(LengthCost)
+            .route()
+            .map_err(|err| err.to_string())?;
+        if (by_length.total_feet - costed.total_feet).abs() > 1e-6 {
+            return Err(format!(
+                "by length the route is {} ft, and costed by length {} ft",
+                by_length.total_feet, costed.total_feet
+            ));
+        }
+        let counted = Solver::from_graph(copy(&graph))
+            .with_cost(EveryStreetAlike)
+            .route()
+            .map_err(|err| err.to_string())?;
+        check_route(&graph, &counted.path, true)?;
+        let measured: usize = counted.edges.iter().map(|(_, _, weight)| weight).sum();
+        match (measured as f64 / WEIGHT_SCALE as f64 - counted.total_feet).abs() < 1e-6
+            && counted.total_feet >= by_length.total_feet - 1e-6
+        {
+            true => Ok(()),
+            false => Err(format!(
+                "counting streets the route is {} ft, its streets add up to {measured}, and by length it's {} ft",
+                counted.total_feet, by_length.total_feet
+            )),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn routes_dont_depend_on_the_order_streets_are_listed() {
+    check_seeds(0..200, |rng| {
+        let spec = GraphSpec::random(rng);
+        let graph = random_graph(&spec, rng);
+        let listed = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        let relisted = Solver::from_graph(reordered(&graph, rng))
+            .route()
+            .map_err(|err| err.to_string())?;
+        match listed.path == relisted.path {
+            true => Ok(()),
+            false => Err(format!(
+                "listing the streets in another order changed the route from {:?} to {:?}",
+                listed.path, relisted.path
+            )),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn diffs_turn_one_route_into_the_other() {
+    // two seeds give two routes over the same map. putting each changed stretch of the diff in place of the first
+    // route's stops makes the second, and the lengths are the routes' own
+    check_seeds(0..200, |rng| {
+        let graph = random_graph(&GraphSpec::random(rng), rng);
+        let route = |seed: u64| {
+            Solver::from_graph(copy(&graph))
+                .with_seed(seed)
+                .route()
+                .map_err(|err| err.to_string())
+        };
+        let (before, after) = (route(rng.next_u64())?, route(rng.next_u64())?);
+        let diff = diff_routes(&graph, &before.path, &after.path);
+        let mut rebuilt: Vec<usize> = vec![];
+        let mut at = 0;
+        for change in &diff.changes {
+            rebuilt.extend(&before.path[at..change.before_at]);
+            if rebuilt.len() != change.after_at {
+                return Err(format!(
+                    "a change is at stop {} of the second route, and {} stops come before it",
+                    change.after_at,
+                    rebuilt.len()
+                ));
+            }
+            rebuilt.extend(&change.after);
+            at = change.before_at + change.before.len();
+        }
+        rebuilt.extend(&before.path[at..]);
+        if rebuilt != after.path {
+            return Err(format!(
+                "the diff of {:?} and {:?} rebuilds {rebuilt:?}",
+                before.path, after.path
+            ));
+        }
+        let off = (diff.before_feet - before.total_feet).abs()
+            + (diff.after_feet - after.total_feet).abs();
+        match off < 1e-6
+            && diff_routes(&graph, &before.path, &before.path)
+                .changes
+                .is_empty()
+        {
+            true => Ok(()),
+            false => Err(format!("the diff's lengths are {off} ft off the routes'")),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn heatmaps_count_every_pass() {
+    // a circuit's passes and street walks add up to its length, and no intersection is passed fewer times than
+    // its streets need, so nothing comes out with negative extra passes hidden
+    check_seeds(0..200, |rng| {
+        let graph = random_graph(&GraphSpec::random(rng), rng);
+        let route = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        let visits = route_visits(&route.path, &graph);
+        let hops = route.path.len().saturating_sub(1);
+        let passes: usize = visits.nodes.values().sum();
+        let walks: usize = visits.streets.values().sum();
+        if passes != hops || walks != hops {
+            return Err(format!(
+                "{hops} hops make {passes} passes and {walks} street walks"
+            ));
+        }
+        match (0..graph.node_count())
+            .find(|node| visits.nodes.get(node).copied().unwrap_or(0) < visits.needed[*node])
+        {
+            Some(node) => Err(format!(
+                "node {node} is passed {:?} times and needs {}",
+                visits.nodes.get(&node),
+                visits.needed[node]
+            )),
+            None => Ok(()),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn reordered_routes_keep_their_pins_together() {
+    // pins cut from the route itself can always be kept. the reworked route still walks every street, as long as
+    // before, with each pin in one piece the way it was given or, if it says so, the other way round
+    check_seeds(0..200, |rng| {
+        let graph = random_graph(&GraphSpec::random(rng), rng);
+        let route = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        let unpinned = reorder_route(&route.path, &[]).map_err(|err| err.to_string())?;
+        if unpinned.path != route.path {
+            return Err(format!(
+                "with no pins {:?} became {:?}",
+                route.path, unpinned.path
+            ));
+        }
+        // a few stretches that don't overlap, cut from between random stops
+        let mut cuts: Vec<usize> = (0..2 + rng.below(6))
+            .map(|_| rng.below(route.path.len()))
+            .collect();
+        cuts.sort();
+        cuts.dedup();
+        let pins: Vec<Vec<usize>> = cuts
+            .chunks(2)
+            .filter(|pair| pair.len() == 2)
+            .map(|pair| route.path[pair[0]..=pair[1]].to_vec())
+            .collect();
+        let reordered = reorder_route(&route.path, &pins).map_err(|err| err.to_string())?;
+        check_route(&graph, &reordered.path, true)?;
+        if reordered.path.len() != route.path.len() {
+            return Err(format!(
+                "{:?} became {:?}, which is a different length",
+                route.path, reordered.path
+            ));
+        }
+        for (index, pin) in pins.iter().enumerate() {
+            let pin: Vec<usize> = match reordered.reversed.contains(&index) {
+                true => pin.iter().rev().copied().collect(),
+                false => pin.clone(),
+            };
+            if !reordered
+                .path
+                .windows(pin.len())
+                .any(|stretch| stretch == pin)
+            {
+                return Err(format!(
+                    "{:?} doesn't walk pin {pin:?} in one go",
+                    reordered.path
+                ));
+            }
+        }
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn seeded_routes_repeat_and_stay_as_short() {
+    check_seeds(0..200, |rng| {
+        let spec = GraphSpec::random(rng);
+        let graph = random_graph(&spec, rng);
+        let seed = rng.next_u64();
+        let unseeded = Solver::from_graph(copy(&graph))
+            .route()
+            .map_err(|err| err.to_string())?;
+        let first = Solver::from_graph(copy(&graph))
+            .with_seed(seed)
+            .route()
+            .map_err(|err| err.to_string())?;
+        let again = Solver::from_graph(reordered(&graph, rng))
+            .with_seed(seed)
+            .route()
+            .map_err(|err| err.to_string())?;
+        check_route(&graph, &first.path, true)?;
+        if first.path != again.path {
+            return Err(format!(
+                "seed {seed} gave {:?} and then {:?}",
+                first.path, again.path
+            ));
+        }
+        // the exact matching is only exact up to 20 odd nodes, and past that a seed can land on another pairing
+        match spec.nodes > 20 || (first.total_feet - unseeded.total_feet).abs() < 1e-6 {
+            true => Ok(()),
+            false => Err(format!(
+                "seed {seed} made the route {} ft, and without one it's {} ft",
+                first.total_feet, unseeded.total_feet
+            )),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn mixed_maps_walk_every_street_legally() {
+    check_seeds(0..200, |rng| {
+        let spec = GraphSpec {
+            nodes: 2 + rng.below(20),
+            ..GraphSpec::random(rng)
+        };
+        let map = random_mixed(&spec, rng);
+        let walk = orient_mixed(&map.travel, &map.two_way);
+        balance_directed_over(&walk, &map.travel);
+        let path = find_directed_cycle(&walk, 0).map_err(|err| err.to_string())?;
+        check_mixed_route(&map, &path)?;
+        // walking every two-way street once each way is always possible, so turning them one way can't do worse
+        let both_ways = map.travel;
+        balance_directed(&both_ways);
+        match arc_feet(&walk) <= arc_feet(&both_ways) {
+            true => Ok(()),
+            false => Err(format!(
+                "orienting the two-way streets walks {} ft, more than the {} ft of walking them both ways",
+                arc_feet(&walk),
+                arc_feet(&both_ways)
+            )),
+        }
+    })
+    .unwrap();
+}
+
+fn arc_feet(graph: &DirectedRoadGraph) -> usize {
+    (0..graph.node_count())
+        .flat_map(|node| graph.out_neighbors_with_values(node).map(|arc| arc.value))
+        .sum::<usize>()
+        / WEIGHT_SCALE
+}